@@ -1,26 +1,61 @@
-extern crate rand;
-
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashMap;
 use std::env;
-use std::fmt::Debug;
-use std::iter::FromIterator;
-use self::rand::{thread_rng, seq, Rng};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::iter::{self, FromIterator};
+use std::ops::{Add, Index, Range};
+use std::sync::Arc;
+use std::{slice, vec};
+use rand::distributions::Distribution;
+use rand::{seq, Rng};
+
+use budget::Budget;
+use engine::Settings;
+use result::SimulationResult;
+use rng::{RngKind, SimRng};
+use view::BagView;
 
 const MAX_SIMS: u32 = 100_000;
 
+/// How many items the `Debug` output of a `Bag` shows before eliding the rest.
+const DEBUG_ITEMS: usize = 5;
+
 /// The `Bag` struct. The main collection type for holding populations of things.
-pub struct Bag<T: Clone> {
-    pub items: Vec<T>,
-    pub max_sims: u32
+///
+/// Items don't need to be `Clone`: simulations only ever borrow them, so bags can hold
+/// unclonable things such as `Mutex`-guarded handles.
+///
+/// The items are stored behind an `Arc`, so cloning a bag (say, to try a different configuration)
+/// shares the population instead of copying it. Methods that modify the items copy the population
+/// first if it's shared with other bags, which does require `T: Clone`.
+///
+/// Two bags are equal when they hold the same items, regardless of order or configuration.
+///
+/// A `Bag` only stores its configuration (such as a seed) and creates fresh RNG state for
+/// every simulation run, so `Bag<T>` is `Send + Sync` whenever `T` is.
+pub struct Bag<T> {
+    pub items: Arc<Vec<T>>,
+    pub max_sims: u32,
+    /// How much to simulate, overriding `max_sims` when set, see [`set_budget`](#method.set_budget).
+    pub budget: Option<Budget>,
+    pub seed: Option<u64>,
+    pub rng_kind: RngKind,
+    pub early_stopping: bool,
+    /// Whether detailed results hold their running estimate, see
+    /// [`set_track_convergence`](#method.set_track_convergence).
+    pub track_convergence: bool,
+    pub threads: usize
 }
 
-fn get_default_max_sims() -> u32 {
+pub(crate) fn get_default_max_sims() -> u32 {
     match env::var("MENDEL_MAX_SIMS") {
         Ok(val) => val.parse::<u32>().unwrap(),
         Err(_) => MAX_SIMS
     }
 }
 
-impl<T: Clone> Bag<T> {
+impl<T> Bag<T> {
 
     /// Constructs a new `Bag<T>` from range.
     ///
@@ -37,9 +72,76 @@ impl<T: Clone> Bag<T> {
     /// ```
     pub fn from_range(min: i32, max: i32) -> Self where
         Vec<T>: FromIterator<i32> {
-        // TODO: Add shuffle option
         let items: Vec<T> = (min..max).collect();
-        Bag { items, max_sims: get_default_max_sims() }
+        Bag::from_vec(items)
+    }
+
+    /// Constructs a new `Bag<T>` from range, with the items' order shuffled by `rng`.
+    ///
+    /// [`min`, `max`)
+    ///
+    /// # Examples
+    ///
+    /// Generate a reproducibly shuffled `Bag<i32>` with numbers 1 through 10:
+    ///
+    /// ```
+    /// extern crate rand;
+    /// extern crate mendel;
+    ///
+    /// use rand::{SeedableRng, StdRng};
+    /// use mendel::Bag;
+    ///
+    /// # fn main() {
+    /// let mut rng = StdRng::from_seed([7; 32]);
+    /// let my_number_bag: Bag<i32> = Bag::from_range_shuffled(1, 11, &mut rng);
+    /// assert_eq!(my_number_bag.items.len(), 10);
+    /// # }
+    /// ```
+    pub fn from_range_shuffled<R: Rng>(min: i32, max: i32, rng: &mut R) -> Self where
+        Vec<T>: FromIterator<i32> {
+        let mut items: Vec<T> = (min..max).collect();
+        rng.shuffle(&mut items);
+        Bag::from_vec(items)
+    }
+
+    /// Constructs a new `Bag<T>` of the dates from `start`, stepping by `step` while they're before `end`.
+    ///
+    /// [`start`, `end`)
+    ///
+    /// Any type that can be stepped by adding to it works: chrono's `NaiveDate` and `DateTime` with a
+    /// `Duration` step such as `Duration::days(1)`, or `SystemTime` with a `std::time::Duration`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` doesn't move `start` forward, when `start` is before `end`.
+    ///
+    /// # Examples
+    ///
+    /// Odds a random day in 2024 is a weekend, with days counted from the Thursday of 1 January 1970:
+    ///
+    /// ```
+    /// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    /// use mendel::Bag;
+    ///
+    /// let day = Duration::from_secs(24 * 60 * 60);
+    /// let days = Bag::from_date_range(UNIX_EPOCH + day * 19_723, UNIX_EPOCH + day * 20_089, day);
+    /// assert_eq!(days.items.len(), 366);
+    /// let weekday = |t: &SystemTime| (t.duration_since(UNIX_EPOCH).unwrap().as_secs() / 86_400 + 4) % 7;
+    /// let odds_of_weekend = days.one(|t| weekday(t) == 0 || weekday(t) == 6);
+    /// assert!(0.27 < odds_of_weekend && odds_of_weekend < 0.30);
+    /// ```
+    pub fn from_date_range<S>(start: T, end: T, step: S) -> Self where
+        T: Clone + PartialOrd + Add<S, Output = T>,
+        S: Clone {
+        let mut items = Vec::new();
+        let mut date = start;
+        while date < end {
+            let next = date.clone() + step.clone();
+            assert!(next > date, "step must move the date forward");
+            items.push(date);
+            date = next;
+        }
+        Bag::from_vec(items)
     }
 
     /// Constructs a new `Bag<T>` from a vector of items.
@@ -54,9 +156,101 @@ impl<T: Clone> Bag<T> {
     /// let animals = vec!["spider", "fish", "tiger", "pigeon"];
     /// let animal_bag = Bag::from_vec(animals);
     /// ```
-    pub fn from_vec(v: Vec<T>) -> Self {
-        let items: Vec<T> = v.clone();
-        Bag { items, max_sims: get_default_max_sims() }
+    pub fn from_vec(items: Vec<T>) -> Self {
+        Bag::from_shared(Arc::new(items))
+    }
+
+    /// Constructs a new `Bag<T>` from items and how many copies of each the bag holds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let balls = Bag::from_counts(vec![("red", 5), ("blue", 3), ("green", 2)]);
+    /// assert_eq!(balls.items.len(), 10);
+    /// assert_eq!(balls.count_where(|b| *b == "blue"), 3);
+    /// ```
+    pub fn from_counts<I>(counts: I) -> Self where
+        T: Clone,
+        I: IntoIterator<Item = (T, usize)> {
+        let mut items = Vec::new();
+        for (item, count) in counts {
+            items.extend(iter::repeat(item).take(count));
+        }
+        Bag::from_vec(items)
+    }
+
+    /// Constructs a new `Bag<T>` of `n` items drawn from a probability distribution, such as rand's
+    /// `Normal`, `Poisson` or `Uniform`, for questions about continuous populations.
+    ///
+    /// The items are drawn from the thread RNG (or `DETERMINISTIC_SEED` with the `deterministic`
+    /// feature), see [`from_distribution_with_rng`](#method.from_distribution_with_rng) to choose the generator.
+    ///
+    /// # Examples
+    ///
+    /// Odds a standard normal draw is above 1.96:
+    ///
+    /// ```
+    /// extern crate rand;
+    /// extern crate mendel;
+    ///
+    /// use rand::distributions::Normal;
+    /// use mendel::Bag;
+    ///
+    /// # fn main() {
+    /// let heights = Bag::from_distribution(Normal::new(0.0, 1.0), 10_000);
+    /// let odds = heights.one(|h| *h > 1.96);
+    /// assert!(0.015 < odds && odds < 0.035);
+    /// # }
+    /// ```
+    pub fn from_distribution<D>(distribution: D, n: usize) -> Self where
+        D: Distribution<T> {
+        Bag::from_distribution_with_rng(distribution, n, &mut SimRng::new(None, RngKind::default()))
+    }
+
+    /// Like [`from_distribution`](#method.from_distribution), but draws the items with `rng`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate rand;
+    /// extern crate mendel;
+    ///
+    /// use rand::distributions::Poisson;
+    /// use rand::{SeedableRng, StdRng};
+    /// use mendel::Bag;
+    ///
+    /// # fn main() {
+    /// let mut rng = StdRng::from_seed([7; 32]);
+    /// let arrivals: Bag<u64> = Bag::from_distribution_with_rng(Poisson::new(3.0), 1_000, &mut rng);
+    /// assert_eq!(arrivals.items.len(), 1_000);
+    /// # }
+    /// ```
+    pub fn from_distribution_with_rng<D, R>(distribution: D, n: usize, rng: &mut R) -> Self where
+        D: Distribution<T>,
+        R: Rng {
+        Bag::from_vec(rng.sample_iter(&distribution).take(n).collect())
+    }
+
+    /// Constructs a new `Bag<T>` which shares an existing population with other bags.
+    ///
+    /// # Examples
+    ///
+    /// Two bags with different configurations over the same population:
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use mendel::Bag;
+    ///
+    /// let population = Arc::new((1..1_001).collect::<Vec<i32>>());
+    /// let quick = Bag::from_shared(Arc::clone(&population));
+    /// let mut thorough = Bag::from_shared(Arc::clone(&population));
+    /// thorough.set_max_sims(1_000_000);
+    /// assert!(Arc::ptr_eq(&quick.items, &thorough.items));
+    /// ```
+    pub fn from_shared(items: Arc<Vec<T>>) -> Self {
+        Bag { items, max_sims: get_default_max_sims(), budget: None, seed: None, rng_kind: RngKind::default(), early_stopping: false, track_convergence: false, threads: 1 }
     }
 
     /// Predicts probability of criteria being met for the first random item grabbed from the bag.
@@ -73,16 +267,9 @@ impl<T: Clone> Bag<T> {
     /// assert!(0.49 < odds_of_even && odds_of_even < 0.51);
     /// ```
     pub fn one<F>(&self, f: F) -> f64 where
-        F: Fn(&T) -> bool {
-        let mut picks_in_favor: u32 = 0;
-        for _ in 0..self.max_sims {
-            let idx = thread_rng().gen_range(0, self.items.len());
-            let item = &self.items[idx];
-            if f(item) {
-                picks_in_favor += 1;
-            }
-        }
-        picks_in_favor as f64 / MAX_SIMS as f64
+        T: Sync,
+        F: Fn(&T) -> bool + Sync {
+        self.one_detailed(f).probability()
     }
 
     /// Predicts probability of criteria being met for the first `sample_size` random items grabbed from the bag.
@@ -106,18 +293,182 @@ impl<T: Clone> Bag<T> {
     /// assert!(0.29 < odds_of_two && odds_of_two < 0.31);
     /// ```
     pub fn sample<F>(&self, sample_size: usize, f: F) -> f64 where
-        T: Debug,
-        F: Fn(Vec<&T>) -> bool {
-        let mut picks_in_favor: u32 = 0;
-        let mut rng = thread_rng();
-        let items_clone = self.items.clone();
-        for _ in 0..self.max_sims {
-            let sample = seq::sample_iter(&mut rng, &items_clone, sample_size).unwrap();
-            if f(sample) {
-                picks_in_favor += 1;
-            }
+        T: Sync,
+        F: Fn(Vec<&T>) -> bool + Sync {
+        self.sample_detailed(sample_size, f).probability()
+    }
+
+    /// Like [`one`](#method.one), but returns the full `SimulationResult` with counts and a confidence interval.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let result = my_bag.one_detailed(|v| v % 2 == 0);
+    /// assert_eq!(result.trials, my_bag.max_sims as u64);
+    /// let (low, high) = result.confidence_interval();
+    /// assert!(low < result.probability() && result.probability() < high);
+    /// ```
+    pub fn one_detailed<F>(&self, f: F) -> SimulationResult where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync {
+        let items: &[T] = &self.items;
+        self.settings().run_parallel(|rng| f(pick_one(rng, items)))
+    }
+
+    /// Like [`sample`](#method.sample), but returns the full `SimulationResult` with counts and a confidence interval.
+    ///
+    /// A sample of every item in the bag is the same every time, so the criteria is evaluated just
+    /// once and every trial counted the same way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let result = my_bag.sample_detailed(2, |values| values.iter().any(|v| **v == 1));
+    /// assert!(0.19 < result.probability() && result.probability() < 0.21);
+    /// let everything = my_bag.sample_detailed(10, |values| values.contains(&&1));
+    /// assert_eq!(everything.successes, everything.trials);
+    /// ```
+    pub fn sample_detailed<F>(&self, sample_size: usize, f: F) -> SimulationResult where
+        T: Sync,
+        F: Fn(Vec<&T>) -> bool + Sync {
+        let items: &[T] = &self.items;
+        if sample_size == items.len() {
+            // Every trial would grab the whole bag, so one evaluation is the answer of every trial
+            let met = f(items.iter().collect());
+            return self.settings().run(|_| met);
         }
-        picks_in_favor as f64 / MAX_SIMS as f64
+        self.settings().run_parallel(|rng| f(draw_sample(rng, items, sample_size)))
+    }
+
+    /// Like [`sample`](#method.sample), but the criteria is handed clones of the `sample_size` items
+    /// instead of references, so it's free to sort, modify or consume them.
+    ///
+    /// # Examples
+    ///
+    /// Odds that the middle value of 3 picks from 1 - 10 is above 5:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let odds = my_bag.sample_owned(3, |mut values| {
+    ///     values.sort();
+    ///     values[1] > 5
+    /// });
+    /// assert!(0.49 < odds && odds < 0.51);
+    /// ```
+    pub fn sample_owned<F>(&self, sample_size: usize, f: F) -> f64 where
+        T: Clone + Sync,
+        F: Fn(Vec<T>) -> bool + Sync {
+        self.sample(sample_size, |values| f(values.into_iter().cloned().collect()))
+    }
+
+    /// Randomly draws `amount` items out of the bag, removing them from the population and
+    /// returning them in the order they were drawn.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `amount` is larger than the number of items in the bag.
+    ///
+    /// # Examples
+    ///
+    /// Pick 3 raffle winners:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut tickets = Bag::from_range(1, 101);
+    /// let winners = tickets.draw(3);
+    /// assert_eq!(winners.len(), 3);
+    /// assert_eq!(tickets.items.len(), 97);
+    /// assert!(winners.iter().all(|w| !tickets.items.contains(w)));
+    /// ```
+    pub fn draw(&mut self, amount: usize) -> Vec<T> where
+        T: Clone {
+        let mut rng = self.settings().rng();
+        self.draw_and_remove(amount, &mut rng)
+    }
+
+    /// Like [`draw`](#method.draw), but draws with the given random number generator instead of the
+    /// bag's own.
+    ///
+    /// Threading one generator through several rounds makes a whole multi-round tournament or raffle
+    /// reproducible, and the bag can be simulated with between rounds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `amount` is larger than the number of items in the bag.
+    ///
+    /// # Examples
+    ///
+    /// Odds ticket 1 wins the second prize draw, once the 3 first prize winners are out:
+    ///
+    /// ```
+    /// extern crate rand;
+    /// extern crate mendel;
+    ///
+    /// use mendel::Bag;
+    /// use rand::{SeedableRng, StdRng};
+    ///
+    /// # fn main() {
+    /// let mut rng = StdRng::seed_from_u64(5);
+    /// let mut tickets = Bag::from_range(1, 101);
+    /// let first_prizes = tickets.draw_and_remove(3, &mut rng);
+    /// let odds = tickets.one(|t| *t == 1);
+    /// if first_prizes.contains(&1) {
+    ///     assert_eq!(odds, 0.0);
+    /// } else {
+    ///     assert!(0.009 < odds && odds < 0.012);
+    /// }
+    /// # }
+    /// ```
+    pub fn draw_and_remove<R: Rng>(&mut self, amount: usize, rng: &mut R) -> Vec<T> where
+        T: Clone {
+        let items = self.items_mut();
+        let indices = seq::sample_indices(rng, items.len(), amount);
+        // Remove from the back so earlier indices stay valid
+        let mut removal_order: Vec<usize> = (0..indices.len()).collect();
+        removal_order.sort_by_key(|&i| Reverse(indices[i]));
+        let mut drawn: Vec<Option<T>> = indices.iter().map(|_| None).collect();
+        for i in removal_order {
+            drawn[i] = Some(items.remove(indices[i]));
+        }
+        drawn.into_iter().map(|item| item.unwrap()).collect()
+    }
+
+    /// Forks the bag into an independent copy with the same items and configuration, such as its
+    /// seed, so two what-if branches of an analysis can start from the same point.
+    ///
+    /// Bags build a fresh random number generator from their seed for every run, so there's no
+    /// stream position to carry over: a seeded fork gets the same results as the original for the
+    /// same queries. The population is shared until either bag modifies it, like with `clone`. To
+    /// fork a chain of draws, also clone the generator given to
+    /// [`draw_and_remove`](#method.draw_and_remove), and see
+    /// [`Estimator::fork`](struct.Estimator.html#method.fork) for accumulated estimates.
+    ///
+    /// # Examples
+    ///
+    /// Two branches of a raffle, each drawing its own winners from the same starting tickets:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut tickets = Bag::from_range(1, 101);
+    /// tickets.set_seed(8);
+    /// let mut branch = tickets.fork();
+    /// assert_eq!(branch.draw(3), tickets.draw(3));
+    /// branch.draw(10);
+    /// assert_eq!(tickets.items.len(), 97);
+    /// assert_eq!(branch.items.len(), 87);
+    /// ```
+    pub fn fork(&self) -> Bag<T> {
+        self.clone()
     }
 
     /// Set the Bag's maximum amount of simulations to run when generating probabilities.
@@ -137,7 +488,408 @@ impl<T: Clone> Bag<T> {
     /// my_bag.set_max_sims(10_000);
     /// assert!(my_bag.max_sims == 10_000);
     /// ```
-    pub fn set_max_sims(&mut self, max_sims: u32) -> () {
+    pub fn set_max_sims(&mut self, max_sims: u32) {
         self.max_sims = max_sims;
+        self.budget = None;
+    }
+
+    /// Set how much the Bag simulates when generating probabilities: a number of simulations, a
+    /// length of time, or a precision to reach.
+    ///
+    /// This overrides [`set_max_sims`](#method.set_max_sims) until it's called again. Calibration
+    /// always runs its fixed pilot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a precision's `epsilon` isn't positive, or its `confidence` isn't between 0 and 1.
+    ///
+    /// # Examples
+    ///
+    /// Simulate until the probability is known to within half a percentage point:
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use mendel::{Bag, Budget};
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_budget(Budget::Precision { epsilon: 0.005, confidence: 0.95 });
+    /// let result = my_bag.one_detailed(|v| v % 2 == 0);
+    /// let (low, high) = result.confidence_interval();
+    /// assert!(high - low <= 0.01);
+    ///
+    /// my_bag.set_budget(Budget::Time(Duration::from_millis(10)));
+    /// assert!(my_bag.one_detailed(|v| v % 2 == 0).trials > 0);
+    /// ```
+    pub fn set_budget(&mut self, budget: Budget) {
+        budget.validate();
+        if let Some(trials) = budget.trials() {
+            self.max_sims = trials.min(u32::MAX as u64) as u32;
+        }
+        self.budget = Some(budget);
+    }
+
+    /// Shuffles the order of the items in the bag in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate rand;
+    /// extern crate mendel;
+    ///
+    /// use mendel::Bag;
+    ///
+    /// # fn main() {
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.shuffle(&mut rand::thread_rng());
+    /// assert_eq!(my_bag.items.len(), 10);
+    /// # }
+    /// ```
+    pub fn shuffle<R: Rng>(&mut self, rng: &mut R) where
+        T: Clone {
+        rng.shuffle(self.items_mut());
+    }
+
+    /// Sorts the items in the bag with a comparator function, giving the bag a deterministic order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut words = Bag::from_vec(vec!["lizard", "hello", "golfing"]);
+    /// words.sort_by(|a, b| a.cmp(b));
+    /// assert_eq!(*words.items, vec!["golfing", "hello", "lizard"]);
+    /// ```
+    pub fn sort_by<F>(&mut self, compare: F) where
+        T: Clone,
+        F: FnMut(&T, &T) -> Ordering {
+        self.items_mut().sort_by(compare);
+    }
+
+    /// Seed the Bag's simulations, making every probability it generates reproducible.
+    ///
+    /// The seed only configures how each run creates its random number generator, no RNG state
+    /// is kept in the bag, so a seeded `Bag` can still be shared between threads.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_seed(1234);
+    /// assert_eq!(my_bag.one(|v| v % 2 == 0), my_bag.one(|v| v % 2 == 0));
+    /// ```
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    /// Choose the random number generator algorithm the Bag simulates with.
+    ///
+    /// Faster generators trade away some statistical quality, see [`RngKind`](enum.RngKind.html).
+    /// Seeds are honoured by every kind, but the same seed gives different numbers with different kinds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, RngKind};
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_rng_kind(RngKind::Xoshiro);
+    /// let odds_of_even = my_bag.one(|v| v % 2 == 0);
+    /// assert!(0.49 < odds_of_even && odds_of_even < 0.51);
+    /// ```
+    pub fn set_rng_kind(&mut self, rng_kind: RngKind) {
+        self.rng_kind = rng_kind;
+    }
+
+    /// Split the simulations of [`one`](#method.one) and [`sample`](#method.sample) (and their
+    /// `_detailed` variants) across `threads` worker threads.
+    ///
+    /// Each worker runs an equal share of `max_sims` with its own random number generator, seeded
+    /// from the run's seed by [`worker_seed`](fn.worker_seed.html), and their counts are merged into
+    /// one result. A seeded bag's parallel runs are exactly reproducible with a budget of trials.
+    /// Only `std::thread` is used, so there are no extra dependencies. The default is 1, which
+    /// simulates on the calling thread.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_parallelism(4);
+    /// let odds_of_even = my_bag.one(|v| v % 2 == 0);
+    /// assert!(0.49 < odds_of_even && odds_of_even < 0.51);
+    /// ```
+    pub fn set_parallelism(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Let the Bag stop simulating early once a probability is overwhelmingly close to 0 or 1.
+    ///
+    /// After at least a thousand simulations, single-event estimates periodically check whether the
+    /// 99.9% confidence interval lies entirely within 0.1% of either 0 or 1. If it does, the run ends
+    /// there and its `SimulationResult` is flagged as `decided_early`. This saves a lot of time on
+    /// obvious questions, at the cost of fewer simulations behind the answer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_early_stopping(true);
+    /// let result = my_bag.one_detailed(|v| *v > 100);
+    /// assert!(result.decided_early);
+    /// assert!(result.trials < my_bag.max_sims as u64);
+    /// ```
+    pub fn set_early_stopping(&mut self, early_stopping: bool) {
+        self.early_stopping = early_stopping;
+    }
+
+    /// Let detailed results, such as those of [`one_detailed`](#method.one_detailed) and
+    /// [`sample_detailed`](#method.sample_detailed), hold how their estimate converged.
+    ///
+    /// The result's `convergence` is then the running estimate and its confidence interval at about
+    /// 100 to 200 evenly spaced trial counts, ending with the final result. A parallel run records a
+    /// point whenever a worker reports a chunk of its counts, so it has fewer points.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_max_sims(10_000);
+    /// my_bag.set_track_convergence(true);
+    /// let result = my_bag.one_detailed(|v| v % 2 == 0);
+    /// let points = result.convergence.as_ref().unwrap();
+    /// assert_eq!(points.len(), 100);
+    /// assert_eq!(points.last().unwrap().trials, result.trials);
+    /// assert!(points.last().unwrap().ci_width < points[0].ci_width);
+    /// ```
+    pub fn set_track_convergence(&mut self, track_convergence: bool) {
+        self.track_convergence = track_convergence;
+    }
+
+    /// The configuration for a simulation run.
+    pub(crate) fn settings(&self) -> Settings {
+        Settings {
+            max_sims: self.max_sims,
+            budget: self.budget,
+            seed: self.seed,
+            rng_kind: self.rng_kind,
+            early_stopping: self.early_stopping,
+            track_convergence: self.track_convergence,
+            threads: self.threads
+        }
+    }
+
+    /// Mutable access to the items, copying them first if the population is shared with other bags.
+    pub(crate) fn items_mut(&mut self) -> &mut Vec<T> where
+        T: Clone {
+        Arc::make_mut(&mut self.items)
+    }
+
+    /// Returns an iterator over the items in the bag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let total: i32 = my_bag.iter().sum();
+    /// assert_eq!(total, 55);
+    /// ```
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Returns an iterator that allows modifying each item in the bag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 4);
+    /// for v in my_bag.iter_mut() {
+    ///     *v *= 10;
+    /// }
+    /// assert_eq!(*my_bag.items, vec![10, 20, 30]);
+    /// ```
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> where
+        T: Clone {
+        self.items_mut().iter_mut()
+    }
+
+    /// Borrows the items in `range` as a `BagView`, which can be simulated over without copying.
+    ///
+    /// The view uses the bag's configuration, such as its `max_sims`, seed and parallelism.
+    ///
+    /// # Examples
+    ///
+    /// Odds of picking an even number from only the first 4 numbers:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 101);
+    /// let first_four = my_bag.view(0..4);
+    /// assert_eq!(first_four.len(), 4);
+    /// let odds_of_even = first_four.one(|v| v % 2 == 0);
+    /// assert!(0.49 < odds_of_even && odds_of_even < 0.51);
+    /// ```
+    pub fn view<'a>(&'a self, range: Range<usize>) -> BagView<'a, T> {
+        BagView::new(&self.items[range], self.settings())
+    }
+}
+
+impl<T> Clone for Bag<T> {
+    fn clone(&self) -> Self {
+        Bag {
+            items: Arc::clone(&self.items),
+            max_sims: self.max_sims,
+            budget: self.budget,
+            seed: self.seed,
+            rng_kind: self.rng_kind,
+            early_stopping: self.early_stopping,
+            track_convergence: self.track_convergence,
+            threads: self.threads
+        }
+    }
+}
+
+impl<T> Default for Bag<T> {
+    fn default() -> Self {
+        Bag::from_vec(Vec::new())
+    }
+}
+
+impl<T: Debug> Debug for Bag<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Populations can be huge, so only show the first few items
+        struct Preview<'a, T: 'a>(&'a [T]);
+
+        impl<'a, T: Debug> Debug for Preview<'a, T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let mut list = f.debug_list();
+                list.entries(self.0.iter().take(DEBUG_ITEMS));
+                if self.0.len() > DEBUG_ITEMS {
+                    list.entry(&format_args!("... {} more", self.0.len() - DEBUG_ITEMS));
+                }
+                list.finish()
+            }
+        }
+
+        f.debug_struct("Bag")
+            .field("len", &self.items.len())
+            .field("max_sims", &self.max_sims)
+            .field("items", &Preview(&self.items))
+            .finish()
+    }
+}
+
+impl<T: Eq + Hash> PartialEq for Bag<T> {
+    fn eq(&self, other: &Bag<T>) -> bool {
+        if self.items.len() != other.items.len() {
+            return false;
+        }
+        let mut counts: HashMap<&T, isize> = HashMap::new();
+        for item in self.items.iter() {
+            *counts.entry(item).or_insert(0) += 1;
+        }
+        for item in other.items.iter() {
+            match counts.get_mut(item) {
+                Some(count) if *count > 0 => *count -= 1,
+                _ => return false
+            }
+        }
+        true
+    }
+}
+
+impl<T: Eq + Hash> Eq for Bag<T> {}
+
+impl<T: Clone> IntoIterator for Bag<T> {
+    type Item = T;
+    type IntoIter = vec::IntoIter<T>;
+
+    fn into_iter(self) -> vec::IntoIter<T> {
+        let items = Arc::try_unwrap(self.items).unwrap_or_else(|shared| (*shared).clone());
+        items.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Bag<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.items.iter()
+    }
+}
+
+impl<'a, T: Clone> IntoIterator for &'a mut Bag<T> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> slice::IterMut<'a, T> {
+        self.items_mut().iter_mut()
+    }
+}
+
+impl<T> FromIterator<T> for Bag<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Bag::from_vec(iter.into_iter().collect())
+    }
+}
+
+impl<T> Index<usize> for Bag<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.items[index]
+    }
+}
+
+impl<T> Index<Range<usize>> for Bag<T> {
+    type Output = [T];
+
+    fn index(&self, range: Range<usize>) -> &[T] {
+        &self.items[range]
+    }
+}
+
+/// Picks one random item from `items`.
+pub(crate) fn pick_one<'a, T, R>(rng: &mut R, items: &'a [T]) -> &'a T where
+    R: Rng {
+    &items[pick_index(rng, items.len())]
+}
+
+/// Picks a uniformly random index of `len` items, the way `pick_one` picks items.
+pub(crate) fn pick_index<R: Rng>(rng: &mut R, len: usize) -> usize {
+    rng.gen_range(0, len)
+}
+
+/// Draws `sample_size` random items from `items` without replacement.
+pub(crate) fn draw_sample<'a, T, R>(rng: &mut R, items: &'a [T], sample_size: usize) -> Vec<&'a T> where
+    R: Rng {
+    if sample_size == items.len() {
+        return items.iter().collect();
+    }
+    if sample_size > items.len() / 2 && sample_size < items.len() {
+        // Close to the whole bag, draw the few items to leave out and keep the rest
+        let mut kept = vec![true; items.len()];
+        for index in seq::sample_indices(rng, items.len(), items.len() - sample_size) {
+            kept[index] = false;
+        }
+        return items.iter().zip(kept).filter(|&(_, keep)| keep).map(|(item, _)| item).collect();
+    }
+    match seq::sample_iter(rng, items, sample_size) {
+        Ok(sample) => sample,
+        Err(_) => panic!("sample_size {} is larger than the population of {}", sample_size, items.len())
     }
 }
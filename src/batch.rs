@@ -0,0 +1,92 @@
+use bag::Bag;
+use report::{Events, Report};
+use result::SimulationResult;
+
+/// Many queries against the same bag, planned so queries over the same sample size share their
+/// simulated samples, see [`Bag::batch`](struct.Bag.html#method.batch).
+pub struct QueryBatch<'a, T: 'a> {
+    bag: &'a Bag<T>,
+    // One group of compatible queries per sample size, in the order they were first asked for
+    groups: Vec<(usize, Events<'a, T>)>
+}
+
+impl<'a, T> QueryBatch<'a, T> {
+
+    /// Adds a named query over a sample of `sample_size` random items.
+    pub fn add<F>(mut self, name: &str, sample_size: usize, f: F) -> Self where
+        F: Fn(Vec<&T>) -> bool + 'a {
+        let index = match self.groups.iter().position(|(k, _)| *k == sample_size) {
+            Some(index) => index,
+            None => {
+                self.groups.push((sample_size, Events::new()));
+                self.groups.len() - 1
+            }
+        };
+        self.groups[index].1.events.push((name.to_string(), Box::new(f)));
+        self
+    }
+
+    /// Returns the number of queries.
+    pub fn len(&self) -> usize {
+        self.groups.iter().map(|(_, events)| events.len()).sum()
+    }
+
+    /// Returns `true` if there are no queries.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Returns the number of simulation passes [`run`](#method.run) makes, one per distinct sample size.
+    pub fn passes(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Answers every query, running one simulation for each group of queries sharing a sample size.
+    pub fn run(&self) -> BatchResults {
+        let reports = self.groups.iter().map(|(k, events)| self.bag.report(*k, events)).collect();
+        BatchResults { reports }
+    }
+}
+
+/// The answers to a [`QueryBatch`](struct.QueryBatch.html), with a `Report` for each sample size.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BatchResults {
+    pub reports: Vec<Report>
+}
+
+impl BatchResults {
+
+    /// Returns the result of the query called `name`.
+    pub fn get(&self, name: &str) -> Option<&SimulationResult> {
+        self.reports.iter().filter_map(|report| report.get(name)).next()
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Starts a batch of queries against this bag.
+    ///
+    /// Queries over the same sample size are evaluated against the same simulated samples in a single
+    /// pass, which is far cheaper than asking each one separately when there are dozens of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let dice = Bag::from_range(1, 7);
+    /// let batch = dice.batch()
+    ///     .add("both sixes", 2, |d| d.iter().all(|v| **v == 6))
+    ///     .add("any six", 2, |d| d.iter().any(|v| **v == 6))
+    ///     .add("three distinct", 3, |d| d[0] != d[1] && d[1] != d[2] && d[0] != d[2]);
+    /// assert_eq!(batch.passes(), 2);
+    /// let answers = batch.run();
+    /// // 2 of 6, drawn without replacement
+    /// let any_six = answers.get("any six").unwrap().probability();
+    /// assert!(0.31 < any_six && any_six < 0.36);
+    /// assert_eq!(answers.get("both sixes").unwrap().successes, 0);
+    /// ```
+    pub fn batch<'a>(&'a self) -> QueryBatch<'a, T> {
+        QueryBatch { bag: self, groups: Vec::new() }
+    }
+}
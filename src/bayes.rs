@@ -0,0 +1,61 @@
+//! Bayes' rule over simulated likelihoods.
+//!
+//! Simulate P(B | A) and P(B | not A) with bags for each case, then combine them with a prior P(A)
+//! to get P(A | B), with the simulations' uncertainty carried through.
+
+use result::{SimulationResult, Z_95};
+
+/// P(A | B) computed from simulated likelihoods, see [`posterior`](fn.posterior.html).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BayesResult {
+    pub probability: f64,
+    /// The standard error of `probability` due to the simulations, by the delta method.
+    pub std_error: f64
+}
+
+impl BayesResult {
+
+    /// The approximate 95% confidence interval of the probability, as `(low, high)`.
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        ((self.probability - Z_95 * self.std_error).max(0.0), (self.probability + Z_95 * self.std_error).min(1.0))
+    }
+}
+
+/// Applies Bayes' rule: the probability of A given that B was observed, from the `prior` P(A) and
+/// simulated results of P(B | A) and P(B | not A).
+///
+/// # Panics
+///
+/// Panics if `prior` isn't a probability, or if B never happened in either simulation.
+///
+/// # Examples
+///
+/// A test catches 9 in 10 cases of a condition 1 in 100 people have, with 1 in 20 false positives.
+/// How likely is a positive result a real case?
+///
+/// ```
+/// use mendel::Bag;
+/// use mendel::bayes::posterior;
+///
+/// let mut sick = Bag::from_range(0, 10);
+/// let mut healthy = Bag::from_range(0, 20);
+/// sick.set_max_sims(20_000);
+/// healthy.set_max_sims(20_000);
+/// let positive_if_sick = sick.one_detailed(|v| *v < 9);
+/// let positive_if_healthy = healthy.one_detailed(|v| *v == 0);
+/// let sick_if_positive = posterior(0.01, &positive_if_sick, &positive_if_healthy);
+/// // 0.009 / (0.009 + 0.0495)
+/// assert!(0.13 < sick_if_positive.probability && sick_if_positive.probability < 0.18);
+/// ```
+pub fn posterior(prior: f64, b_given_a: &SimulationResult, b_given_not_a: &SimulationResult) -> BayesResult {
+    assert!((0.0..=1.0).contains(&prior), "prior must be a probability, got {}", prior);
+    let (x, y) = (b_given_a.probability(), b_given_not_a.probability());
+    let evidence = prior * x + (1.0 - prior) * y;
+    assert!(evidence > 0.0, "B never happened in the simulations");
+    let probability = prior * x / evidence;
+    // Partial derivatives of P(A | B) with respect to both likelihoods
+    let d_x = prior * (1.0 - prior) * y / (evidence * evidence);
+    let d_y = -prior * (1.0 - prior) * x / (evidence * evidence);
+    let variance = d_x * d_x * b_given_a.std_error().powi(2) + d_y * d_y * b_given_not_a.std_error().powi(2);
+    BayesResult { probability, std_error: variance.sqrt() }
+}
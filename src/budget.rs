@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+
+use math::normal_quantile;
+use result::SimulationResult;
+
+/// A time budget checks the clock once every this many simulations.
+const TIME_CHECK_EVERY: u64 = 64;
+
+/// A precision budget always runs this many simulations, then checks its interval this often.
+const PRECISION_CHECK_EVERY: u64 = 100;
+
+/// How much simulating a run may do, see [`Bag::set_budget`](struct.Bag.html#method.set_budget).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Budget {
+    /// Run this many simulations, the same as [`Bag::set_max_sims`](struct.Bag.html#method.set_max_sims).
+    Trials(u64),
+    /// Keep simulating for this long. At least one simulation always runs.
+    Time(Duration),
+    /// Keep simulating until the `confidence` (such as `0.95`) interval of the probability is
+    /// within `epsilon` of it on either side.
+    ///
+    /// Results made of more than one probability, such as a distribution or a report, run enough
+    /// simulations for any probability to reach the precision. Beware that very small epsilons
+    /// need a lot of simulations: about `1 / epsilon^2` for a 95% interval.
+    Precision { epsilon: f64, confidence: f64 }
+}
+
+impl Budget {
+
+    /// # Panics
+    ///
+    /// Panics if a precision's `epsilon` isn't positive, or its `confidence` isn't between 0 and 1.
+    pub(crate) fn validate(&self) {
+        if let Budget::Precision { epsilon, confidence } = *self {
+            assert!(epsilon > 0.0, "epsilon must be positive, got {}", epsilon);
+            z_score(confidence);
+        }
+    }
+
+    /// Whether a run that got `successes` out of `trials` simulations since `started` is done.
+    pub(crate) fn is_spent(&self, successes: u64, trials: u64, started: Instant) -> bool {
+        match *self {
+            Budget::Trials(limit) => trials >= limit,
+            Budget::Time(limit) => trials > 0 && trials % TIME_CHECK_EVERY == 0 && started.elapsed() >= limit,
+            Budget::Precision { epsilon, confidence } => {
+                if trials == 0 || trials % PRECISION_CHECK_EVERY != 0 {
+                    return false;
+                }
+                let (low, high) = SimulationResult::new(successes, trials).wilson_interval(z_score(confidence));
+                (high - low) / 2.0 <= epsilon
+            }
+        }
+    }
+
+    /// The budget of each of `workers` independent runs whose results get merged.
+    pub(crate) fn split(&self, workers: usize, i: usize) -> Budget {
+        match *self {
+            Budget::Trials(limit) => {
+                let share = limit / workers as u64 + if (i as u64) < limit % workers as u64 { 1 } else { 0 };
+                Budget::Trials(share)
+            }
+            Budget::Time(limit) => Budget::Time(limit),
+            // Merging runs narrows the interval by the square root of their number
+            Budget::Precision { epsilon, confidence } => Budget::Precision { epsilon: epsilon * (workers as f64).sqrt(), confidence }
+        }
+    }
+
+    /// The budget for results with no single probability to watch, where a precision is reached
+    /// by the simulations a probability of 1/2 would need.
+    pub(crate) fn without_successes(&self) -> Budget {
+        match *self {
+            Budget::Precision { epsilon, confidence } => {
+                let z = z_score(confidence);
+                Budget::Trials((z * z / (4.0 * epsilon * epsilon)).ceil() as u64)
+            }
+            budget => budget
+        }
+    }
+
+    /// The number of simulations, if the budget is a fixed number of them.
+    pub(crate) fn trials(&self) -> Option<u64> {
+        match *self {
+            Budget::Trials(limit) => Some(limit),
+            _ => None
+        }
+    }
+}
+
+/// The z-score of a two-sided `confidence` interval.
+fn z_score(confidence: f64) -> f64 {
+    assert!(confidence > 0.0 && confidence < 1.0, "confidence must be between 0 and 1, got {}", confidence);
+    normal_quantile(0.5 + confidence / 2.0)
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use bag::Bag;
+use budget::Budget;
+use persist::{read_cache, write_cache};
+use result::SimulationResult;
+
+/// The 64 bit FNV-1a hash, which unlike the standard library's hasher is the same from one build
+/// to the next, so fingerprints saved to a file stay valid.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+impl<T: Hash> Bag<T> {
+
+    /// A fingerprint of the bag's items, in order, and of every setting that changes its results:
+    /// the budget, seed, random number generator, early stopping and parallelism.
+    ///
+    /// Equal bags with equal settings get the same fingerprint, on every run of the same program.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// let fingerprint = my_bag.fingerprint();
+    /// assert_eq!(Bag::from_range(1, 11).fingerprint(), fingerprint);
+    /// my_bag.set_seed(1);
+    /// assert!(my_bag.fingerprint() != fingerprint);
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv::new();
+        self.items.hash(&mut hasher);
+        let settings = self.settings();
+        match settings.budget() {
+            Budget::Trials(trials) => (0u8, trials).hash(&mut hasher),
+            Budget::Time(limit) => (1u8, limit).hash(&mut hasher),
+            Budget::Precision { epsilon, confidence } => (2u8, epsilon.to_bits(), confidence.to_bits()).hash(&mut hasher)
+        }
+        (settings.seed, settings.rng_kind, settings.early_stopping, settings.track_convergence, settings.threads).hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Results of earlier simulations, looked up by the fingerprint of the bag and a name for the
+/// query, so rerunning an identical query doesn't simulate again.
+///
+/// A cache lives in memory, or with [`open`](#method.open) is also saved to a file. Saved results
+/// don't keep their `convergence`.
+#[derive(Debug, Default)]
+pub struct ResultCache {
+    pub(crate) entries: HashMap<u64, SimulationResult>,
+    path: Option<PathBuf>
+}
+
+impl ResultCache {
+
+    /// Constructs an empty in-memory cache.
+    pub fn new() -> Self {
+        ResultCache::default()
+    }
+
+    /// Opens a cache saved at `path`, which is written again whenever a new result is cached.
+    /// A missing file starts an empty cache.
+    ///
+    /// Fails with `InvalidData` if the file isn't a saved cache.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries = match File::open(&path) {
+            Ok(file) => read_cache(BufReader::new(file))?.into_iter().collect(),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e)
+        };
+        Ok(ResultCache { entries, path: Some(path) })
+    }
+
+    /// Returns the number of cached results.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the cached result of the query called `query` on `bag`, or runs `simulate` on the
+    /// bag and caches its result.
+    ///
+    /// The query name stands in for the criteria, which can't be compared, so it needs to change
+    /// whenever the criteria does. Fails only if a file-backed cache can't be written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, ResultCache};
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let mut cache = ResultCache::new();
+    /// let first = cache.get_or_run(&my_bag, "even", |bag| bag.one_detailed(|v| v % 2 == 0)).unwrap();
+    /// // Not simulated again
+    /// let again = cache.get_or_run(&my_bag, "even", |_| unreachable!()).unwrap();
+    /// assert_eq!(first, again);
+    /// ```
+    pub fn get_or_run<T, F>(&mut self, bag: &Bag<T>, query: &str, simulate: F) -> io::Result<SimulationResult> where
+        T: Hash,
+        F: FnOnce(&Bag<T>) -> SimulationResult {
+        let mut hasher = Fnv::new();
+        (bag.fingerprint(), query).hash(&mut hasher);
+        let key = hasher.finish();
+        if let Some(result) = self.entries.get(&key) {
+            return Ok(result.clone());
+        }
+        let result = simulate(bag);
+        self.entries.insert(key, result.clone());
+        if let Some(ref path) = self.path {
+            write_cache(BufWriter::new(File::create(path)?), self)?;
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::{Duration, Instant};
+
+use bag::{pick_one, Bag};
+use result::Z_95;
+
+/// Number of simulations run by the pilot when calibrating.
+const PILOT_SIMS: u32 = 2_000;
+
+/// The outcome of a calibration run, see [`Bag::calibrate`](struct.Bag.html#method.calibrate).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Calibration {
+    /// Number of simulations the pilot ran.
+    pub pilot_sims: u32,
+    /// Probability estimated by the pilot.
+    pub pilot_estimate: f64,
+    /// Simulations needed for the requested margin of error at 95% confidence.
+    pub recommended_sims: u32,
+    /// Expected wall-clock time of a run with `recommended_sims` simulations.
+    pub projected_runtime: Duration
+}
+
+/// Simulations needed so that a proportion near `p` is within `margin` at 95% confidence.
+pub(crate) fn sims_for_margin(p: f64, margin: f64) -> u32 {
+    let n = (Z_95 * Z_95 * p * (1.0 - p) / (margin * margin)).ceil();
+    if n > u32::MAX as f64 { u32::MAX } else { n.max(1.0) as u32 }
+}
+
+impl<T> Bag<T> {
+
+    /// Runs a short pilot simulation to recommend how many simulations are needed to estimate
+    /// the odds of `f` (as with [`one`](#method.one)) within +/- `target_margin` at 95% confidence.
+    ///
+    /// A pilot that saw no (or only) successes would suggest a variance of zero, so the
+    /// pilot's estimate is nudged by half a success before computing the variance.
+    ///
+    /// # Examples
+    ///
+    /// How many simulations are needed to be within 0.5% when picking an even number?
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// let calibration = my_bag.calibrate(|v| v % 2 == 0, 0.005);
+    /// assert!(calibration.recommended_sims > 35_000);
+    /// my_bag.set_max_sims(calibration.recommended_sims);
+    /// ```
+    pub fn calibrate<F>(&self, f: F, target_margin: f64) -> Calibration where
+        F: Fn(&T) -> bool {
+        assert!(target_margin > 0.0, "target_margin must be positive");
+        let start = Instant::now();
+        let mut pilot = self.settings();
+        pilot.set_max_sims(PILOT_SIMS);
+        pilot.early_stopping = false;
+        let picks_in_favor = pilot.run(|rng| f(pick_one(rng, &self.items))).successes;
+        let elapsed = start.elapsed();
+
+        let pilot_estimate = picks_in_favor as f64 / PILOT_SIMS as f64;
+        let adjusted = (picks_in_favor as f64 + 0.5) / (PILOT_SIMS as f64 + 1.0);
+        let recommended_sims = sims_for_margin(adjusted, target_margin);
+        let projected_runtime = elapsed.mul_f64(recommended_sims as f64 / PILOT_SIMS as f64);
+        Calibration { pilot_sims: PILOT_SIMS, pilot_estimate, recommended_sims, projected_runtime }
+    }
+}
@@ -0,0 +1,112 @@
+use result::{SimulationResult, Z_95};
+
+/// A probability combined from independent simulations, carrying its propagated standard error,
+/// see [`SimulationResult::and`](struct.SimulationResult.html#method.and).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CombinedProbability {
+    pub probability: f64,
+    pub std_error: f64
+}
+
+impl CombinedProbability {
+
+    /// The 95% confidence interval of the probability, as `(low, high)`.
+    ///
+    /// Uses the normal approximation, clamped to between 0 and 1.
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        let margin = Z_95 * self.std_error;
+        ((self.probability - margin).max(0.0), (self.probability + margin).min(1.0))
+    }
+
+    /// The probability of both independent events happening.
+    pub fn and<P: Into<CombinedProbability>>(self, other: P) -> CombinedProbability {
+        let other = other.into();
+        let (a, b) = (self.probability, other.probability);
+        let (va, vb) = (self.variance(), other.variance());
+        CombinedProbability { probability: a * b, std_error: (b * b * va + a * a * vb + va * vb).sqrt() }
+    }
+
+    /// The probability of either of two independent events happening.
+    pub fn or<P: Into<CombinedProbability>>(self, other: P) -> CombinedProbability {
+        let other = other.into();
+        // Neither happening is the product of the complements, which have the same variances
+        let neither = CombinedProbability { probability: 1.0 - self.probability, std_error: self.std_error }
+            .and(CombinedProbability { probability: 1.0 - other.probability, std_error: other.std_error });
+        CombinedProbability { probability: 1.0 - neither.probability, std_error: neither.std_error }
+    }
+
+    /// The probability of an event given a condition, where this is the probability of both the
+    /// event and the condition and `condition` is the probability of the condition alone, estimated
+    /// independently.
+    ///
+    /// The error is propagated to first order, and the probability is capped at 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the probability of the condition is 0.
+    pub fn given<P: Into<CombinedProbability>>(self, condition: P) -> CombinedProbability {
+        let condition = condition.into();
+        let (a, b) = (self.probability, condition.probability);
+        assert!(b > 0.0, "the condition has a probability of 0");
+        let ratio = a / b;
+        let std_error = (self.variance() / (b * b) + ratio * ratio * condition.variance() / (b * b)).sqrt();
+        CombinedProbability { probability: ratio.min(1.0), std_error }
+    }
+
+    fn variance(&self) -> f64 {
+        self.std_error * self.std_error
+    }
+}
+
+impl From<SimulationResult> for CombinedProbability {
+    fn from(result: SimulationResult) -> Self {
+        CombinedProbability::from(&result)
+    }
+}
+
+impl<'a> From<&'a SimulationResult> for CombinedProbability {
+    fn from(result: &'a SimulationResult) -> Self {
+        CombinedProbability { probability: result.probability(), std_error: result.std_error() }
+    }
+}
+
+impl SimulationResult {
+
+    /// Combines this with the result of an independent simulation into the probability of both
+    /// events happening, propagating both results' errors.
+    ///
+    /// The combined probability can be combined further, see
+    /// [`CombinedProbability`](struct.CombinedProbability.html).
+    ///
+    /// # Examples
+    ///
+    /// The odds of a 1 from one bag and an even number from another:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let one = Bag::from_range(1, 11).one_detailed(|v| *v == 1);
+    /// let even = Bag::from_range(1, 7).one_detailed(|v| v % 2 == 0);
+    /// let both = one.and(even);
+    /// assert!(0.04 < both.probability && both.probability < 0.06);
+    /// assert!(both.std_error > 0.0);
+    /// let (low, high) = both.confidence_interval();
+    /// assert!(low < both.probability && both.probability < high);
+    /// ```
+    pub fn and<P: Into<CombinedProbability>>(&self, other: P) -> CombinedProbability {
+        CombinedProbability::from(self).and(other)
+    }
+
+    /// Combines this with the result of an independent simulation into the probability of either
+    /// event happening, propagating both results' errors.
+    pub fn or<P: Into<CombinedProbability>>(&self, other: P) -> CombinedProbability {
+        CombinedProbability::from(self).or(other)
+    }
+
+    /// Combines this, the result for both an event and a condition, with the independent result for
+    /// the condition alone into the probability of the event given the condition, propagating both
+    /// results' errors. See [`CombinedProbability::given`](struct.CombinedProbability.html#method.given).
+    pub fn given<P: Into<CombinedProbability>>(&self, condition: P) -> CombinedProbability {
+        CombinedProbability::from(self).given(condition)
+    }
+}
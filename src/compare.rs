@@ -0,0 +1,201 @@
+use rand::Rng;
+
+use bag::Bag;
+use budget::Budget;
+use engine::Settings;
+use math::two_sided_p_value;
+use result::SimulationResult;
+use rng::{RngKind, SimRng};
+
+/// z-score of 80% power, used for the minimum detectable effect.
+const Z_POWER_80: f64 = 0.8416;
+
+/// The outcome of comparing the same event across two bags, see [`compare_bags`](fn.compare_bags.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comparison {
+    pub a: SimulationResult,
+    pub b: SimulationResult,
+    /// The probability in bag `a` minus the probability in bag `b`.
+    pub difference: f64,
+    /// The two-proportion z-test statistic of the difference.
+    pub z_score: f64,
+    /// The two-sided p-value of the z-test.
+    pub p_value: f64
+}
+
+impl Comparison {
+
+    /// Compares two results with a two-proportion z-test.
+    pub fn from_results(a: SimulationResult, b: SimulationResult) -> Self {
+        let difference = a.probability() - b.probability();
+        let pooled = (a.successes + b.successes) as f64 / (a.trials + b.trials) as f64;
+        let std_error = (pooled * (1.0 - pooled) * (1.0 / a.trials as f64 + 1.0 / b.trials as f64)).sqrt();
+        // Both bags always (or never) meet the criteria, so there's no difference to detect
+        let z_score = if std_error > 0.0 { difference / std_error } else { 0.0 };
+        Comparison { a, b, difference, z_score, p_value: two_sided_p_value(z_score) }
+    }
+
+    /// Returns `true` if the difference is significant at the given level, such as `0.05`.
+    pub fn is_significant(&self, alpha: f64) -> bool {
+        self.p_value < alpha
+    }
+}
+
+/// Estimates the probability of criteria being met for the first `sample_size` random items of each
+/// bag, and tests whether the two populations genuinely differ.
+///
+/// # Examples
+///
+/// ```
+/// use mendel::{compare_bags, Bag};
+///
+/// let fair = Bag::from_range(1, 7);
+/// let loaded = Bag::from_vec(vec![1, 2, 3, 4, 5, 6, 6, 6]);
+/// let comparison = compare_bags(&fair, &loaded, 1, |rolls| *rolls[0] == 6);
+/// assert!(comparison.difference < 0.0);
+/// assert!(comparison.is_significant(0.05));
+/// ```
+pub fn compare_bags<T, F>(bag_a: &Bag<T>, bag_b: &Bag<T>, sample_size: usize, f: F) -> Comparison where
+    T: Sync,
+    F: Fn(Vec<&T>) -> bool + Sync {
+    let a = bag_a.sample_detailed(sample_size, &f);
+    let b = bag_b.sample_detailed(sample_size, &f);
+    Comparison::from_results(a, b)
+}
+
+/// An A/B test over real-world observations from two groups, analysed by permutation.
+///
+/// Under the null hypothesis that both groups share one success rate, the observed outcomes are
+/// interchangeable, so each simulation randomly reassigns the pooled outcomes to two groups of the
+/// original sizes. The p-value is how often a reassignment differs at least as much as the real
+/// groups did, with a +1 correction so the false-positive rate stays at or below `alpha`.
+///
+/// The permutations are simulated like a bag's, with its own budget, seed, generator and parallelism.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AbTest {
+    pub a: SimulationResult,
+    pub b: SimulationResult,
+    pub alpha: f64,
+    settings: Settings
+}
+
+/// The outcome of an [`AbTest`](struct.AbTest.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct AbTestResult {
+    /// The success rate of group `a` minus that of group `b`.
+    pub observed_difference: f64,
+    pub p_value: f64,
+    /// `true` if `p_value` is below the test's `alpha`.
+    pub significant: bool,
+    /// The smallest absolute difference which would have been significant.
+    pub critical_difference: f64,
+    /// The smallest true difference the test detects with 80% power, approximated from the
+    /// simulated null distribution as the critical difference plus 0.84 of its standard deviations.
+    pub minimum_detectable_effect: f64
+}
+
+impl AbTest {
+
+    /// Constructs a new `AbTest` from each group's observed successes and trials, using a 0.05 significance level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::AbTest;
+    ///
+    /// // 120 of 1,000 visitors converted on the old page, 160 of 1,000 on the new one
+    /// let mut test = AbTest::new(120, 1_000, 160, 1_000);
+    /// test.set_max_sims(10_000);
+    /// let result = test.run();
+    /// assert!(result.observed_difference < 0.0);
+    /// assert!(result.significant);
+    /// ```
+    pub fn new(successes_a: u64, trials_a: u64, successes_b: u64, trials_b: u64) -> Self {
+        AbTest {
+            a: SimulationResult::new(successes_a, trials_a),
+            b: SimulationResult::new(successes_b, trials_b),
+            alpha: 0.05,
+            settings: Settings::default()
+        }
+    }
+
+    /// Set the significance level, i.e. the accepted false-positive rate.
+    pub fn set_alpha(&mut self, alpha: f64) {
+        self.alpha = alpha;
+    }
+
+    /// Set the number of permutations to simulate.
+    pub fn set_max_sims(&mut self, max_sims: u32) {
+        self.settings.set_max_sims(max_sims);
+    }
+
+    /// Set how much to simulate, see [`Bag::set_budget`](struct.Bag.html#method.set_budget).
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.settings.set_budget(budget);
+    }
+
+    /// Seed the permutations, making the result reproducible.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.settings.seed = Some(seed);
+    }
+
+    /// Set the random number generator, see [`Bag::set_rng_kind`](struct.Bag.html#method.set_rng_kind).
+    pub fn set_rng_kind(&mut self, rng_kind: RngKind) {
+        self.settings.rng_kind = rng_kind;
+    }
+
+    /// Split the permutations across `threads` worker threads, see
+    /// [`Bag::set_parallelism`](struct.Bag.html#method.set_parallelism).
+    pub fn set_parallelism(&mut self, threads: usize) {
+        self.settings.threads = threads.max(1);
+    }
+
+    /// Runs the permutation test.
+    pub fn run(&self) -> AbTestResult {
+        let (n_a, n_b) = (self.a.trials, self.b.trials);
+        let successes = self.a.successes + self.b.successes;
+        let observed_difference = self.a.probability() - self.b.probability();
+        // Allow for floating point noise when comparing against the observed difference
+        let threshold = observed_difference.abs() - 1e-12;
+
+        let mut null_differences = self.settings.repeat_parallel(|rng: &mut SimRng| {
+            let successes_a = hypergeometric(rng, successes, n_a + n_b, n_a);
+            (successes_a as f64 / n_a as f64 - (successes - successes_a) as f64 / n_b as f64).abs()
+        });
+        let extreme = null_differences.iter().filter(|difference| **difference >= threshold).count();
+        let p_value = (extreme + 1) as f64 / (null_differences.len() as f64 + 1.0);
+
+        null_differences.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        let critical_idx = ((1.0 - self.alpha) * null_differences.len() as f64).ceil() as usize;
+        let critical_difference = null_differences.get(critical_idx.min(null_differences.len().saturating_sub(1)))
+            .cloned().unwrap_or(0.0);
+        let pooled = successes as f64 / (n_a + n_b) as f64;
+        let null_std = (pooled * (1.0 - pooled) * (1.0 / n_a as f64 + 1.0 / n_b as f64)).sqrt();
+
+        AbTestResult {
+            observed_difference,
+            p_value,
+            significant: p_value < self.alpha,
+            critical_difference,
+            minimum_detectable_effect: critical_difference + Z_POWER_80 * null_std
+        }
+    }
+}
+
+/// Number of successes when drawing `draws` of `total` items, `successes` of which are successes, without replacement.
+fn hypergeometric<R: Rng>(rng: &mut R, successes: u64, total: u64, draws: u64) -> u64 {
+    // Draw whichever side is smaller
+    if draws * 2 > total {
+        return successes - hypergeometric(rng, successes, total, total - draws);
+    }
+    let (mut remaining_successes, mut remaining) = (successes, total);
+    let mut drawn_successes = 0;
+    for _ in 0..draws {
+        if rng.gen_range(0, remaining) < remaining_successes {
+            drawn_successes += 1;
+            remaining_successes -= 1;
+        }
+        remaining -= 1;
+    }
+    drawn_successes
+}
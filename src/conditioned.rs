@@ -0,0 +1,105 @@
+use bag::{draw_sample, pick_one, Bag};
+use engine::Settings;
+
+/// Acceptance rates below this get a warning, see [`Conditioned::warning`](struct.Conditioned.html#method.warning).
+const LOW_ACCEPTANCE_RATE: f64 = 0.001;
+
+/// A view of a `Bag` that simulates draws given that the drawn items meet a condition,
+/// see [`Bag::conditioned`](struct.Bag.html#method.conditioned).
+pub struct Conditioned<'a, T: 'a> {
+    items: Vec<&'a T>,
+    pub acceptance_rate: f64,
+    settings: Settings
+}
+
+impl<'a, T> Conditioned<'a, T> {
+
+    /// Returns the number of items which meet the condition.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if no item meets the condition.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns a warning if so few items meet the condition that estimates may be misleading.
+    pub fn warning(&self) -> Option<String> {
+        if self.items.is_empty() {
+            Some("no items meet the condition, so there is nothing to draw".to_string())
+        } else if self.acceptance_rate < LOW_ACCEPTANCE_RATE {
+            Some(format!("only {} items ({:.4}%) meet the condition, estimates rest on very few distinct items",
+                         self.items.len(), self.acceptance_rate * 100.0))
+        } else {
+            None
+        }
+    }
+
+    /// Predicts probability of criteria being met for the first random item grabbed, given it meets the condition.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no item meets the condition.
+    pub fn one<F>(&self, f: F) -> f64 where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync {
+        self.assert_not_empty();
+        self.settings.run_parallel(|rng| f(*pick_one(rng, &self.items))).probability()
+    }
+
+    /// Predicts probability of criteria being met for the first `sample_size` random items grabbed,
+    /// given they all meet the condition.
+    ///
+    /// # Panics
+    ///
+    /// Panics if fewer than `sample_size` items meet the condition.
+    pub fn sample<F>(&self, sample_size: usize, f: F) -> f64 where
+        T: Sync,
+        F: Fn(Vec<&T>) -> bool + Sync {
+        self.assert_not_empty();
+        self.settings.run_parallel(|rng| f(draw_sample(rng, &self.items, sample_size).into_iter().cloned().collect()))
+            .probability()
+    }
+
+    /// Set the view's maximum amount of simulations to run when generating probabilities.
+    pub fn set_max_sims(&mut self, max_sims: u32) {
+        self.settings.set_max_sims(max_sims);
+    }
+
+    fn assert_not_empty(&self) {
+        assert!(!self.items.is_empty(), "no items meet the condition");
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Conditions the bag on `condition`, so that every query on the returned view is
+    /// "given that the item is X".
+    ///
+    /// Rather than rejecting non-matching draws during every simulation, the matching items are
+    /// found once up front. The fraction of items that matched is kept as `acceptance_rate`, and
+    /// [`warning`](struct.Conditioned.html#method.warning) flags conditions that almost nothing meets.
+    /// The view uses the bag's configuration, such as its `max_sims`, seed and parallelism.
+    ///
+    /// # Examples
+    ///
+    /// Odds a number from 1 - 10 is a multiple of 4, given that it's even:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let evens = my_bag.conditioned(|v| v % 2 == 0);
+    /// assert_eq!(evens.acceptance_rate, 0.5);
+    /// assert!(evens.warning().is_none());
+    /// let odds = evens.one(|v| v % 4 == 0);
+    /// assert!(0.39 < odds && odds < 0.41);
+    /// ```
+    pub fn conditioned<F>(&self, condition: F) -> Conditioned<'_, T> where
+        F: Fn(&T) -> bool {
+        let items: Vec<&T> = self.items.iter().filter(|item| condition(item)).collect();
+        let acceptance_rate = if self.items.is_empty() { 0.0 } else { items.len() as f64 / self.items.len() as f64 };
+        Conditioned { items, acceptance_rate, settings: self.settings() }
+    }
+}
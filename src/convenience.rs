@@ -0,0 +1,39 @@
+use bag::Bag;
+
+/// Predicts probability of criteria being met for a random item of `population`, in one line.
+///
+/// Builds a temporary bag with the default settings, see [`Bag::one`](struct.Bag.html#method.one).
+///
+/// # Examples
+///
+/// ```
+/// use mendel::probability_of;
+///
+/// let odds_of_even = probability_of(1..11, |v| v % 2 == 0);
+/// assert!(0.49 < odds_of_even && odds_of_even < 0.51);
+/// ```
+pub fn probability_of<T, I, F>(population: I, f: F) -> f64 where
+    T: Sync,
+    I: IntoIterator<Item = T>,
+    F: Fn(&T) -> bool + Sync {
+    population.into_iter().collect::<Bag<T>>().one(f)
+}
+
+/// Predicts probability of criteria being met for `sample_size` random items of `population`, in one line.
+///
+/// Builds a temporary bag with the default settings, see [`Bag::sample`](struct.Bag.html#method.sample).
+///
+/// # Examples
+///
+/// ```
+/// use mendel::probability_of_sample;
+///
+/// let odds_of_pair = probability_of_sample(vec!['a', 'a', 'b', 'c'], 2, |s| s[0] == s[1]);
+/// assert!(0.15 < odds_of_pair && odds_of_pair < 0.18);
+/// ```
+pub fn probability_of_sample<T, I, F>(population: I, sample_size: usize, f: F) -> f64 where
+    T: Sync,
+    I: IntoIterator<Item = T>,
+    F: Fn(Vec<&T>) -> bool + Sync {
+    population.into_iter().collect::<Bag<T>>().sample(sample_size, f)
+}
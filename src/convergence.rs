@@ -0,0 +1,94 @@
+use budget::Budget;
+use result::SimulationResult;
+
+/// How many points a convergence series aims for, between this many and twice as many.
+pub(crate) const CONVERGENCE_POINTS: u64 = 100;
+
+/// The running estimate of a simulation after some of its trials, see
+/// [`Bag::set_track_convergence`](struct.Bag.html#method.set_track_convergence).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConvergencePoint {
+    pub trials: u64,
+    /// The probability estimated from the trials so far.
+    pub estimate: f64,
+    /// The width of the 95% confidence interval of the estimate so far.
+    pub ci_width: f64
+}
+
+fn point(successes: u64, trials: u64) -> ConvergencePoint {
+    let result = SimulationResult::new(successes, trials);
+    let (low, high) = result.confidence_interval();
+    ConvergencePoint { trials, estimate: result.probability(), ci_width: high - low }
+}
+
+/// Records the running estimate of a run at evenly spaced trial counts.
+pub(crate) struct Tracker {
+    /// Trials between points, doubled whenever there are twice as many points as wanted.
+    every: u64,
+    /// The trial count of the next point.
+    next: u64,
+    points: Vec<ConvergencePoint>
+}
+
+impl Tracker {
+
+    /// A tracker for a run with `budget`. Budgets without a fixed number of trials start dense and
+    /// thin out the points as the run goes.
+    pub(crate) fn new(budget: Budget) -> Self {
+        let every = budget.trials().map_or(1, |trials| (trials / CONVERGENCE_POINTS).max(1));
+        Tracker { every, next: every, points: Vec::new() }
+    }
+
+    /// Records the counts so far, unless they're too close to the last point.
+    pub(crate) fn record(&mut self, successes: u64, trials: u64) {
+        if trials < self.next {
+            return;
+        }
+        self.points.push(point(successes, trials));
+        if self.points.len() as u64 == 2 * CONVERGENCE_POINTS {
+            self.every *= 2;
+            // Every other point is what recording at the new spacing would have kept
+            let mut index = 0;
+            self.points.retain(|_| {
+                index += 1;
+                index % 2 == 0
+            });
+        }
+        self.next = trials + self.every;
+    }
+
+    /// The points recorded for `result`, ending with the result itself.
+    pub(crate) fn finish(mut self, result: &SimulationResult) -> Vec<ConvergencePoint> {
+        if result.trials > 0 && self.points.last().map(|p| p.trials) != Some(result.trials) {
+            self.points.push(point(result.successes, result.trials));
+        }
+        self.points
+    }
+}
+
+impl SimulationResult {
+
+    /// Formats the convergence points as CSV, with a header row, or returns `None` if the
+    /// convergence wasn't tracked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_max_sims(10_000);
+    /// my_bag.set_track_convergence(true);
+    /// let result = my_bag.one_detailed(|v| v % 2 == 0);
+    /// assert!(result.convergence_csv().unwrap().starts_with("trials,estimate,ci_width\n100,"));
+    /// ```
+    pub fn convergence_csv(&self) -> Option<String> {
+        self.convergence.as_ref().map(|points| {
+            let mut csv = String::from("trials,estimate,ci_width\n");
+            for point in points {
+                csv.push_str(&format!("{},{},{}\n", point.trials, point.estimate, point.ci_width));
+            }
+            csv
+        })
+    }
+}
@@ -0,0 +1,86 @@
+use bag::{draw_sample, Bag};
+use distribution::Distribution;
+
+impl<T> Bag<T> {
+
+    /// Predicts probability of at least `count` of `sample_size` random items meeting the criteria.
+    ///
+    /// # Examples
+    ///
+    /// Odds of at least 2 even numbers in 3 picks from 1 - 10:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let odds = my_bag.at_least(2, 3, |v| v % 2 == 0);
+    /// assert!(0.49 < odds && odds < 0.51);
+    /// ```
+    pub fn at_least<F>(&self, count: usize, sample_size: usize, f: F) -> f64 where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync {
+        self.sample(sample_size, |values| values.into_iter().filter(|v| f(v)).count() >= count)
+    }
+
+    /// Predicts probability of exactly `count` of `sample_size` random items meeting the criteria.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let odds = my_bag.exactly(3, 3, |v| v % 2 == 0);
+    /// // (5 * 4 * 3) / (10 * 9 * 8)
+    /// assert!(0.07 < odds && odds < 0.09);
+    /// ```
+    pub fn exactly<F>(&self, count: usize, sample_size: usize, f: F) -> f64 where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync {
+        self.sample(sample_size, |values| values.into_iter().filter(|v| f(v)).count() == count)
+    }
+
+    /// Predicts probability of at most `count` of `sample_size` random items meeting the criteria.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let odds = my_bag.at_most(0, 3, |v| v % 2 == 0);
+    /// assert!(0.07 < odds && odds < 0.09);
+    /// ```
+    pub fn at_most<F>(&self, count: usize, sample_size: usize, f: F) -> f64 where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync {
+        self.sample(sample_size, |values| values.into_iter().filter(|v| f(v)).count() <= count)
+    }
+
+    /// Simulates how many of `sample_size` random items meet the criteria, returning the whole
+    /// distribution of the count.
+    ///
+    /// The odds of [`at_least`](#method.at_least), [`exactly`](#method.exactly) and
+    /// [`at_most`](#method.at_most) any count can all be read off it without simulating again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let evens = my_bag.count_in_sample(3, |v| v % 2 == 0);
+    /// assert!(0.49 < evens.at_least(&2) && evens.at_least(&2) < 0.51);
+    /// assert!(0.07 < evens.probability(&3) && evens.probability(&3) < 0.09);
+    /// assert!(0.07 < evens.at_most(&0) && evens.at_most(&0) < 0.09);
+    /// ```
+    pub fn count_in_sample<F>(&self, sample_size: usize, f: F) -> Distribution<usize> where
+        F: Fn(&T) -> bool {
+        let mut counts = vec![0u64; sample_size + 1];
+        let trials = self.settings().repeat(|rng| {
+            let sample = draw_sample(rng, &self.items, sample_size);
+            counts[sample.into_iter().filter(|v| f(v)).count()] += 1;
+        });
+        Distribution::from_counts(counts.into_iter().enumerate().collect(), trials)
+    }
+}
@@ -0,0 +1,188 @@
+use bag::{draw_sample, pick_one, Bag};
+use budget::Budget;
+use engine::Settings;
+use result::SimulationResult;
+use rng::SimRng;
+
+/// Largest `r_hat` considered converged, the usual Gelman-Rubin cutoff.
+const MAX_R_HAT: f64 = 1.1;
+
+/// How consistent independent replications of the same estimate are, see
+/// [`Bag::diagnose`](struct.Bag.html#method.diagnose).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostics {
+    /// The result of every replication.
+    pub replications: Vec<SimulationResult>,
+    /// All replications merged into one result.
+    pub pooled: SimulationResult,
+    /// Standard deviation of the replications' estimates.
+    pub spread: f64,
+    /// A Gelman-Rubin style ratio of the total variance to the variance within replications.
+    ///
+    /// Close to 1 when the replications agree, and infinite when every replication got a
+    /// certain result (never or always meeting the criteria) but they disagree.
+    pub r_hat: f64
+}
+
+/// The estimates of independent replications of the same query, see
+/// [`Bag::replicate`](struct.Bag.html#method.replicate).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replicates {
+    /// The seed of every replication, so any of them can be rerun with `Bag::set_seed`.
+    pub seeds: Vec<u64>,
+    /// The probability estimated by every replication, in the same order as `seeds`.
+    pub estimates: Vec<f64>,
+    pub mean: f64,
+    /// Sample standard deviation of the estimates.
+    pub spread: f64,
+    pub min: f64,
+    pub max: f64
+}
+
+impl Replicates {
+
+    fn new(seeds: Vec<u64>, estimates: Vec<f64>) -> Self {
+        let count = estimates.len() as f64;
+        let mean = estimates.iter().sum::<f64>() / count;
+        let spread = if estimates.len() > 1 {
+            (estimates.iter().map(|e| (e - mean) * (e - mean)).sum::<f64>() / (count - 1.0)).sqrt()
+        } else {
+            0.0
+        };
+        let min = estimates.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = estimates.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        Replicates { seeds, estimates, mean, spread, min, max }
+    }
+}
+
+impl Diagnostics {
+
+    /// Compares the results of independent replications of the same query.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than two replications, or they didn't all run the same number of trials.
+    pub fn from_results(replications: Vec<SimulationResult>) -> Self {
+        assert!(replications.len() >= 2, "at least two replications are needed");
+        let n = replications[0].trials as f64;
+        assert!(replications.iter().all(|r| r.trials as f64 == n), "replications must run the same number of trials");
+        let m = replications.len() as f64;
+        let pooled: SimulationResult = replications.iter().sum();
+        let estimates: Vec<f64> = replications.iter().map(|r| r.probability()).collect();
+        let mean = pooled.probability();
+        let between = estimates.iter().map(|p| (p - mean) * (p - mean)).sum::<f64>() / (m - 1.0);
+        // The variance of every replication's trials, corrected for the sample
+        let within = if n > 1.0 {
+            estimates.iter().map(|p| p * (1.0 - p) * n / (n - 1.0)).sum::<f64>() / m
+        } else {
+            0.0
+        };
+        let r_hat = if within > 0.0 {
+            (((n - 1.0) / n * within + between) / within).sqrt()
+        } else if between > 0.0 {
+            f64::INFINITY
+        } else {
+            1.0
+        };
+        Diagnostics { replications, pooled, spread: between.sqrt(), r_hat }
+    }
+
+    /// Whether the replications agree, with `r_hat` below 1.1 and estimates spread by at most `tolerance`.
+    pub fn is_converged(&self, tolerance: f64) -> bool {
+        self.r_hat < MAX_R_HAT && self.spread <= tolerance
+    }
+}
+
+/// Runs `count` independent replications of `trial` that all run the same number of trials, as
+/// `Diagnostics::from_results` needs. Early stopping is off, the first replication spends the
+/// budget and the others run as many trials as it did.
+fn replicate_runs<F>(settings: &Settings, count: usize, trial: F) -> Vec<SimulationResult> where
+    F: Fn(&mut SimRng) -> bool {
+    let mut results: Vec<SimulationResult> = Vec::with_capacity(count);
+    for mut settings in settings.independent(count) {
+        settings.early_stopping = false;
+        if let Some(first) = results.first() {
+            settings.budget = Some(Budget::Trials(first.trials));
+        }
+        results.push(settings.run(&trial));
+    }
+    results
+}
+
+impl<T> Bag<T> {
+
+    /// Runs `replications` independent replications of [`one`](#method.one), each with the bag's
+    /// budget and a different seed, to check whether the simulation budget gives stable results.
+    ///
+    /// Early stopping is ignored, and with a time or precision budget the replications after the
+    /// first run as many trials as the first did, so they can be compared. Seeded bags replicate
+    /// reproducibly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_max_sims(10_000);
+    /// let diagnostics = my_bag.diagnose(4, |v| v % 2 == 0);
+    /// assert_eq!(diagnostics.pooled.trials, 40_000);
+    /// assert!(diagnostics.is_converged(0.02));
+    /// ```
+    pub fn diagnose<F>(&self, replications: usize, f: F) -> Diagnostics where
+        F: Fn(&T) -> bool {
+        Diagnostics::from_results(replicate_runs(&self.settings(), replications, |rng| f(pick_one(rng, &self.items))))
+    }
+
+    /// Runs [`one`](#method.one) `replications` times, each with a distinct seed, and returns every
+    /// estimate with their spread, to check empirically how stable the current `max_sims` is.
+    ///
+    /// The seeds are drawn from the bag's seed if it has one, so seeded bags replicate reproducibly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `replications` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_max_sims(1_000);
+    /// let replicates = my_bag.replicate(5, |v| v % 2 == 0);
+    /// assert_eq!(replicates.estimates.len(), 5);
+    /// assert!(replicates.spread < 0.05);
+    ///
+    /// // Rerun the replication with the highest estimate
+    /// let highest = replicates.estimates.iter().position(|e| *e == replicates.max).unwrap();
+    /// my_bag.set_seed(replicates.seeds[highest]);
+    /// assert_eq!(my_bag.one(|v| v % 2 == 0), replicates.max);
+    /// ```
+    pub fn replicate<F>(&self, replications: usize, f: F) -> Replicates where
+        F: Fn(&T) -> bool {
+        assert!(replications > 0, "replications must be positive");
+        let settings = self.settings().independent(replications);
+        let estimates = settings.iter()
+            .map(|settings| settings.run(|rng| f(pick_one(rng, &self.items))).probability())
+            .collect();
+        Replicates::new(settings.iter().map(|s| s.seed.unwrap()).collect(), estimates)
+    }
+
+    /// Like [`replicate`](#method.replicate), but replicates [`sample`](#method.sample).
+    pub fn replicate_sample<F>(&self, replications: usize, sample_size: usize, f: F) -> Replicates where
+        F: Fn(Vec<&T>) -> bool {
+        assert!(replications > 0, "replications must be positive");
+        let settings = self.settings().independent(replications);
+        let estimates = settings.iter()
+            .map(|settings| settings.run(|rng| f(draw_sample(rng, &self.items, sample_size))).probability())
+            .collect();
+        Replicates::new(settings.iter().map(|s| s.seed.unwrap()).collect(), estimates)
+    }
+
+    /// Like [`diagnose`](#method.diagnose), but replicates [`sample`](#method.sample).
+    pub fn diagnose_sample<F>(&self, replications: usize, sample_size: usize, f: F) -> Diagnostics where
+        F: Fn(Vec<&T>) -> bool {
+        Diagnostics::from_results(replicate_runs(&self.settings(), replications, |rng| f(draw_sample(rng, &self.items, sample_size))))
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use bag::Bag;
+use compare::{compare_bags, Comparison};
+use summary::tally;
+
+/// How the share of one key changed between two bags, see [`Bag::diff`](struct.Bag.html#method.diff).
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyChange<K> {
+    pub key: K,
+    /// The share of items with the key in the first bag.
+    pub before: f64,
+    /// The share of items with the key in the second bag.
+    pub after: f64
+}
+
+impl<K> KeyChange<K> {
+
+    /// How much the share went up, negative if it went down.
+    pub fn change(&self) -> f64 {
+        self.after - self.before
+    }
+}
+
+/// How the composition of a bag changed, see [`Bag::diff`](struct.Bag.html#method.diff).
+#[derive(Clone, Debug, PartialEq)]
+pub struct BagDiff<K> {
+    /// Every key of either bag, the largest changes first.
+    pub changes: Vec<KeyChange<K>>,
+    /// The event's probability in the second bag compared with the first, if one was given, see
+    /// [`Bag::diff_with_event`](struct.Bag.html#method.diff_with_event).
+    pub event: Option<Comparison>
+}
+
+impl<K: PartialEq> BagDiff<K> {
+
+    /// Returns the change of `key`, if either bag has it.
+    pub fn get(&self, key: &K) -> Option<&KeyChange<K>> {
+        self.changes.iter().find(|change| change.key == *key)
+    }
+
+    /// The total variation distance between the bags' compositions: the share of items that
+    /// would need a different key to turn one into the other, from 0 for the same shares to 1 for
+    /// no keys in common.
+    pub fn total_variation(&self) -> f64 {
+        self.changes.iter().map(|change| change.change().abs()).sum::<f64>() / 2.0
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Compares the share items of every key make up of this bag with their share of `other`.
+    ///
+    /// Keys are in order of how much their share changed, up or down, the largest first. Useful
+    /// for finding out what changed when iterating on the definition of a population.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let before = Bag::from_counts(vec![("red", 5), ("blue", 5)]);
+    /// let after = Bag::from_counts(vec![("red", 5), ("blue", 3), ("green", 2)]);
+    /// let diff = before.diff(&after, |ball| *ball);
+    /// assert_eq!(diff.changes[0].key, "blue");
+    /// assert!((diff.changes[0].change() + 0.2).abs() < 1e-12);
+    /// assert!((diff.total_variation() - 0.2).abs() < 1e-12);
+    /// assert_eq!(diff.get(&"red").unwrap().change(), 0.0);
+    /// ```
+    pub fn diff<K, F>(&self, other: &Bag<T>, key_fn: F) -> BagDiff<K> where
+        K: Eq + Hash + Clone,
+        F: Fn(&T) -> K {
+        let share = |count: usize, bag: &Bag<T>| count as f64 / bag.items.len() as f64;
+        let before = tally(self.items.iter(), &key_fn);
+        let after = tally(other.items.iter(), &key_fn);
+        let positions: HashMap<K, usize> = before.iter().enumerate().map(|(i, (key, _))| (key.clone(), i)).collect();
+        let mut changes: Vec<KeyChange<K>> = before.into_iter()
+            .map(|(key, count)| KeyChange { key, before: share(count, self), after: 0.0 })
+            .collect();
+        for (key, count) in after {
+            match positions.get(&key) {
+                Some(&i) => changes[i].after = share(count, other),
+                None => changes.push(KeyChange { key, before: 0.0, after: share(count, other) })
+            }
+        }
+        changes.sort_by(|a, b| b.change().abs().partial_cmp(&a.change().abs()).unwrap());
+        BagDiff { changes, event: None }
+    }
+
+    /// Like [`diff`](#method.diff), but also simulates how the probability of criteria being met
+    /// for `sample_size` random items moved, see [`compare_bags`](fn.compare_bags.html).
+    ///
+    /// The `event` compares `other` against this bag, so its `difference` is how much the
+    /// probability went up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let before = Bag::from_counts(vec![("red", 5), ("blue", 5)]);
+    /// let after = Bag::from_counts(vec![("red", 8), ("blue", 2)]);
+    /// let diff = before.diff_with_event(&after, |ball| *ball, 2, |balls| balls.iter().all(|b| **b == "red"));
+    /// let event = diff.event.unwrap();
+    /// // From 10 / 45 to 28 / 45 pairs
+    /// assert!(0.38 < event.difference && event.difference < 0.42);
+    /// assert!(event.is_significant(0.05));
+    /// ```
+    pub fn diff_with_event<K, G, F>(&self, other: &Bag<T>, key_fn: G, sample_size: usize, f: F) -> BagDiff<K> where
+        T: Sync,
+        K: Eq + Hash + Clone,
+        G: Fn(&T) -> K,
+        F: Fn(Vec<&T>) -> bool + Sync {
+        let mut diff = self.diff(other, key_fn);
+        diff.event = Some(compare_bags(other, self, sample_size, f));
+        diff
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use bag::Bag;
+use math::ln_choose;
+use summary::tally;
+
+/// `ln(e^a + e^b)`, without overflowing.
+fn ln_add(a: f64, b: f64) -> f64 {
+    let (high, low) = if a > b { (a, b) } else { (b, a) };
+    if low == f64::NEG_INFINITY { high } else { high + (low - high).exp().ln_1p() }
+}
+
+impl<T> Bag<T> {
+
+    /// Predicts probability of `sample_size` random items all having different keys, the complement
+    /// of any two of them sharing one.
+    ///
+    /// See [`all_distinct_exact`](#method.all_distinct_exact) for the exact answer from the counts of
+    /// every key.
+    ///
+    /// # Examples
+    ///
+    /// The birthday problem: odds 23 people, out of a crowd with 10 people born on every day of
+    /// the year, all have different birthdays:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut crowd = Bag::from_vec((0..3_650).map(|i| i % 365).collect());
+    /// crowd.set_max_sims(10_000);
+    /// let all_different = crowd.all_distinct(23, |birthday| *birthday);
+    /// assert!(0.50 < all_different && all_different < 0.56);
+    /// ```
+    pub fn all_distinct<K, F>(&self, sample_size: usize, key_fn: F) -> f64 where
+        T: Sync,
+        K: Eq + Hash,
+        F: Fn(&T) -> K + Sync {
+        self.sample(sample_size, |values| {
+            let mut seen = HashSet::with_capacity(values.len());
+            values.into_iter().all(|v| seen.insert(key_fn(v)))
+        })
+    }
+
+    /// Computes the exact probability of `sample_size` random items all having different keys, from
+    /// the counts of every key.
+    ///
+    /// That's the number of ways to pick `sample_size` items of different keys, over the number of
+    /// ways to pick any `sample_size` items. Samples larger than the bag are impossible.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let crowd = Bag::from_vec((0..3_650).map(|i| i % 365).collect());
+    /// let all_different = crowd.all_distinct_exact(23, |birthday| *birthday);
+    /// assert!((all_different - 0.5281).abs() < 0.0001);
+    /// ```
+    pub fn all_distinct_exact<K, F>(&self, sample_size: usize, key_fn: F) -> f64 where
+        K: Eq + Hash + Clone,
+        F: Fn(&T) -> K {
+        let len = self.items.len();
+        if sample_size > len {
+            return 0.0;
+        }
+        // ln_ways[j] is the log of the number of ways to pick j items of different keys so far
+        let mut ln_ways = vec![f64::NEG_INFINITY; sample_size + 1];
+        ln_ways[0] = 0.0;
+        for (_, count) in tally(self.items.iter(), key_fn) {
+            let ln_count = (count as f64).ln();
+            for j in (1..sample_size + 1).rev() {
+                ln_ways[j] = ln_add(ln_ways[j], ln_ways[j - 1] + ln_count);
+            }
+        }
+        (ln_ways[sample_size] - ln_choose(len as u64, sample_size as u64)).exp().min(1.0)
+    }
+}
@@ -0,0 +1,101 @@
+use std::fmt::Display;
+
+/// The simulated distribution of an outcome, such as the largest value in a sample.
+///
+/// Outcomes are kept in ascending order with the number of trials that produced them, so the
+/// probabilities of single outcomes or thresholds can be read off without simulating again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution<K> {
+    /// Number of trials run.
+    pub trials: u64,
+    /// Every outcome seen and how many trials produced it, in ascending order.
+    pub outcomes: Vec<(K, u64)>
+}
+
+impl<K: PartialOrd> Distribution<K> {
+
+    /// Builds a distribution from outcome counts, which may be unsorted and contain
+    /// repeated or never seen outcomes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two outcomes can't be compared, such as a NaN.
+    pub(crate) fn from_counts(mut counts: Vec<(K, u64)>, trials: u64) -> Self {
+        counts.retain(|&(_, count)| count > 0);
+        counts.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("outcomes must be comparable"));
+        let mut outcomes: Vec<(K, u64)> = Vec::with_capacity(counts.len());
+        for (outcome, count) in counts {
+            match outcomes.last_mut() {
+                Some(last) if last.0 == outcome => last.1 += count,
+                _ => outcomes.push((outcome, count))
+            }
+        }
+        Distribution { trials, outcomes }
+    }
+
+    /// Sums the probability of every outcome that meets the criteria.
+    fn probability_where<F>(&self, f: F) -> f64 where
+        F: Fn(&K) -> bool {
+        let matching: u64 = self.outcomes.iter().filter(|o| f(&o.0)).map(|o| o.1).sum();
+        matching as f64 / self.trials as f64
+    }
+
+    /// Returns the probability of exactly `outcome`.
+    pub fn probability(&self, outcome: &K) -> f64 {
+        self.probability_where(|o| o == outcome)
+    }
+
+    /// Returns the probability of an outcome of at least `threshold`.
+    pub fn at_least(&self, threshold: &K) -> f64 {
+        self.probability_where(|o| o >= threshold)
+    }
+
+    /// Returns the probability of an outcome of at most `threshold`.
+    pub fn at_most(&self, threshold: &K) -> f64 {
+        self.probability_where(|o| o <= threshold)
+    }
+
+    /// Returns every outcome with its probability, in ascending order.
+    pub fn probabilities(&self) -> Vec<(&K, f64)> {
+        self.outcomes.iter().map(|o| (&o.0, o.1 as f64 / self.trials as f64)).collect()
+    }
+}
+
+/// Partial blocks for the fractional end of a bar, in eighths.
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+impl<K: PartialOrd + Display> Distribution<K> {
+
+    /// Renders the distribution as a terminal bar chart, one line per outcome.
+    ///
+    /// The most likely outcome's bar is `width` characters long. Bars are drawn with Unicode
+    /// block characters, down to an eighth of a character.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let highest = Bag::from_range(1, 7).sample_max(2, |v| *v as f64);
+    /// let chart = highest.render_ascii(40);
+    /// assert_eq!(chart.lines().count(), 5);
+    /// println!("{}", chart);
+    /// ```
+    pub fn render_ascii(&self, width: usize) -> String {
+        let probabilities = self.probabilities();
+        let labels: Vec<String> = probabilities.iter().map(|&(outcome, _)| outcome.to_string()).collect();
+        let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let highest = probabilities.iter().map(|&(_, p)| p).fold(0.0, f64::max);
+        let mut chart = String::new();
+        for (label, &(_, p)) in labels.iter().zip(&probabilities) {
+            let eighths = if highest > 0.0 { (p / highest * width as f64 * 8.0).round() as usize } else { 0 };
+            let mut bar: String = "█".repeat(eighths / 8);
+            if eighths % 8 > 0 {
+                bar.push(EIGHTHS[eighths % 8]);
+            }
+            let padding = width + 1 - bar.chars().count();
+            chart.push_str(&format!("{:>lw$} │{}{}{:>7.2}%\n", label, bar, " ".repeat(padding), p * 100.0, lw = label_width));
+        }
+        chart
+    }
+}
@@ -0,0 +1,64 @@
+use std::hash::Hash;
+
+use bag::{pick_one, Bag};
+use entropy::entropy_bits;
+use summary::tally;
+
+/// Diversity indices of the keys of a bag's items, see
+/// [`Bag::diversity`](struct.Bag.html#method.diversity).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Diversity {
+    /// Simpson's index: the probability two items grabbed with replacement have the same key.
+    pub simpson: f64,
+    /// The Gini–Simpson index, `1 - simpson`: the probability the two items' keys differ.
+    pub gini_simpson: f64,
+    /// The inverse Simpson index, `1 / simpson`: the number of equally common keys that would be as diverse.
+    pub inverse_simpson: f64,
+    /// The Shannon entropy of the keys in bits, see [`Bag::entropy`](struct.Bag.html#method.entropy).
+    pub shannon: f64,
+    /// The simulated probability two items grabbed with replacement have different keys, which
+    /// `gini_simpson` is the exact value of.
+    pub simulated_differ: f64
+}
+
+impl<T> Bag<T> {
+
+    /// Computes how diverse the keys of the bag's items are, with the Simpson family of indices.
+    ///
+    /// The indices are exact, from the counts of every key. Alongside them `max_sims` pairs of items
+    /// are grabbed, to show the Gini–Simpson index is the probability two random draws differ.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bag is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let trees = Bag::from_counts(vec![("oak", 5), ("maple", 3), ("birch", 2)]);
+    /// let diversity = trees.diversity(|t| *t);
+    /// assert!((diversity.gini_simpson - 0.62).abs() < 1e-12);
+    /// assert!((diversity.simulated_differ - 0.62).abs() < 0.01);
+    /// ```
+    pub fn diversity<K, F>(&self, key_fn: F) -> Diversity where
+        K: Eq + Hash + Clone,
+        F: Fn(&T) -> K {
+        assert!(!self.items.is_empty(), "can't measure the diversity of an empty bag");
+        let counts: Vec<f64> = tally(self.items.iter(), &key_fn).into_iter().map(|(_, count)| count as f64).collect();
+        let total = self.items.len() as f64;
+        let simpson: f64 = counts.iter().map(|c| (c / total) * (c / total)).sum();
+        let simulated_differ = self.settings().run(|rng| {
+            let first = key_fn(pick_one(rng, &self.items));
+            first != key_fn(pick_one(rng, &self.items))
+        }).probability();
+        Diversity {
+            simpson,
+            gini_simpson: 1.0 - simpson,
+            inverse_simpson: 1.0 / simpson,
+            shannon: entropy_bits(counts),
+            simulated_differ
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use rand::Rng;
+
+use bag::Bag;
+
+impl<T: Clone> Bag<T> {
+
+    /// Predicts probability of criteria being met after `draws` random draws from a population that
+    /// changes as it's drawn from.
+    ///
+    /// Every simulation starts from a fresh copy of the bag's items. Each draw takes a random item out of
+    /// that working population and hands it to `update`, along with the working population, which may
+    /// add or remove items based on what was drawn (put the item back to draw with replacement). If the
+    /// population runs out the simulation stops drawing early. The criteria is then given the drawn items,
+    /// in order, and what's left of the population.
+    ///
+    /// This covers depletion and contagion style models which a static [`sample`](#method.sample) can't express.
+    ///
+    /// # Examples
+    ///
+    /// A jar of 1 red and 9 blue marbles, where every blue marble drawn is replaced by a red one. Odds
+    /// of drawing at least one red in 3 draws:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut marbles = vec!["red"];
+    /// marbles.extend(vec!["blue"; 9]);
+    /// let jar = Bag::from_vec(marbles);
+    /// let odds = jar.sample_dynamic(3, |drawn, population| {
+    ///     if *drawn == "blue" {
+    ///         population.push("red");
+    ///     }
+    /// }, |drawn, _| drawn.contains(&"red"));
+    /// // 1 - (9/10 * 8/10 * 7/10) = 49.6%
+    /// assert!(0.486 < odds && odds < 0.506);
+    /// ```
+    pub fn sample_dynamic<U, F>(&self, draws: usize, update: U, f: F) -> f64 where
+        U: Fn(&T, &mut Vec<T>),
+        F: Fn(&[T], &[T]) -> bool {
+        self.settings().run(|rng| {
+            let (drawn, population) = draw_dynamic(rng, &self.items, draws, &update);
+            f(&drawn, &population)
+        }).probability()
+    }
+}
+
+/// Draws up to `draws` items one at a time from a working copy of `items`, letting `update` change
+/// the population after each draw. Returns the drawn items in order and what's left.
+pub(crate) fn draw_dynamic<T, R, U>(rng: &mut R, items: &[T], draws: usize, update: &U) -> (Vec<T>, Vec<T>) where
+    T: Clone,
+    R: Rng,
+    U: Fn(&T, &mut Vec<T>) {
+    let mut population: Vec<T> = items.to_vec();
+    let mut drawn: Vec<T> = Vec::with_capacity(draws);
+    for _ in 0..draws {
+        if population.is_empty() {
+            break;
+        }
+        let idx = rng.gen_range(0, population.len());
+        let item = population.swap_remove(idx);
+        update(&item, &mut population);
+        drawn.push(item);
+    }
+    (drawn, population)
+}
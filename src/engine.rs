@@ -0,0 +1,271 @@
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Instant;
+
+
+use bag::get_default_max_sims;
+use budget::Budget;
+use convergence::Tracker;
+use result::SimulationResult;
+use rng::{run_seed, worker_seed, RngKind, SimRng};
+
+/// Simulations always run before early stopping is considered.
+const EARLY_STOP_MIN_SIMS: u64 = 1_000;
+
+/// How often, in simulations, early stopping is considered.
+const EARLY_STOP_CHECK_EVERY: u64 = 500;
+
+/// Early stopping needs the result to be within this distance of 0 or 1...
+const EARLY_STOP_MARGIN: f64 = 0.001;
+
+/// ...with 99.9% confidence.
+const EARLY_STOP_Z: f64 = 3.29;
+
+/// Simulations a worker runs between reporting its counts.
+pub(crate) const CHUNK_SIMS: u64 = 1_000;
+
+/// How a simulation run is configured. Bags and their views build one of these for every run.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Settings {
+    pub max_sims: u32,
+    /// Overrides `max_sims` when set.
+    pub budget: Option<Budget>,
+    pub seed: Option<u64>,
+    pub rng_kind: RngKind,
+    pub early_stopping: bool,
+    pub track_convergence: bool,
+    pub threads: usize
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            max_sims: get_default_max_sims(),
+            budget: None,
+            seed: None,
+            rng_kind: RngKind::default(),
+            early_stopping: false,
+            track_convergence: false,
+            threads: 1
+        }
+    }
+}
+
+impl Settings {
+
+    /// Builds the random number generator for a run.
+    pub(crate) fn rng(&self) -> SimRng {
+        SimRng::new(self.seed, self.rng_kind)
+    }
+
+    /// The budget of a run, `max_sims` trials unless another budget was set.
+    pub(crate) fn budget(&self) -> Budget {
+        self.budget.unwrap_or(Budget::Trials(self.max_sims as u64))
+    }
+
+    /// Sets the budget to `max_sims` trials.
+    pub(crate) fn set_max_sims(&mut self, max_sims: u32) {
+        self.max_sims = max_sims;
+        self.budget = None;
+    }
+
+    /// Sets the budget, see `Bag::set_budget`.
+    pub(crate) fn set_budget(&mut self, budget: Budget) {
+        budget.validate();
+        if let Some(trials) = budget.trials() {
+            self.max_sims = trials.min(u32::MAX as u64) as u32;
+        }
+        self.budget = Some(budget);
+    }
+
+    /// Runs trials until the budget is spent and counts how many of them met the criteria.
+    ///
+    /// With early stopping enabled the run ends as soon as the estimate is overwhelmingly
+    /// close to 0 or 1, and the result is flagged as `decided_early`. With convergence tracking
+    /// enabled the result holds the running estimate along the way.
+    pub(crate) fn run<F>(&self, mut trial: F) -> SimulationResult where
+        F: FnMut(&mut SimRng) -> bool {
+        let budget = self.budget();
+        let started = Instant::now();
+        let mut rng = self.rng();
+        let mut tracker = self.tracker();
+        let (mut successes, mut trials) = (0u64, 0u64);
+        let mut decided_early = false;
+        while !budget.is_spent(successes, trials, started) {
+            if trial(&mut rng) {
+                successes += 1;
+            }
+            trials += 1;
+            if let Some(tracker) = tracker.as_mut() {
+                tracker.record(successes, trials);
+            }
+            if self.early_stopping && is_decided(successes, trials) && !budget.is_spent(successes, trials, started) {
+                decided_early = true;
+                break;
+            }
+        }
+        let mut result = SimulationResult::new(successes, trials);
+        result.decided_early = decided_early;
+        result.convergence = tracker.map(|tracker| tracker.finish(&result));
+        result
+    }
+
+    /// A tracker for the running estimate of a run, if convergence tracking is enabled.
+    fn tracker(&self) -> Option<Tracker> {
+        if self.track_convergence { Some(Tracker::new(self.budget())) } else { None }
+    }
+
+    /// Runs simulations that have no single success to count until the budget is spent, and
+    /// returns how many ran.
+    pub(crate) fn repeat<F>(&self, mut simulation: F) -> u64 where
+        F: FnMut(&mut SimRng) {
+        let budget = self.budget().without_successes();
+        let started = Instant::now();
+        let mut rng = self.rng();
+        let mut trials = 0;
+        while !budget.is_spent(0, trials, started) {
+            simulation(&mut rng);
+            trials += 1;
+        }
+        trials
+    }
+}
+
+impl Settings {
+
+    /// Copies of these settings for `count` runs that are independent of each other, seeded by
+    /// `worker_seed` from this run's seed. Seeded settings give reproducible copies.
+    pub(crate) fn independent(&self, count: usize) -> Vec<Settings> {
+        let seed = run_seed(self.seed);
+        (0..count).map(|i| Settings { seed: Some(worker_seed(seed, i as u64)), ..self.clone() }).collect()
+    }
+
+    /// Like `run`, but splits the trials across `threads` worker threads and merges their counts.
+    ///
+    /// Each worker simulates with its own generator, seeded by `worker_seed`, reports its
+    /// counts over a channel every `CHUNK_SIMS` simulations, and stops early on its own if early
+    /// stopping is enabled.
+    pub(crate) fn run_parallel<F>(&self, trial: F) -> SimulationResult where
+        F: Fn(&mut SimRng) -> bool + Sync {
+        self.run_parallel_with(trial, |_| {})
+    }
+
+    /// Like `run_parallel`, but hands `progress` the result so far after every chunk of
+    /// `CHUNK_SIMS` simulations reported by a worker, and the final result at the end. The running
+    /// estimate of a parallel run is tracked from the chunks.
+    pub(crate) fn run_parallel_with<F, P>(&self, trial: F, mut progress: P) -> SimulationResult where
+        F: Fn(&mut SimRng) -> bool + Sync,
+        P: FnMut(&SimulationResult) {
+        if self.worker_count() <= 1 {
+            let (mut successes, mut trials) = (0u64, 0u64);
+            let result = self.run(|rng| {
+                let met = trial(rng);
+                successes += met as u64;
+                trials += 1;
+                if trials % CHUNK_SIMS == 0 {
+                    progress(&SimulationResult::new(successes, trials));
+                }
+                met
+            });
+            if result.trials % CHUNK_SIMS != 0 || result.decided_early {
+                progress(&result);
+            }
+            return result;
+        }
+        let (sender, receiver) = mpsc::channel();
+        let trial = &trial;
+        let mut tracker = self.tracker();
+        let mut result = thread::scope(|scope| {
+            for worker in self.workers() {
+                let sender = sender.clone();
+                scope.spawn(move || worker.work(trial, &sender));
+            }
+            drop(sender);
+            receiver.iter().fold(SimulationResult::new(0, 0), |total, chunk| {
+                let total = total.merge(&chunk);
+                if let Some(tracker) = tracker.as_mut() {
+                    tracker.record(total.successes, total.trials);
+                }
+                progress(&total);
+                total
+            })
+        });
+        result.convergence = tracker.map(|tracker| tracker.finish(&result));
+        result
+    }
+
+    /// Like `repeat`, but splits the simulations across `threads` worker threads like `run_parallel`,
+    /// and collects what every simulation returned.
+    pub(crate) fn repeat_parallel<F, R>(&self, simulation: F) -> Vec<R> where
+        F: Fn(&mut SimRng) -> R + Sync,
+        R: Send {
+        let collect = |settings: &Settings| {
+            let mut values = Vec::new();
+            settings.repeat(|rng| values.push(simulation(rng)));
+            values
+        };
+        if self.worker_count() <= 1 {
+            return collect(self);
+        }
+        let collect = &collect;
+        thread::scope(|scope| {
+            let handles: Vec<_> = self.workers().into_iter()
+                .map(|worker| scope.spawn(move || collect(&worker)))
+                .collect();
+            handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+        })
+    }
+
+    /// The number of workers of a parallel run, no more than there are trials.
+    fn worker_count(&self) -> usize {
+        let budget = self.budget();
+        let trials = budget.trials().map_or(usize::MAX, |trials| trials.min(usize::MAX as u64) as usize);
+        self.threads.min(trials).max(1)
+    }
+
+    /// The settings of every worker of a parallel run, which split the budget between them.
+    pub(crate) fn workers(&self) -> Vec<Settings> {
+        let threads = self.worker_count();
+        let budget = self.budget();
+        self.independent(threads).into_iter().enumerate().map(|(i, worker)| {
+            Settings { budget: Some(budget.split(threads, i)), threads: 1, ..worker }
+        }).collect()
+    }
+
+    /// Runs the trials of a single worker, sending the counts of every chunk of `CHUNK_SIMS`
+    /// simulations to `sender`. Stops once nobody is listening anymore.
+    pub(crate) fn work<F>(&self, trial: &F, sender: &Sender<SimulationResult>) where
+        F: Fn(&mut SimRng) -> bool {
+        let budget = self.budget();
+        let started = Instant::now();
+        let mut rng = self.rng();
+        let (mut successes, mut trials) = (0u64, 0u64);
+        let mut chunk = SimulationResult::new(0, 0);
+        while !budget.is_spent(successes, trials, started) {
+            if trial(&mut rng) {
+                successes += 1;
+                chunk.successes += 1;
+            }
+            trials += 1;
+            chunk.trials += 1;
+            let spent = budget.is_spent(successes, trials, started);
+            chunk.decided_early = self.early_stopping && !spent && is_decided(successes, trials);
+            if chunk.trials == CHUNK_SIMS || spent || chunk.decided_early {
+                let decided_early = chunk.decided_early;
+                if sender.send(chunk).is_err() || decided_early || spent {
+                    return;
+                }
+                chunk = SimulationResult::new(0, 0);
+            }
+        }
+    }
+}
+
+/// Whether the confidence interval of `successes` in `trials` lies entirely near 0 or near 1.
+fn is_decided(successes: u64, trials: u64) -> bool {
+    if trials < EARLY_STOP_MIN_SIMS || trials % EARLY_STOP_CHECK_EVERY != 0 {
+        return false;
+    }
+    let (low, high) = SimulationResult::new(successes, trials).wilson_interval(EARLY_STOP_Z);
+    high <= EARLY_STOP_MARGIN || low >= 1.0 - EARLY_STOP_MARGIN
+}
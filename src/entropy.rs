@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use bag::{pick_one, Bag};
+use frequency::FrequencyBag;
+use summary::{tally, tally_counts};
+use weighted::CumulativeWeights;
+
+/// The Shannon entropy in bits of the distribution with these counts per outcome.
+pub(crate) fn entropy_bits<I: IntoIterator<Item = f64>>(counts: I) -> f64 {
+    let counts: Vec<f64> = counts.into_iter().filter(|c| *c > 0.0).collect();
+    let total: f64 = counts.iter().sum();
+    counts.iter().map(|c| {
+        let p = c / total;
+        -p * p.log2()
+    }).sum()
+}
+
+impl<T> Bag<T> {
+
+    /// Returns the Shannon entropy, in bits, of the key of a random item grabbed from the bag.
+    ///
+    /// This is how surprising a draw is on average: 0 when every item has the same key, and
+    /// `log2(n)` when each of `n` keys is equally common. It's computed exactly from the counts of
+    /// every key. An empty bag has an entropy of 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let coin_flips = Bag::from_vec(vec!["heads", "tails"]);
+    /// assert_eq!(coin_flips.entropy(|f| *f), 1.0);
+    /// let dice = Bag::from_range(1, 7);
+    /// assert!((dice.entropy(|d| *d) - 6f64.log2()).abs() < 1e-12);
+    /// ```
+    pub fn entropy<K, F>(&self, key_fn: F) -> f64 where
+        K: Eq + Hash + Clone,
+        F: Fn(&T) -> K {
+        entropy_bits(tally(self.items.iter(), key_fn).into_iter().map(|(_, count)| count as f64))
+    }
+
+    /// Estimates the Shannon entropy, in bits, of the key of a random item grabbed from the bag, from
+    /// the keys of simulated grabs.
+    ///
+    /// This is the plug-in estimate, which slightly underestimates the entropy when there are many
+    /// rare keys. Prefer [`entropy`](#method.entropy) for bags that can be counted; this is here to
+    /// check a simulation setup against it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bag is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut dice = Bag::from_range(1, 7);
+    /// dice.set_max_sims(20_000);
+    /// assert!((dice.simulated_entropy(|d| *d) - 6f64.log2()).abs() < 0.01);
+    /// ```
+    pub fn simulated_entropy<K, F>(&self, key_fn: F) -> f64 where
+        K: Eq + Hash,
+        F: Fn(&T) -> K {
+        assert!(!self.items.is_empty(), "can't grab from an empty bag");
+        let mut counts: HashMap<K, f64> = HashMap::new();
+        self.settings().repeat(|rng| *counts.entry(key_fn(pick_one(rng, &self.items))).or_insert(0.0) += 1.0);
+        entropy_bits(counts.into_values())
+    }
+
+    /// Returns the Shannon entropy, in bits, of the key of a random item grabbed from the bag, where
+    /// each item's chance of being grabbed is proportional to its weight, as in
+    /// [`one_weighted_by`](#method.one_weighted_by).
+    ///
+    /// It's computed exactly from the total weight of every key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a weight is negative or not finite, or if every weight is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let regions = Bag::from_vec(vec![("Pittsburgh", 300), ("Denver", 700)]);
+    /// let entropy = regions.entropy_weighted_by(|r| r.1 as f64, |r| r.0);
+    /// assert!((entropy - 0.881).abs() < 0.001);
+    /// ```
+    pub fn entropy_weighted_by<K, W, F>(&self, weight_fn: W, key_fn: F) -> f64 where
+        K: Eq + Hash,
+        W: Fn(&T) -> f64,
+        F: Fn(&T) -> K {
+        let mut weights: HashMap<K, f64> = HashMap::new();
+        let mut total = 0.0;
+        for item in self.items.iter() {
+            let weight = weight_fn(item);
+            assert!(weight >= 0.0 && weight.is_finite(), "weights must be finite and non-negative, got {}", weight);
+            total += weight;
+            *weights.entry(key_fn(item)).or_insert(0.0) += weight;
+        }
+        assert!(total > 0.0, "at least one weight must be positive");
+        entropy_bits(weights.into_values())
+    }
+
+    /// Estimates the Shannon entropy, in bits, of the key of a random item grabbed from the bag
+    /// proportionally to its weight, from the keys of simulated grabs. See
+    /// [`simulated_entropy`](#method.simulated_entropy) and
+    /// [`entropy_weighted_by`](#method.entropy_weighted_by) for the exact answer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a weight is negative or not finite, or if every weight is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut regions = Bag::from_vec(vec![("Pittsburgh", 300), ("Denver", 700)]);
+    /// regions.set_max_sims(20_000);
+    /// let entropy = regions.simulated_entropy_weighted_by(|r| r.1 as f64, |r| r.0);
+    /// assert!((entropy - 0.881).abs() < 0.01);
+    /// ```
+    pub fn simulated_entropy_weighted_by<K, W, F>(&self, weight_fn: W, key_fn: F) -> f64 where
+        K: Eq + Hash,
+        W: Fn(&T) -> f64,
+        F: Fn(&T) -> K {
+        let weights = CumulativeWeights::new(self.items.iter().map(weight_fn));
+        let mut counts: HashMap<K, f64> = HashMap::new();
+        self.settings().repeat(|rng| *counts.entry(key_fn(&self.items[weights.pick(rng)])).or_insert(0.0) += 1.0);
+        entropy_bits(counts.into_values())
+    }
+}
+
+impl<T> FrequencyBag<T> {
+
+    /// Returns the Shannon entropy, in bits, of the key of a random individual grabbed from the
+    /// population, computed exactly from the row counts. See [`Bag::entropy`](struct.Bag.html#method.entropy).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let cities = Bag::from_frequencies(vec![("Pittsburgh", 300), ("Denver", 700)]);
+    /// let entropy = cities.entropy(|c| *c);
+    /// assert!((entropy - 0.881).abs() < 0.001);
+    /// ```
+    pub fn entropy<K, F>(&self, key_fn: F) -> f64 where
+        K: Eq + Hash + Clone,
+        F: Fn(&T) -> K {
+        let counts = tally_counts(self.rows.iter().zip(self.row_counts()), key_fn);
+        entropy_bits(counts.into_iter().map(|(_, count)| count as f64))
+    }
+}
@@ -0,0 +1,98 @@
+use std::rc::Rc;
+
+use bag::{draw_sample, pick_one, Bag};
+use result::SimulationResult;
+use rng::SimRng;
+
+/// An estimate that can be refined with more simulations on demand, see
+/// [`Bag::estimator`](struct.Bag.html#method.estimator).
+///
+/// The random number generator carries on between calls, so a seeded estimator that runs 1,000
+/// simulations twice gets the same result as one that runs 2,000 at once.
+pub struct Estimator<'a> {
+    trial: Rc<dyn Fn(&mut SimRng) -> bool + 'a>,
+    rng: SimRng,
+    result: SimulationResult
+}
+
+impl<'a> Estimator<'a> {
+
+    fn new<F>(rng: SimRng, trial: F) -> Self where
+        F: Fn(&mut SimRng) -> bool + 'a {
+        Estimator { trial: Rc::new(trial), rng, result: SimulationResult::new(0, 0) }
+    }
+
+    /// Runs `sims` more simulations, and returns the result of all simulations so far.
+    pub fn run_more(&mut self, sims: u32) -> SimulationResult {
+        let mut successes = 0;
+        for _ in 0..sims {
+            if (self.trial)(&mut self.rng) {
+                successes += 1;
+            }
+        }
+        self.result = self.result.merge(&SimulationResult::new(successes, sims as u64));
+        self.result.clone()
+    }
+
+    /// The result of all simulations so far.
+    pub fn result(&self) -> SimulationResult {
+        self.result.clone()
+    }
+
+    /// Copies the estimator, including its simulations so far and the position of its random number
+    /// generator, so two branches of an analysis can carry on from the same point independently.
+    ///
+    /// A seeded estimator and its fork get the same results from the same calls. Unseeded ones
+    /// share the thread's generator, so they only start from the same simulations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_seed(4);
+    /// let mut estimator = my_bag.estimator(|v| v % 2 == 0);
+    /// estimator.run_more(1_000);
+    /// let mut branch = estimator.fork();
+    /// assert_eq!(branch.result(), estimator.result());
+    /// assert_eq!(branch.run_more(500), estimator.run_more(500));
+    /// ```
+    pub fn fork(&self) -> Estimator<'a> {
+        Estimator { trial: Rc::clone(&self.trial), rng: self.rng.clone(), result: self.result.clone() }
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Creates an `Estimator` of the odds of `f` (as with [`one`](#method.one)), which starts out with
+    /// no simulations and accumulates them over calls to
+    /// [`run_more`](struct.Estimator.html#method.run_more).
+    ///
+    /// # Examples
+    ///
+    /// Refining an estimate until it's within 1%:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let mut estimator = my_bag.estimator(|v| v % 2 == 0);
+    /// let mut result = estimator.run_more(1_000);
+    /// while result.std_error() * 1.96 > 0.01 {
+    ///     result = estimator.run_more(1_000);
+    /// }
+    /// assert!(result.trials >= 9_000);
+    /// assert_eq!(estimator.result(), result);
+    /// ```
+    pub fn estimator<'a, F>(&'a self, f: F) -> Estimator<'a> where
+        F: Fn(&T) -> bool + 'a {
+        Estimator::new(self.settings().rng(), move |rng| f(pick_one(rng, &self.items)))
+    }
+
+    /// Like [`estimator`](#method.estimator), but for samples as with [`sample`](#method.sample).
+    pub fn sample_estimator<'a, F>(&'a self, sample_size: usize, f: F) -> Estimator<'a> where
+        F: Fn(Vec<&T>) -> bool + 'a {
+        Estimator::new(self.settings().rng(), move |rng| f(draw_sample(rng, &self.items, sample_size)))
+    }
+}
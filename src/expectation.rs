@@ -0,0 +1,65 @@
+use bag::{draw_sample, Bag};
+
+/// The estimated expectation of a value given a condition, see
+/// [`Bag::estimate_given`](struct.Bag.html#method.estimate_given).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConditionalEstimate {
+    /// The mean value of the samples that met the condition, NaN if none did.
+    pub mean: f64,
+    /// The standard error of `mean`.
+    pub std_error: f64,
+    /// The number of samples that met the condition.
+    pub accepted: u64,
+    /// The number of samples drawn.
+    pub trials: u64
+}
+
+impl ConditionalEstimate {
+
+    /// The share of samples that met the condition, an estimate of its probability.
+    pub fn acceptance_rate(&self) -> f64 {
+        self.accepted as f64 / self.trials as f64
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Estimates the expected value of `value_fn` over `sample_size` random items, given that they
+    /// meet `condition_fn`.
+    ///
+    /// Samples that don't meet the condition are rejected, so a rare condition leaves few samples to
+    /// average: check the `accepted` count or the
+    /// [`acceptance_rate`](struct.ConditionalEstimate.html#method.acceptance_rate).
+    ///
+    /// # Examples
+    ///
+    /// Expected sum of 2 of 6 cards numbered 1 - 6, given neither is the 6:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let cards = Bag::from_range(1, 7);
+    /// let estimate = cards.estimate_given(2, |s| s.iter().map(|v| **v as f64).sum(), |s| s.iter().all(|v| **v != 6));
+    /// assert!((estimate.mean - 6.0).abs() < 0.05);
+    /// // 10 of the 15 pairs have no 6
+    /// assert!(0.65 < estimate.acceptance_rate() && estimate.acceptance_rate() < 0.68);
+    /// ```
+    pub fn estimate_given<V, C>(&self, sample_size: usize, value_fn: V, condition_fn: C) -> ConditionalEstimate where
+        V: Fn(&[&T]) -> f64,
+        C: Fn(&[&T]) -> bool {
+        let (mut accepted, mut sum, mut sum_of_squares) = (0u64, 0.0, 0.0);
+        let trials = self.settings().repeat(|rng| {
+            let sample = draw_sample(rng, &self.items, sample_size);
+            if condition_fn(&sample) {
+                let value = value_fn(&sample);
+                accepted += 1;
+                sum += value;
+                sum_of_squares += value * value;
+            }
+        });
+        let n = accepted as f64;
+        let mean = sum / n;
+        let variance = if accepted > 1 { ((sum_of_squares - n * mean * mean) / (n - 1.0)).max(0.0) } else { f64::NAN };
+        ConditionalEstimate { mean, std_error: (variance / n).sqrt(), accepted, trials }
+    }
+}
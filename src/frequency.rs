@@ -0,0 +1,270 @@
+use std::collections::HashSet;
+use std::io::{self, BufRead};
+
+use rand::{seq, Rng};
+
+use bag::Bag;
+use budget::Budget;
+use engine::Settings;
+use result::SimulationResult;
+use weighted::AliasTable;
+
+/// A population stored as rows that each stand for `count` identical individuals, see
+/// [`Bag::from_frequencies`](struct.Bag.html#method.from_frequencies).
+///
+/// Simulations pick individuals, not rows, so a row with a count of 1,000 is a thousand times
+/// more likely to be grabbed than a row with a count of 1, without storing it a thousand times.
+pub struct FrequencyBag<T> {
+    pub rows: Vec<T>,
+    /// The running total of the row counts, so row `i` covers individuals `ends[i - 1]..ends[i]`.
+    ends: Vec<u64>,
+    /// Picks rows in constant time once built, see [`precompute`](#method.precompute).
+    alias: Option<AliasTable>,
+    settings: Settings
+}
+
+impl<T> Bag<T> {
+
+    /// Builds a population from `(item, count)` rows, each standing for `count` individuals.
+    ///
+    /// Rows with a count of zero are kept, but never picked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let households = Bag::from_frequencies(vec![("one person", 6), ("couple", 3), ("family", 1)]);
+    /// assert_eq!(households.population(), 10);
+    /// let odds = households.one(|h| *h == "one person");
+    /// assert!(0.59 < odds && odds < 0.61);
+    /// ```
+    pub fn from_frequencies<I>(rows: I) -> FrequencyBag<T> where
+        I: IntoIterator<Item = (T, u64)> {
+        let mut total: u64 = 0;
+        let (rows, ends) = rows.into_iter()
+            .map(|(item, count)| {
+                total += count;
+                (item, total)
+            })
+            .unzip();
+        FrequencyBag { rows, ends, alias: None, settings: Settings::default() }
+    }
+
+    /// Loads a population from CSV where the `count_column` column holds how many individuals each
+    /// row stands for, see [`from_frequencies`](#method.from_frequencies).
+    ///
+    /// The first line is the header. `row_to_item` gets every field of a row, including the count.
+    /// Fields may be quoted, but each record has to be on a single line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let csv = "region,age,people\nnorth,young,1200\nnorth,old,800\nsouth,young,2000\n";
+    /// let census = Bag::from_weighted_csv(csv.as_bytes(), "people", |fields| fields[0] == "north").unwrap();
+    /// assert_eq!(census.population(), 4000);
+    /// let odds_of_north = census.one(|is_north| *is_north);
+    /// assert!(0.49 < odds_of_north && odds_of_north < 0.51);
+    /// ```
+    pub fn from_weighted_csv<R, F>(reader: R, count_column: &str, row_to_item: F) -> io::Result<FrequencyBag<T>> where
+        R: BufRead,
+        F: Fn(&[&str]) -> T {
+        let mut lines = reader.lines();
+        let header = match lines.next() {
+            Some(line) => parse_csv_line(&line?),
+            None => return Err(invalid_data("the CSV has no header".to_string()))
+        };
+        let count_index = match header.iter().position(|name| name == count_column) {
+            Some(index) => index,
+            None => return Err(invalid_data(format!("the CSV has no \"{}\" column", count_column)))
+        };
+        let mut rows = Vec::new();
+        for (number, line) in lines.enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let fields = parse_csv_line(&line);
+            let fields: Vec<&str> = fields.iter().map(|field| field.as_str()).collect();
+            let count = fields.get(count_index)
+                .and_then(|count| count.trim().parse::<u64>().ok())
+                .ok_or_else(|| invalid_data(format!("line {} has no valid count", number + 2)))?;
+            rows.push((row_to_item(&fields), count));
+        }
+        Ok(Bag::from_frequencies(rows))
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Splits a CSV line into its fields, undoing the quoting of `report::csv_field`.
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c)
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+impl<T> FrequencyBag<T> {
+
+    /// Returns the number of individuals in the population, the sum of the row counts.
+    pub fn population(&self) -> u64 {
+        self.ends.last().cloned().unwrap_or(0)
+    }
+
+    /// The count of every row, in order.
+    pub(crate) fn row_counts(&self) -> impl Iterator<Item = u64> + '_ {
+        self.ends.iter().scan(0, |previous, &end| {
+            let count = end - *previous;
+            *previous = end;
+            Some(count)
+        })
+    }
+
+    /// Returns the row the individual at `index` belongs to.
+    fn individual(&self, index: u64) -> &T {
+        &self.rows[self.ends.partition_point(|&end| end <= index)]
+    }
+
+    /// Builds a Walker alias table of the rows, so every later grab picks a row in constant time
+    /// rather than searching the running totals of the counts.
+    ///
+    /// Worth it for populations of many rows that will be simulated many times. Samples use the
+    /// table when they take at most half the population, redrawing any individual picked twice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the population is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut ages = Bag::from_frequencies((0..100).map(|age| (age, 100 - age as u64)));
+    /// ages.precompute();
+    /// let odds = ages.one(|age| *age < 50);
+    /// // 3775 of 5050 people
+    /// assert!(0.74 < odds && odds < 0.76);
+    /// ```
+    pub fn precompute(&mut self) {
+        let counts: Vec<f64> = self.row_counts().map(|count| count as f64).collect();
+        self.alias = Some(AliasTable::new(&counts));
+    }
+
+    /// Picks a random individual's row from the alias table, and the individual's index.
+    fn pick_individual<R: Rng>(&self, alias: &AliasTable, rng: &mut R) -> (usize, u64) {
+        let row = alias.pick(rng);
+        let start = if row == 0 { 0 } else { self.ends[row - 1] };
+        (row, rng.gen_range(start, self.ends[row]))
+    }
+
+    /// Predicts probability of criteria being met for the first random individual grabbed from the population.
+    ///
+    /// See [`Bag::one`](struct.Bag.html#method.one).
+    pub fn one<F>(&self, f: F) -> f64 where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync {
+        self.one_detailed(f).probability()
+    }
+
+    /// Like [`one`](#method.one), but returns the full `SimulationResult`.
+    pub fn one_detailed<F>(&self, f: F) -> SimulationResult where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync {
+        if let Some(ref alias) = self.alias {
+            return self.settings.run_parallel(|rng| f(&self.rows[alias.pick(rng)]));
+        }
+        let population = self.population();
+        self.settings.run_parallel(|rng| f(self.individual(rng.gen_range(0, population))))
+    }
+
+    /// Predicts probability of criteria being met for the first `sample_size` random individuals
+    /// grabbed from the population. Several of them may belong to the same row.
+    ///
+    /// See [`Bag::sample`](struct.Bag.html#method.sample).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// // Both of the only two red balls
+    /// let balls = Bag::from_frequencies(vec![("red", 2), ("blue", 2)]);
+    /// let odds = balls.sample(2, |picked| picked.iter().all(|b| **b == "red"));
+    /// assert!(0.15 < odds && odds < 0.18);
+    /// ```
+    pub fn sample<F>(&self, sample_size: usize, f: F) -> f64 where
+        T: Sync,
+        F: Fn(Vec<&T>) -> bool + Sync {
+        self.sample_detailed(sample_size, f).probability()
+    }
+
+    /// Like [`sample`](#method.sample), but returns the full `SimulationResult`.
+    pub fn sample_detailed<F>(&self, sample_size: usize, f: F) -> SimulationResult where
+        T: Sync,
+        F: Fn(Vec<&T>) -> bool + Sync {
+        let population = self.population() as usize;
+        assert!(sample_size <= population, "sample_size {} is larger than the population of {}", sample_size, population);
+        match self.alias {
+            Some(ref alias) if sample_size <= population / 2 => {
+                return self.settings.run_parallel(|rng| {
+                    let mut seen = HashSet::with_capacity(sample_size);
+                    let mut picked = Vec::with_capacity(sample_size);
+                    while picked.len() < sample_size {
+                        let (row, index) = self.pick_individual(alias, rng);
+                        if seen.insert(index) {
+                            picked.push(&self.rows[row]);
+                        }
+                    }
+                    f(picked)
+                });
+            }
+            _ => {}
+        }
+        self.settings.run_parallel(|rng| {
+            f(seq::sample_indices(rng, population, sample_size).into_iter()
+                .map(|index| self.individual(index as u64))
+                .collect())
+        })
+    }
+
+    /// Set the maximum amount of simulations to run when generating probabilities.
+    pub fn set_max_sims(&mut self, max_sims: u32) {
+        self.settings.set_max_sims(max_sims);
+    }
+
+    /// Set how much to simulate, see [`Bag::set_budget`](struct.Bag.html#method.set_budget).
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.settings.set_budget(budget);
+    }
+
+    /// Seed the random number generator so simulations are reproducible, see
+    /// [`Bag::set_seed`](struct.Bag.html#method.set_seed).
+    pub fn set_seed(&mut self, seed: u64) {
+        self.settings.seed = Some(seed);
+    }
+
+    /// Split simulations across `threads` worker threads, see
+    /// [`Bag::set_parallelism`](struct.Bag.html#method.set_parallelism).
+    pub fn set_parallelism(&mut self, threads: usize) {
+        self.settings.threads = threads.max(1);
+    }
+}
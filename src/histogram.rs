@@ -0,0 +1,88 @@
+use bag::{draw_sample, Bag};
+
+/// The simulated distribution of a numeric statistic, bucketed into bins, see
+/// [`Bag::histogram`](struct.Bag.html#method.histogram).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    /// The bin edges, in ascending order. Bin `i` holds values from `edges[i]` up to but not
+    /// including `edges[i + 1]`, except that the last bin includes its upper edge.
+    pub edges: Vec<f64>,
+    /// How many trials had their value in each bin.
+    pub counts: Vec<u64>,
+    /// How many trials had a value below the first edge.
+    pub below: u64,
+    /// How many trials had a value above the last edge.
+    pub above: u64,
+    pub trials: u64
+}
+
+impl Histogram {
+
+    /// The probability of the value landing in each bin.
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.counts.iter().map(|&count| count as f64 / self.trials as f64).collect()
+    }
+
+    /// Every bin as `(low, high, probability)`.
+    pub fn bins(&self) -> Vec<(f64, f64, f64)> {
+        self.edges.windows(2).zip(self.probabilities()).map(|(edges, p)| (edges[0], edges[1], p)).collect()
+    }
+
+    /// The bin `value` falls in, if any.
+    fn bin(&self, value: f64) -> Option<usize> {
+        let last = self.edges.len() - 1;
+        if value == self.edges[last] {
+            return Some(last - 1);
+        }
+        match self.edges.partition_point(|&edge| edge <= value) {
+            0 => None,
+            i if i > last => None,
+            i => Some(i - 1)
+        }
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Simulates the distribution of a numeric statistic of `sample_size` random items, counting
+    /// how often it lands in each of the bins between consecutive `edges`.
+    ///
+    /// Values outside of the edges are counted separately, as `below` and `above`, so the bins'
+    /// probabilities add up to less than 1 if the edges don't cover every value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are fewer than 2 edges, the edges aren't increasing, or a value is NaN.
+    ///
+    /// # Examples
+    ///
+    /// The sum of 2 cards numbered 1 - 6, from 3 to 11:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let cards = Bag::from_range(1, 7);
+    /// let sums = cards.histogram(2, |s| s.iter().map(|v| **v as f64).sum(), &[3.0, 6.0, 9.0, 11.0]);
+    /// let p = sums.probabilities();
+    /// // 4 of the 15 pairs sum to 3 - 5, 7 to 6 - 8 and 4 to 9 - 11
+    /// assert!(0.25 < p[0] && p[0] < 0.28);
+    /// assert!(0.45 < p[1] && p[1] < 0.49);
+    /// assert_eq!(sums.below + sums.above, 0);
+    /// ```
+    pub fn histogram<F>(&self, sample_size: usize, value_fn: F, edges: &[f64]) -> Histogram where
+        F: Fn(&[&T]) -> f64 {
+        assert!(edges.len() >= 2, "at least 2 edges are needed");
+        assert!(edges.windows(2).all(|w| w[0] < w[1]), "edges must be increasing");
+        let mut histogram = Histogram { edges: edges.to_vec(), counts: vec![0; edges.len() - 1], below: 0, above: 0, trials: 0 };
+        histogram.trials = self.settings().repeat(|rng| {
+            let value = value_fn(&draw_sample(rng, &self.items, sample_size));
+            assert!(!value.is_nan(), "values must not be NaN");
+            match histogram.bin(value) {
+                Some(i) => histogram.counts[i] += 1,
+                None if value < histogram.edges[0] => histogram.below += 1,
+                None => histogram.above += 1
+            }
+        });
+        histogram
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use rand::Rng;
+
+use bag::Bag;
+
+/// Independence tests reject below this p-value.
+const INDEPENDENCE_ALPHA: f64 = 0.05;
+
+/// The outcome of [`Bag::independence_test`](struct.Bag.html#method.independence_test).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IndependenceTest {
+    /// Pearson's chi-square statistic of the attributes' contingency table.
+    pub chi_square: f64,
+    /// Cramér's V, the strength of the association: 0 for none, up to 1 when either attribute
+    /// determines the other.
+    pub cramers_v: f64,
+    /// The fraction of random pairings of the attributes with a chi-square statistic at least as large.
+    pub p_value: f64,
+    /// How many random pairings were simulated.
+    pub permutations: u64,
+    /// Whether the p-value is at least 0.05, so independence can't be rejected.
+    pub is_independent: bool
+}
+
+/// Numbers the distinct keys of `items` from 0, in order of appearance. Returns the numbers and how
+/// many distinct keys there are.
+fn codes<T, K, F>(items: &[T], key_fn: F) -> (Vec<usize>, usize) where
+    K: Eq + Hash,
+    F: Fn(&T) -> K {
+    let mut seen = HashMap::new();
+    let codes = items.iter().map(|item| {
+        let next = seen.len();
+        *seen.entry(key_fn(item)).or_insert(next)
+    }).collect();
+    (codes, seen.len())
+}
+
+/// Pearson's chi-square statistic for the contingency table of paired codes.
+fn chi_square(a: &[usize], rows: usize, b: &[usize], columns: usize) -> f64 {
+    let mut table = vec![0u64; rows * columns];
+    let (mut row_totals, mut column_totals) = (vec![0u64; rows], vec![0u64; columns]);
+    for (&i, &j) in a.iter().zip(b) {
+        table[i * columns + j] += 1;
+        row_totals[i] += 1;
+        column_totals[j] += 1;
+    }
+    let n = a.len() as f64;
+    let mut statistic = 0.0;
+    for (i, &row_total) in row_totals.iter().enumerate() {
+        for (j, &column_total) in column_totals.iter().enumerate() {
+            let expected = row_total as f64 * column_total as f64 / n;
+            let difference = table[i * columns + j] as f64 - expected;
+            statistic += difference * difference / expected;
+        }
+    }
+    statistic
+}
+
+impl<T> Bag<T> {
+
+    /// Tests whether two attributes of the bag's items are independent, with a permutation test.
+    ///
+    /// The chi-square statistic of the items' attributes is compared with those of the bag's budget of
+    /// random re-pairings of the same attributes, which are independent by construction. Unlike the
+    /// chi-square distribution, this stays accurate for small bags and rare attribute values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bag is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut numbers = Bag::from_range(0, 60);
+    /// numbers.set_max_sims(2_000);
+    /// numbers.set_seed(1);
+    /// // Parity and the remainder of 3 are unrelated, parity and the remainder of 4 are not
+    /// assert!(numbers.independence_test(|v| v % 2, |v| v % 3).is_independent);
+    /// let related = numbers.independence_test(|v| v % 2, |v| v % 4);
+    /// assert!(!related.is_independent);
+    /// assert!((related.cramers_v - 1.0).abs() < 1e-9);
+    /// ```
+    pub fn independence_test<A, B, FA, FB>(&self, attribute_a: FA, attribute_b: FB) -> IndependenceTest where
+        A: Eq + Hash,
+        B: Eq + Hash,
+        FA: Fn(&T) -> A,
+        FB: Fn(&T) -> B {
+        assert!(!self.items.is_empty(), "can't test an empty bag");
+        let (a, rows) = codes(&self.items, attribute_a);
+        let (mut b, columns) = codes(&self.items, attribute_b);
+        if rows.min(columns) == 1 {
+            // A constant attribute is trivially independent of anything
+            return IndependenceTest { chi_square: 0.0, cramers_v: 0.0, p_value: 1.0, permutations: 0, is_independent: true };
+        }
+        let observed = chi_square(&a, rows, &b, columns);
+        let mut as_large = 0u64;
+        let permutations = self.settings().repeat(|rng| {
+            rng.shuffle(&mut b);
+            // Allow for rounding, permutations with the same table have the same statistic
+            if chi_square(&a, rows, &b, columns) >= observed * (1.0 - 1e-9) {
+                as_large += 1;
+            }
+        });
+        let p_value = (as_large + 1) as f64 / (permutations as f64 + 1.0);
+        let cramers_v = (observed / (self.items.len() as f64 * (rows.min(columns) - 1) as f64)).sqrt();
+        IndependenceTest { chi_square: observed, cramers_v, p_value, permutations, is_independent: p_value >= INDEPENDENCE_ALPHA }
+    }
+}
@@ -4,16 +4,144 @@ selections, such as the odds of selecting a green ball out of a bag of many diff
 balls or the odds of selecting 2 boys and 1 girl from a classroom.
 `mendel` accomplishes not through (arguably trivial) mathematical probability formulas, but
 rather via running many simulations on the population selections and recording the results.
+
+# Features
+
+* `arrow`: [`Bag::from_parquet`](struct.Bag.html#method.from_parquet) loads a population from the rows
+  of a Parquet file, so columnar datasets don't have to be exported to CSV first.
+* `deterministic`: every simulation that hasn't been given a seed uses the fixed
+  [`DETERMINISTIC_SEED`](constant.DETERMINISTIC_SEED.html) instead of the thread RNG, so doctests and
+  examples print the same numbers on every run. Leave it off for real work.
+* `mmap`: [`Bag::from_mmap`](struct.Bag.html#method.from_mmap) simulates over a memory-mapped file of
+  fixed-size records, so populations larger than RAM never have to be loaded. Unix only.
+* `plot`: render a [`Distribution`](struct.Distribution.html) as an SVG histogram, or the convergence of an
+  estimate with [`SimulationResult::convergence_svg`](struct.SimulationResult.html#method.convergence_svg).
 */
 
+#[cfg(all(feature = "mmap", unix))]
+extern crate libc;
+extern crate rand;
+
+#[macro_use]
+mod macros;
+
 mod bag;
+mod batch;
+pub mod bayes;
+mod budget;
+mod cache;
+mod calibration;
+mod combine;
+mod compare;
+mod conditioned;
+mod convenience;
+mod convergence;
+mod counting;
+mod diagnostics;
+mod diff;
+mod distinct;
+mod distribution;
+mod diversity;
+mod dynamic;
+mod engine;
+mod entropy;
+mod estimator;
+mod expectation;
+mod frequency;
+mod histogram;
+mod independence;
+mod math;
+mod mixture;
+#[cfg(all(feature = "mmap", unix))]
+mod mmap;
+mod order;
+mod overlap;
+mod pair;
+#[cfg(feature = "arrow")]
+mod parquet;
+mod partition;
+mod persist;
+#[cfg(feature = "plot")]
+mod plot;
+mod posterior;
+mod profile;
+mod range;
+mod ratio;
+mod report;
+mod result;
+mod rng;
+mod rounds;
+mod scenario;
+mod sequence;
+mod sensitivity;
+mod sink;
+mod splitting;
+mod stream;
+mod summary;
+mod survey;
+mod sweep;
+mod synth;
+#[macro_use]
+pub mod testing;
+mod trace;
+pub mod validate;
+mod view;
+mod weighted;
+mod witness;
 
 pub use bag::Bag;
+pub use batch::{BatchResults, QueryBatch};
+pub use budget::Budget;
+pub use cache::ResultCache;
+pub use calibration::Calibration;
+pub use combine::CombinedProbability;
+pub use compare::{compare_bags, AbTest, AbTestResult, Comparison};
+pub use conditioned::Conditioned;
+pub use convenience::{probability_of, probability_of_sample};
+pub use convergence::ConvergencePoint;
+pub use diagnostics::{Diagnostics, Replicates};
+pub use diff::{BagDiff, KeyChange};
+pub use distribution::Distribution;
+pub use diversity::Diversity;
+pub use estimator::Estimator;
+pub use expectation::ConditionalEstimate;
+pub use frequency::FrequencyBag;
+pub use histogram::Histogram;
+pub use independence::IndependenceTest;
+#[cfg(all(feature = "mmap", unix))]
+pub use mmap::MappedBag;
+pub use mixture::Mixture;
+pub use overlap::Overlap;
+#[cfg(feature = "arrow")]
+pub use parquet::{ParquetRow, ParquetValue};
+pub use posterior::{Beta, Posterior};
+pub use profile::Profile;
+pub use range::WeightedRange;
+pub use ratio::Ratio;
+pub use report::{EventMatrix, Events, Report, ReportRow};
+pub use result::SimulationResult;
+pub use rng::{worker_seed, RngKind, DETERMINISTIC_SEED};
+pub use rounds::Rounds;
+pub use scenario::{Scenario, ScenarioReport};
+pub use sensitivity::{sensitivity, sensitivity_parallel};
+pub use sink::{CsvSink, JsonLinesSink, ResultSink, StdoutSink};
+pub use splitting::RareEvent;
+pub use stream::Subscription;
+pub use summary::{Group, Stats, Summary};
+pub use synth::Synth;
+pub use trace::TraceFormat;
+pub use view::BagView;
+pub use weighted::PositionDistribution;
+pub use witness::Witnessed;
 
 #[cfg(test)]
 mod tests {
 
     use super::bag::Bag;
+    use super::{compare_bags, Diagnostics, probability_of, probability_of_sample, AbTest, Comparison, sensitivity, sensitivity_parallel, Events, PositionDistribution, RngKind, SimulationResult, Synth, TraceFormat};
+    use rand::{SeedableRng, StdRng};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
 
     fn close_enough(inp: f64, exp: f64) -> bool {
         // Input is within +/- 1% of the expected result
@@ -21,6 +149,7 @@ mod tests {
     }
 
     #[derive(Clone)]
+    #[allow(dead_code)]
     struct Region<'a> {
         name: &'a str,
         state: &'a str,
@@ -49,7 +178,7 @@ mod tests {
         assert!(close_enough(result, 0.33));
     }
 
-    #[derive(Clone, PartialEq, Debug)]
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
     enum Color {
         Red,
         Blue,
@@ -96,6 +225,28 @@ mod tests {
         assert!(close_enough(result, 0.5));
     }
 
+    #[test]
+    fn test_sample_near_whole_bag() {
+        use std::time::Duration;
+        use super::Budget;
+        let mut numbers = Bag::from_range(0, 10);
+        numbers.set_seed(15);
+        // Leaving out 1 of 10 items misses the 0 a tenth of the time
+        assert!(close_enough(numbers.sample(9, |values| values.contains(&&0)), 0.9));
+        assert_eq!(numbers.sample(7, |values| {
+            let mut distinct: Vec<i32> = values.iter().map(|v| **v).collect();
+            distinct.sort();
+            distinct.dedup();
+            distinct.len() == 7
+        }), 1.0);
+
+        // The whole bag is evaluated once, but reports every trial the budget ran
+        numbers.set_budget(Budget::Time(Duration::from_millis(10)));
+        let everything = numbers.sample_detailed(10, |values| values.len() == 10);
+        assert!(everything.trials > 1);
+        assert_eq!(everything.successes, everything.trials);
+    }
+
     #[test]
     fn test_set_max_sims() {
         let mut bag = Bag::from_range(1, 11);
@@ -103,4 +254,2015 @@ mod tests {
         assert_eq!(bag.max_sims, 123);
     }
 
+    #[test]
+    fn test_one_respects_max_sims() {
+        let mut bag = Bag::from_range(1, 11);
+        bag.set_max_sims(1_000);
+        let result = bag.one(|v| *v <= 5);
+        assert!(result > 0.4 && result < 0.6);
+        assert_eq!(bag.one(|_| true), 1.0);
+    }
+
+    #[test]
+    fn test_calibrate() {
+        let bag = Bag::from_range(1, 11);
+        // p(1 - p) is at most 0.25, so +/- 1% needs at most 1.96^2 * 0.25 / 0.01^2 = 9604 sims
+        let calibration = bag.calibrate(|v| *v % 2 == 0, 0.01);
+        assert!(calibration.recommended_sims > 9_000 && calibration.recommended_sims <= 9_604);
+        assert!(calibration.pilot_estimate > 0.4 && calibration.pilot_estimate < 0.6);
+
+        // Rarer events have less variance and so need fewer sims
+        let rare = bag.calibrate(|v| *v == 1, 0.01);
+        assert!(rare.recommended_sims < calibration.recommended_sims);
+    }
+
+    #[test]
+    fn test_summary() {
+        let balls = Bag::from_vec(vec![
+            Ball { color: Color::Green },
+            Ball { color: Color::Red },
+            Ball { color: Color::Green },
+            Ball { color: Color::Blue },
+            Ball { color: Color::Red },
+            Ball { color: Color::Green }
+        ]);
+        let summary = balls.summary(|b| b.color.clone());
+        assert_eq!(summary.total, 6);
+        assert_eq!(summary.groups.len(), 3);
+        assert_eq!(summary.groups[0].key, Color::Green);
+        assert_eq!(summary.groups[1].key, Color::Red);
+        assert_eq!(summary.get(&Color::Blue).unwrap().count, 1);
+        assert!(close_enough(summary.groups[0].proportion, 0.5));
+
+        let words = Bag::from_vec(vec!["apple", "fig", "apple"]);
+        let table = words.summary(|w| *w).to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "apple      2      66.67%");
+        assert_eq!(lines[3], "total      3     100.00%");
+    }
+
+    #[test]
+    fn test_composition_queries() {
+        let words = Bag::from_vec(vec!["hello", "tomato", "lizard", "golfing", "hello"]);
+        assert_eq!(words.unique_count(|w| *w), 4);
+        assert_eq!(words.unique_count(|w| w.len()), 3);
+        assert_eq!(words.count_where(|w| w.starts_with('h')), 2);
+        assert_eq!(words.count_where(|w| w.is_empty()), 0);
+
+        let multiplicities = words.multiplicities(|w| w.len());
+        assert_eq!(multiplicities.len(), 3);
+        assert_eq!(multiplicities[&5], 2);
+        assert_eq!(multiplicities[&6], 2);
+        assert_eq!(multiplicities[&7], 1);
+    }
+
+    #[test]
+    fn test_shuffle_and_sort() {
+        let mut bag = Bag::from_range(1, 101);
+        let mut rng = StdRng::from_seed([42; 32]);
+        bag.shuffle(&mut rng);
+        assert_ne!(*bag.items, (1..101).collect::<Vec<i32>>());
+        bag.sort_by(|a, b| a.cmp(b));
+        assert_eq!(*bag.items, (1..101).collect::<Vec<i32>>());
+
+        // The same seed always gives the same order
+        let a: Bag<i32> = Bag::from_range_shuffled(1, 101, &mut StdRng::from_seed([3; 32]));
+        let b: Bag<i32> = Bag::from_range_shuffled(1, 101, &mut StdRng::from_seed([3; 32]));
+        assert_eq!(a.items, b.items);
+    }
+
+    #[test]
+    fn test_index_and_view() {
+        let bag = Bag::from_range(1, 21);
+        assert_eq!(bag[0], 1);
+        assert_eq!(bag[19], 20);
+        assert_eq!(&bag[5..8], &[6, 7, 8]);
+
+        let view = bag.view(10..20);
+        assert_eq!(view.len(), 10);
+        assert_eq!(view[0], 11);
+        assert_eq!(view.one(|v| *v > 10), 1.0);
+        let result = view.sample(2, |values| values.iter().all(|v| **v > 15));
+        // (5 / 10) * (4 / 9) = 22.2%
+        assert!(close_enough(result, 0.222));
+
+        // Views split their simulations across the bag's threads like the bag does
+        let mut bag = bag;
+        bag.set_seed(4);
+        bag.set_max_sims(10_000);
+        bag.set_parallelism(4);
+        assert_eq!(bag.view(0..20).one(|v| *v % 3 == 0), bag.one(|v| *v % 3 == 0));
+        assert_eq!(bag.view(0..20).sample(2, |values| values[0] < values[1]), bag.sample(2, |values| values[0] < values[1]));
+    }
+
+    #[test]
+    fn test_iterators() {
+        let mut bag: Bag<i32> = (1..6).collect();
+        assert_eq!(bag.iter().filter(|v| **v % 2 == 1).count(), 3);
+        for v in &mut bag {
+            *v += 1;
+        }
+        bag.iter_mut().for_each(|v| *v *= 2);
+        assert_eq!((&bag).into_iter().max(), Some(&12));
+
+        let view_total: i32 = bag.view(0..2).iter().sum();
+        assert_eq!(view_total, 10);
+
+        let evens: Bag<i32> = bag.into_iter().filter(|v| *v % 4 == 0).collect();
+        assert_eq!(*evens.items, vec![4, 8, 12]);
+        let drained: Vec<i32> = evens.into_iter().collect();
+        assert_eq!(drained, vec![4, 8, 12]);
+    }
+
+    #[test]
+    fn test_standard_traits() {
+        let bag = Bag::from_vec(vec![3, 1, 2, 1]);
+        let mut copy = bag.clone();
+        copy.set_max_sims(10);
+        copy.sort_by(|a, b| a.cmp(b));
+        assert_eq!(bag, copy);
+        assert_ne!(bag, Bag::from_vec(vec![3, 1, 2, 2]));
+        assert_ne!(bag, Bag::from_vec(vec![3, 1, 2]));
+
+        let empty: Bag<i32> = Bag::default();
+        assert_eq!(empty.items.len(), 0);
+
+        let small = Bag::from_vec(vec!["a", "b"]);
+        assert_eq!(format!("{:?}", small), format!(r#"Bag {{ len: 2, max_sims: {}, items: ["a", "b"] }}"#, small.max_sims));
+        let big = Bag::from_range(0, 1_000_000);
+        let debug = format!("{:?}", big);
+        assert!(debug.contains("len: 1000000"));
+        assert!(debug.contains("items: [0, 1, 2, 3, 4, ... 999995 more]"));
+    }
+
+    #[test]
+    fn test_bag_is_send_and_sync() {
+        fn assert_send_sync<S: Send + Sync>() {}
+        assert_send_sync::<Bag<i32>>();
+        assert_send_sync::<Bag<String>>();
+
+        let mut bag = Bag::from_range(1, 11);
+        bag.set_seed(99);
+        bag.set_max_sims(1_000);
+        let shared = Arc::new(bag);
+        let handles: Vec<_> = (0..4).map(|_| {
+            let bag = Arc::clone(&shared);
+            thread::spawn(move || bag.one(|v| *v > 5))
+        }).collect();
+        let results: Vec<f64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        // Every thread built the same seeded RNG, so every result is identical
+        assert!(results.iter().all(|r| *r == results[0]));
+        assert!(close_enough(results[0], shared.one(|v| *v > 5)));
+    }
+
+    #[test]
+    fn test_non_clone_items() {
+        struct Account {
+            balance: Mutex<u32>
+        }
+
+        let accounts: Bag<Account> = (0..10).map(|i| Account { balance: Mutex::new(i * 100) }).collect();
+        let result = accounts.one(|a| *a.balance.lock().unwrap() >= 500);
+        assert!(close_enough(result, 0.5));
+        let result = accounts.sample(2, |pair| pair.iter().all(|a| *a.balance.lock().unwrap() >= 500));
+        // (5 / 10) * (4 / 9) = 22.2%
+        assert!(close_enough(result, 0.222));
+        assert_eq!(accounts.count_where(|a| *a.balance.lock().unwrap() == 0), 1);
+    }
+
+    #[test]
+    fn test_shared_population() {
+        let population = Arc::new((1..11).collect::<Vec<i32>>());
+        let a = Bag::from_shared(Arc::clone(&population));
+        let mut b = a.clone();
+        b.set_max_sims(500);
+        assert!(Arc::ptr_eq(&a.items, &b.items));
+        assert_eq!(Arc::strong_count(&population), 3);
+
+        // Modifying a shared bag copies its items, leaving the others untouched
+        b.iter_mut().for_each(|v| *v *= 2);
+        assert!(!Arc::ptr_eq(&a.items, &b.items));
+        assert_eq!(*a.items, *population);
+        assert_eq!(b[0], 2);
+
+        // Non-Clone items can still be shared
+        let locks = Bag::from_vec(vec![Mutex::new(1), Mutex::new(2)]);
+        let shared_locks = locks.clone();
+        *shared_locks[0].lock().unwrap() = 10;
+        assert_eq!(*locks[0].lock().unwrap(), 10);
+    }
+
+    #[test]
+    fn test_sample_owned_and_draw() {
+        let numbers = Bag::from_range(1, 50);
+        let result = numbers.sample_owned(3, |values| values.into_iter().sum::<i32>() % 2 == 0);
+        assert!(close_enough(result, 0.5));
+
+        let mut raffle = Bag::from_vec(vec!["ann", "bob", "cat", "dan", "eve"]);
+        let kept = raffle.clone();
+        let winners = raffle.draw(2);
+        assert_eq!(winners.len(), 2);
+        assert_ne!(winners[0], winners[1]);
+        assert_eq!(raffle.items.len(), 3);
+        assert_eq!(kept.items.len(), 5);
+        let mut everyone: Vec<&str> = raffle.items.iter().chain(winners.iter()).cloned().collect();
+        everyone.sort();
+        assert_eq!(everyone, *kept.items);
+
+        let rest = raffle.draw(3);
+        assert_eq!(rest.len(), 3);
+        assert!(raffle.items.is_empty());
+    }
+
+    #[test]
+    fn test_one_weighted_by() {
+        let cities = Bag::from_vec(vec![
+            Region { name: "Pittsburgh", state: "PA",  population: 300 },
+            Region { name: "Denver", state: "CO", population: 700 },
+            Region { name: "State College", state: "PA", population: 42 },
+            Region { name: "Nowhere", state: "CO", population: 0 },
+        ]);
+        // 342 out of 1042 thousand residents live in PA
+        let result = cities.one_weighted_by(|c| c.population as f64, |c| c.state == "PA");
+        assert!(close_enough(result, 0.328));
+        // Zero weight items are never picked
+        assert_eq!(cities.one_weighted_by(|c| c.population as f64, |c| c.population == 0), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one weight must be positive")]
+    fn test_one_weighted_by_all_zero() {
+        Bag::from_range(1, 4).one_weighted_by(|_| 0.0, |_| true);
+    }
+
+    #[test]
+    fn test_sample_dynamic() {
+        let mut bag = Bag::from_vec(vec![true, false, false, false]);
+        bag.set_max_sims(20_000);
+        // Without any updates this is the same as drawing without replacement
+        let result = bag.sample_dynamic(2, |_, _| {}, |drawn, rest| drawn.contains(&true) && rest.len() == 2);
+        assert!(result > 0.48 && result < 0.52);
+
+        // Putting every item back draws with replacement: 1 - (3/4)^2 = 43.75%
+        let result = bag.sample_dynamic(2, |item, population| population.push(*item), |drawn, _| drawn.contains(&true));
+        assert!(result > 0.42 && result < 0.45);
+
+        // Drawing more items than exist stops once the population is empty
+        assert_eq!(bag.sample_dynamic(10, |_, _| {}, |drawn, rest| drawn.len() == 4 && rest.is_empty()), 1.0);
+    }
+
+    #[test]
+    fn test_conditioned() {
+        let numbers = Bag::from_range(1, 21);
+        let over_ten = numbers.conditioned(|v| *v > 10);
+        assert_eq!(over_ten.len(), 10);
+        assert_eq!(over_ten.one(|v| *v > 10), 1.0);
+        assert!(close_enough(over_ten.one(|v| *v % 2 == 0), 0.5));
+        // Both of 2 picks over 15, given both are over 10: (5 / 10) * (4 / 9) = 22.2%
+        assert!(close_enough(over_ten.sample(2, |values| values.iter().all(|v| **v > 15)), 0.222));
+
+        let big = Bag::from_range(0, 10_000);
+        assert!(big.conditioned(|v| *v == 7).warning().is_some());
+        assert!(big.conditioned(|v| *v % 2 == 0).warning().is_none());
+        let impossible = big.conditioned(|v| *v < 0);
+        assert!(impossible.is_empty());
+        assert_eq!(impossible.acceptance_rate, 0.0);
+        assert!(impossible.warning().unwrap().contains("no items"));
+
+        // Conditioned views split their simulations across the bag's threads like the bag does
+        let mut numbers = numbers;
+        numbers.set_seed(4);
+        numbers.set_max_sims(10_000);
+        numbers.set_parallelism(4);
+        let everything = numbers.conditioned(|_| true);
+        assert_eq!(everything.one(|v| *v % 3 == 0), numbers.one(|v| *v % 3 == 0));
+        assert_eq!(everything.sample(2, |values| values[0] < values[1]), numbers.sample(2, |values| values[0] < values[1]));
+    }
+
+    #[test]
+    fn test_simulation_result() {
+        let result = SimulationResult::new(250, 1_000);
+        assert_eq!(result.probability(), 0.25);
+        assert!((result.std_error() - 0.01369).abs() < 0.0001);
+        let (low, high) = result.confidence_interval();
+        assert!((low - 0.2241).abs() < 0.001 && (high - 0.2779).abs() < 0.001);
+
+        let (low, high) = SimulationResult::new(1_000, 1_000).confidence_interval();
+        assert!(low > 0.99 && high == 1.0);
+    }
+
+    #[test]
+    fn test_report() {
+        let numbers = Bag::from_range(1, 11);
+        let events = Events::new()
+            .add("contains 1", |values| values.contains(&&1))
+            .add("sum, over 10", |values| values.iter().map(|v| **v).sum::<i32>() > 10)
+            .add("impossible", |_| false);
+        let report = numbers.report(2, &events);
+        assert_eq!(report.sample_size, 2);
+        assert_eq!(report.rows.len(), 3);
+        assert!(close_enough(report.get("contains 1").unwrap().probability(), 0.2));
+        // 25 of the 45 pairs sum to over 10
+        assert!(close_enough(report.get("sum, over 10").unwrap().probability(), 0.556));
+        assert_eq!(report.get("impossible").unwrap().successes, 0);
+        assert!(report.get("missing").is_none());
+
+        let table = report.to_string();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("event         probability"));
+        assert!(lines[3].starts_with("impossible         0.0000          0"));
+        assert!(lines.iter().all(|l| l.len() == lines[0].len()));
+
+        let csv = report.to_csv();
+        let csv_lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(csv_lines[0], "event,probability,successes,trials,ci_low,ci_high");
+        assert!(csv_lines[2].starts_with("\"sum, over 10\","));
+        assert_eq!(csv_lines.len(), 4);
+    }
+
+    #[test]
+    fn test_result_formatting() {
+        let result = SimulationResult::new(47_619, 100_000);
+        assert_eq!(result.as_fixed(2), "0.48");
+        assert_eq!(result.as_fixed(0), "0");
+        assert_eq!(result.as_percent(2), "47.62%");
+        assert_eq!(result.as_percent(0), "48%");
+        assert_eq!(result.as_odds_ratio(), "about 1 in 2");
+        assert_eq!(SimulationResult::new(5, 5).as_odds_ratio(), "about 1 in 1");
+        assert_eq!(format!("{:.2}", result), "0.48 (95% CI 0.47 - 0.48)");
+        assert_eq!(SimulationResult::new(1, 4).to_string(), "0.2500 (95% CI 0.0456 - 0.6994)");
+    }
+
+    #[test]
+    fn test_sample_sweep() {
+        use std::time::Duration;
+        use super::Budget;
+
+        let mut bag = Bag::from_range(1, 21);
+        bag.set_max_sims(50_000);
+        let sweep = bag.sample_sweep(vec![4, 1, 10], |values| values.iter().any(|v| **v <= 2));
+        assert_eq!(sweep.iter().map(|&(k, _)| k).collect::<Vec<usize>>(), vec![4, 1, 10]);
+        // 1 - C(18, k) / C(20, k)
+        assert!(close_enough(sweep[0].1, 0.368));
+        assert!(close_enough(sweep[1].1, 0.1));
+        assert!(close_enough(sweep[2].1, 0.763));
+
+        let full = bag.sample_sweep(0..=20, |values| values.len() % 2 == 0);
+        assert_eq!(full.len(), 21);
+        assert!(full.iter().all(|&(k, p)| p == if k % 2 == 0 { 1.0 } else { 0.0 }));
+
+        // A time budget still gives probabilities
+        bag.set_budget(Budget::Time(Duration::from_millis(5)));
+        let timed = bag.sample_sweep(vec![1], |values| values[0] <= &2);
+        assert!(timed[0].1 > 0.0 && timed[0].1 < 0.5);
+    }
+
+    #[test]
+    fn test_sensitivity() {
+        let build = |blue: usize| {
+            let mut balls = vec![Ball { color: Color::Red }; 10];
+            balls.extend(vec![Ball { color: Color::Blue }; blue]);
+            let mut bag = Bag::from_vec(balls);
+            bag.set_max_sims(20_000);
+            bag
+        };
+        let no_blue = |balls: Vec<&Ball>| balls.iter().all(|b| b.color != Color::Blue);
+        let curve = sensitivity(vec![0, 5, 10], build, 2, no_blue);
+        assert_eq!(curve[0], (0, 1.0));
+        // (10 / 15) * (9 / 14) = 42.9%
+        assert!(curve[1].1 > 0.41 && curve[1].1 < 0.45);
+
+        let parallel = sensitivity_parallel(0..=10, build, 2, no_blue, 3);
+        assert_eq!(parallel.iter().map(|&(b, _)| b).collect::<Vec<usize>>(), (0..=10).collect::<Vec<usize>>());
+        assert_eq!(parallel[0].1, 1.0);
+        assert!(parallel[10].1 > 0.22 && parallel[10].1 < 0.25);
+    }
+
+    #[test]
+    fn test_compare_bags() {
+        let mut a = Bag::from_range(1, 11);
+        let mut b = Bag::from_range(1, 13);
+        a.set_max_sims(20_000);
+        b.set_max_sims(20_000);
+        let comparison = compare_bags(&a, &b, 1, |values| *values[0] <= 5);
+        // 50% against 41.7%
+        assert!(comparison.difference > 0.06 && comparison.difference < 0.11);
+        assert!(comparison.z_score > 10.0);
+        assert!(comparison.p_value < 0.0001);
+        assert!(comparison.is_significant(0.01));
+
+        let same = compare_bags(&a, &a, 1, |values| *values[0] <= 5);
+        assert!(same.z_score.abs() < 5.0);
+
+        // Known values: 60 / 100 against 40 / 100 gives z = 2.828, p = 0.0047
+        let known = Comparison::from_results(SimulationResult::new(60, 100), SimulationResult::new(40, 100));
+        assert!((known.z_score - 2.828).abs() < 0.001);
+        assert!((known.p_value - 0.00468).abs() < 0.0001);
+
+        let certain = Comparison::from_results(SimulationResult::new(10, 10), SimulationResult::new(5, 5));
+        assert_eq!(certain.z_score, 0.0);
+        assert!(certain.p_value > 0.9999);
+    }
+
+    #[test]
+    fn test_ab_test() {
+        let mut test = AbTest::new(30, 100, 50, 100);
+        test.set_max_sims(20_000);
+        test.set_seed(5);
+        let result = test.run();
+        assert!(close_enough(result.observed_difference, -0.2));
+        // Fisher's exact test puts this at about 0.0056
+        assert!(result.p_value > 0.002 && result.p_value < 0.012);
+        assert!(result.significant);
+        // With 100 per group and a 40% rate, differences over ~0.13 are significant
+        assert!(result.critical_difference > 0.11 && result.critical_difference < 0.15);
+        assert!(result.minimum_detectable_effect > result.critical_difference);
+        assert_eq!(test.run(), result);
+
+        let mut identical = AbTest::new(40, 100, 40, 100);
+        identical.set_max_sims(5_000);
+        let result = identical.run();
+        assert_eq!(result.observed_difference, 0.0);
+        assert!(result.p_value > 0.99);
+        assert!(!result.significant);
+    }
+
+    #[test]
+    fn test_ab_test_settings() {
+        use std::time::Duration;
+        use super::Budget;
+        let mut test = AbTest::new(30, 100, 50, 100);
+        test.set_max_sims(20_000);
+        test.set_seed(5);
+        test.set_parallelism(4);
+        let parallel = test.run();
+        assert_eq!(test.run(), parallel);
+        assert!(parallel.p_value > 0.002 && parallel.p_value < 0.012);
+        assert!(parallel.critical_difference > 0.11 && parallel.critical_difference < 0.15);
+
+        test.set_rng_kind(RngKind::Pcg);
+        assert!(test.run().significant);
+        test.set_budget(Budget::Time(Duration::from_millis(20)));
+        let timed = test.run();
+        assert!(timed.significant);
+        assert!(timed.critical_difference > 0.11 && timed.critical_difference < 0.15);
+    }
+
+    #[test]
+    fn test_early_stopping() {
+        let mut bag = Bag::from_range(1, 11);
+        bag.set_early_stopping(true);
+        let never = bag.one_detailed(|v| *v > 10);
+        assert!(never.decided_early);
+        assert_eq!(never.successes, 0);
+        assert!(never.trials >= 1_000 && never.trials < 20_000);
+        let always = bag.sample_detailed(2, |values| values.len() == 2);
+        assert!(always.decided_early);
+        assert_eq!(always.successes, always.trials);
+
+        // Uncertain events run every simulation
+        let even = bag.one_detailed(|v| *v % 2 == 0);
+        assert!(!even.decided_early);
+        assert_eq!(even.trials, bag.max_sims as u64);
+
+        bag.set_early_stopping(false);
+        let never = bag.one_detailed(|v| *v > 10);
+        assert!(!never.decided_early);
+        assert_eq!(never.trials, bag.max_sims as u64);
+    }
+
+    #[test]
+    fn test_merge_results() {
+        let a = SimulationResult::new(30, 100);
+        let b = SimulationResult::new(10, 50);
+        let merged = a.merge(&b);
+        assert_eq!(merged, SimulationResult::new(40, 150));
+        assert_eq!(vec![a.clone(), b].into_iter().sum::<SimulationResult>(), merged);
+        assert_eq!(Vec::<SimulationResult>::new().iter().sum::<SimulationResult>(), SimulationResult::new(0, 0));
+
+        let mut early = SimulationResult::new(0, 10);
+        early.decided_early = true;
+        assert!(a.merge(&early).decided_early);
+
+        // Shards with different seeds combine into one estimate
+        let mut bag = Bag::from_range(1, 11);
+        bag.set_max_sims(25_000);
+        let total: SimulationResult = (0..4).map(|seed| {
+            bag.set_seed(seed);
+            bag.one_detailed(|v| *v <= 3)
+        }).sum();
+        assert_eq!(total.trials, 100_000);
+        assert!(close_enough(total.probability(), 0.3));
+    }
+
+    #[test]
+    #[cfg(feature = "deterministic")]
+    fn test_deterministic_feature() {
+        let bag = Bag::from_range(1, 101);
+        assert_eq!(bag.one_detailed(|v| *v % 7 == 0), bag.one_detailed(|v| *v % 7 == 0));
+        let mut seeded = bag.clone();
+        seeded.set_seed(super::DETERMINISTIC_SEED);
+        assert_eq!(bag.sample(3, |values| values.contains(&&1)), seeded.sample(3, |values| values.contains(&&1)));
+    }
+
+    #[test]
+    fn test_rng_kinds() {
+        let kinds = [RngKind::Std, RngKind::ChaCha, RngKind::Pcg, RngKind::Xoshiro, RngKind::XorShift];
+        let mut seeded_results = Vec::new();
+        for kind in kinds.iter() {
+            let mut bag = Bag::from_range(1, 101);
+            bag.set_rng_kind(*kind);
+            assert!(close_enough(bag.one(|v| *v <= 25), 0.25));
+            assert!(close_enough(bag.sample(2, |values| values.iter().all(|v| **v <= 50)), 0.247));
+
+            bag.set_seed(7);
+            let result = bag.one_detailed(|v| *v % 3 == 0);
+            assert_eq!(result, bag.one_detailed(|v| *v % 3 == 0));
+            seeded_results.push(result.successes);
+        }
+        // The same seed gives each algorithm its own stream
+        seeded_results.sort();
+        seeded_results.dedup();
+        assert_eq!(seeded_results.len(), kinds.len());
+        assert_eq!(RngKind::default(), RngKind::Std);
+    }
+
+    #[test]
+    fn test_parallelism() {
+        let mut bag = Bag::from_range(1, 11);
+        bag.set_max_sims(100_001);
+        bag.set_parallelism(4);
+        assert_eq!(bag.threads, 4);
+        let result = bag.one_detailed(|v| *v <= 3);
+        assert_eq!(result.trials, 100_001);
+        assert!(close_enough(result.probability(), 0.3));
+        assert!(close_enough(bag.sample(2, |values| values.contains(&&1)), 0.2));
+
+        // More threads than simulations
+        bag.set_max_sims(3);
+        bag.set_parallelism(8);
+        assert_eq!(bag.one_detailed(|_| true), SimulationResult::new(3, 3));
+
+        bag.set_parallelism(0);
+        assert_eq!(bag.threads, 1);
+    }
+
+    #[cfg(all(feature = "mmap", unix))]
+    #[test]
+    fn test_from_mmap() {
+        use std::fs;
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join(format!("mendel-test-{}.bin", std::process::id()));
+        {
+            let mut file = fs::File::create(&path).unwrap();
+            for value in 1..11u32 {
+                file.write_all(&value.to_le_bytes()).unwrap();
+            }
+        }
+        let decode = |bytes: &[u8]| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let mut bag = Bag::from_mmap(&path, 4, decode).unwrap();
+        assert_eq!(bag.len(), 10);
+        assert_eq!(bag.get(0), 1);
+        assert_eq!(bag.get(9), 10);
+        assert!(close_enough(bag.one(|v| v % 2 == 0), 0.5));
+        assert!(close_enough(bag.sample(2, |values| values.contains(&1)), 0.2));
+        bag.set_seed(7);
+        bag.set_parallelism(2);
+        assert_eq!(bag.one_detailed(|v| *v <= 3), bag.one_detailed(|v| *v <= 3));
+
+        // The file's length has to be a whole number of records
+        assert!(Bag::from_mmap(&path, 3, |bytes: &[u8]| bytes[0]).is_err());
+        fs::remove_file(&path).unwrap();
+        assert!(Bag::from_mmap(&path, 4, decode).is_err());
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_from_parquet() {
+        use std::fs;
+        use super::ParquetValue;
+
+        // Two row groups of an `id` INT64, an optional `name` string, a `score` DOUBLE and an optional
+        // `active` BOOLEAN, with plain and dictionary encoded, version 1 and 2, Snappy and uncompressed pages
+        let file: &[u8] = &[
+            0x50, 0x41, 0x52, 0x31, 0x15, 0x00, 0x15, 0x30, 0x15, 0x30, 0x2c, 0x15, 0x06, 0x15, 0x00, 0x15,
+            0x06, 0x15, 0x06, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x15, 0x04, 0x15,
+            0x1e, 0x15, 0x22, 0x4c, 0x15, 0x04, 0x15, 0x00, 0x00, 0x00, 0x0f, 0x38, 0x03, 0x00, 0x00, 0x00,
+            0x72, 0x65, 0x64, 0x04, 0x00, 0x00, 0x00, 0x62, 0x6c, 0x75, 0x65, 0x15, 0x00, 0x15, 0x12, 0x15,
+            0x16, 0x2c, 0x15, 0x06, 0x15, 0x10, 0x15, 0x06, 0x15, 0x06, 0x00, 0x00, 0x09, 0x20, 0x02, 0x00,
+            0x00, 0x00, 0x03, 0x05, 0x01, 0x03, 0x02, 0x15, 0x06, 0x15, 0x30, 0x15, 0x16, 0x5c, 0x15, 0x06,
+            0x15, 0x00, 0x15, 0x06, 0x15, 0x00, 0x15, 0x00, 0x15, 0x00, 0x00, 0x00, 0x18, 0x00, 0x00, 0x05,
+            0x01, 0x04, 0xe0, 0x3f, 0x3e, 0x08, 0x00, 0x15, 0x06, 0x15, 0x0a, 0x15, 0x0a, 0x5c, 0x15, 0x06,
+            0x15, 0x02, 0x15, 0x06, 0x15, 0x00, 0x15, 0x08, 0x15, 0x00, 0x12, 0x00, 0x00, 0x04, 0x01, 0x02,
+            0x00, 0x01, 0x15, 0x00, 0x15, 0x10, 0x15, 0x10, 0x2c, 0x15, 0x02, 0x15, 0x00, 0x15, 0x06, 0x15,
+            0x06, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x15, 0x00, 0x15, 0x10, 0x15,
+            0x10, 0x2c, 0x15, 0x02, 0x15, 0x00, 0x15, 0x06, 0x15, 0x06, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x15, 0x00, 0x15, 0x30, 0x15, 0x30, 0x2c, 0x15, 0x04, 0x15, 0x00, 0x15,
+            0x06, 0x15, 0x06, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x04, 0x01, 0x05, 0x00, 0x00, 0x00, 0x67,
+            0x72, 0x65, 0x65, 0x6e, 0x05, 0x00, 0x00, 0x00, 0x67, 0x72, 0x65, 0x65, 0x6e, 0x15, 0x00, 0x15,
+            0x20, 0x15, 0x20, 0x2c, 0x15, 0x04, 0x15, 0x00, 0x15, 0x06, 0x15, 0x06, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0xf4, 0xbf, 0x9c, 0x75, 0x00, 0x88, 0x3c, 0xe4, 0x37, 0x7e, 0x15, 0x06,
+            0x15, 0x14, 0x15, 0x14, 0x5c, 0x15, 0x04, 0x15, 0x00, 0x15, 0x04, 0x15, 0x06, 0x15, 0x04, 0x15,
+            0x00, 0x00, 0x00, 0x04, 0x01, 0x04, 0x00, 0x00, 0x00, 0x02, 0x01, 0x02, 0x00, 0x15, 0x02, 0x19,
+            0x5c, 0x48, 0x06, 0x73, 0x63, 0x68, 0x65, 0x6d, 0x61, 0x15, 0x08, 0x00, 0x15, 0x04, 0x25, 0x00,
+            0x18, 0x02, 0x69, 0x64, 0x00, 0x15, 0x0c, 0x25, 0x02, 0x18, 0x04, 0x6e, 0x61, 0x6d, 0x65, 0x25,
+            0x00, 0x00, 0x15, 0x0a, 0x25, 0x00, 0x18, 0x05, 0x73, 0x63, 0x6f, 0x72, 0x65, 0x00, 0x15, 0x00,
+            0x25, 0x02, 0x18, 0x06, 0x61, 0x63, 0x74, 0x69, 0x76, 0x65, 0x00, 0x16, 0x0a, 0x19, 0x2c, 0x19,
+            0x4c, 0x26, 0x08, 0x1c, 0x15, 0x04, 0x19, 0x15, 0x00, 0x19, 0x18, 0x02, 0x69, 0x64, 0x15, 0x00,
+            0x16, 0x06, 0x16, 0x02, 0x16, 0x02, 0x26, 0x08, 0x00, 0x00, 0x26, 0x5a, 0x1c, 0x15, 0x0c, 0x19,
+            0x15, 0x00, 0x19, 0x18, 0x04, 0x6e, 0x61, 0x6d, 0x65, 0x15, 0x02, 0x16, 0x06, 0x16, 0x02, 0x16,
+            0x02, 0x26, 0x96, 0x01, 0x26, 0x5a, 0x00, 0x00, 0x26, 0xce, 0x01, 0x1c, 0x15, 0x0a, 0x19, 0x15,
+            0x00, 0x19, 0x18, 0x05, 0x73, 0x63, 0x6f, 0x72, 0x65, 0x15, 0x02, 0x16, 0x06, 0x16, 0x02, 0x16,
+            0x02, 0x26, 0xce, 0x01, 0x00, 0x00, 0x26, 0x8e, 0x02, 0x1c, 0x15, 0x00, 0x19, 0x15, 0x00, 0x19,
+            0x18, 0x06, 0x61, 0x63, 0x74, 0x69, 0x76, 0x65, 0x15, 0x00, 0x16, 0x06, 0x16, 0x02, 0x16, 0x02,
+            0x26, 0x8e, 0x02, 0x00, 0x00, 0x16, 0xc8, 0x01, 0x16, 0x06, 0x00, 0x19, 0x4c, 0x26, 0xc4, 0x02,
+            0x1c, 0x15, 0x04, 0x19, 0x15, 0x00, 0x19, 0x18, 0x02, 0x69, 0x64, 0x15, 0x00, 0x16, 0x04, 0x16,
+            0x02, 0x16, 0x02, 0x26, 0xc4, 0x02, 0x00, 0x00, 0x26, 0xa8, 0x03, 0x1c, 0x15, 0x0c, 0x19, 0x15,
+            0x00, 0x19, 0x18, 0x04, 0x6e, 0x61, 0x6d, 0x65, 0x15, 0x00, 0x16, 0x04, 0x16, 0x02, 0x16, 0x02,
+            0x26, 0xa8, 0x03, 0x00, 0x00, 0x26, 0xfa, 0x03, 0x1c, 0x15, 0x0a, 0x19, 0x15, 0x00, 0x19, 0x18,
+            0x05, 0x73, 0x63, 0x6f, 0x72, 0x65, 0x15, 0x00, 0x16, 0x04, 0x16, 0x02, 0x16, 0x02, 0x26, 0xfa,
+            0x03, 0x00, 0x00, 0x26, 0xbc, 0x04, 0x1c, 0x15, 0x00, 0x19, 0x15, 0x00, 0x19, 0x18, 0x06, 0x61,
+            0x63, 0x74, 0x69, 0x76, 0x65, 0x15, 0x00, 0x16, 0x04, 0x16, 0x02, 0x16, 0x02, 0x26, 0xbc, 0x04,
+            0x00, 0x00, 0x16, 0xc8, 0x01, 0x16, 0x04, 0x00, 0x28, 0x0b, 0x6d, 0x65, 0x6e, 0x64, 0x65, 0x6c,
+            0x20, 0x74, 0x65, 0x73, 0x74, 0x00, 0x49, 0x01, 0x00, 0x00, 0x50, 0x41, 0x52, 0x31,
+        ];
+        let path = std::env::temp_dir().join("mendel-test-from-parquet.parquet");
+        fs::write(&path, file).unwrap();
+
+        let bag = Bag::from_parquet(&path, |row| {
+            (row.int("id").unwrap(), row.str("name").map(|name| name.to_string()), row.float("score").unwrap(), row.boolean("active"))
+        }).unwrap();
+        assert_eq!(*bag.items, vec![
+            (1, Some("red".to_string()), 0.5, Some(true)),
+            (2, None, 0.5, Some(false)),
+            (3, Some("blue".to_string()), 0.5, None),
+            (4, Some("green".to_string()), -1.25, Some(true)),
+            (5, Some("green".to_string()), 1e300, Some(false))
+        ]);
+        let rows = Bag::from_parquet(&path, |row| (row.values().to_vec(), row.get("missing").is_none())).unwrap();
+        assert_eq!(rows.items[1], (vec![ParquetValue::Int(2), ParquetValue::Null, ParquetValue::Float(0.5), ParquetValue::Boolean(false)], true));
+
+        // Anything cut short of the footer isn't a Parquet file
+        fs::write(&path, &file[..file.len() - 1]).unwrap();
+        assert_eq!(Bag::from_parquet(&path, |_| ()).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+        assert!(Bag::from_parquet(&path, |_| ()).is_err());
+    }
+
+    #[test]
+    fn test_frequencies() {
+        let rows = Bag::from_frequencies(vec![(Color::Red, 7), (Color::Blue, 0), (Color::Green, 3)]);
+        assert_eq!(rows.population(), 10);
+        assert_eq!(rows.rows.len(), 3);
+        assert!(close_enough(rows.one(|c| *c == Color::Red), 0.7));
+        assert_eq!(rows.one(|c| *c == Color::Blue), 0.0);
+        // Individuals are drawn without replacement: 7/10 * 6/9
+        assert!(close_enough(rows.sample(2, |cs| cs.iter().all(|c| **c == Color::Red)), 7.0 / 15.0));
+        assert_eq!(rows.sample(10, |cs| cs.len() == 10), 1.0);
+
+        let csv = "name,\"weight, people\"\n\"Smith, J\",3\n\"O\"\"Neil\",1\n";
+        let names = Bag::from_weighted_csv(csv.as_bytes(), "weight, people", |fields| fields[0].to_string()).unwrap();
+        assert_eq!(names.rows, vec!["Smith, J".to_string(), "O\"Neil".to_string()]);
+        assert_eq!(names.population(), 4);
+        assert!(Bag::from_weighted_csv(csv.as_bytes(), "count", |_| ()).is_err());
+        assert!(Bag::from_weighted_csv("a,count\nx,lots\n".as_bytes(), "count", |_| ()).is_err());
+    }
+
+    #[test]
+    fn test_one_by_position() {
+        let bag = Bag::from_range(0, 4);
+        assert!(close_enough(bag.one_by_position(&PositionDistribution::Uniform, |v| *v == 0), 0.25));
+        // 1 / (1 + 1/4 + 1/9 + 1/16)
+        let zipf = PositionDistribution::Zipf { exponent: 2.0 };
+        assert!(close_enough(bag.one_by_position(&zipf, |v| *v == 0), 0.7026));
+        // 0.5 / (0.5 + 0.25 + 0.125 + 0.0625)
+        let geometric = PositionDistribution::Geometric { p: 0.5 };
+        assert!(close_enough(bag.one_by_position(&geometric, |v| *v == 0), 0.5333));
+        let custom = PositionDistribution::Weights(vec![0.0, 1.0, 0.0, 3.0]);
+        assert!(close_enough(bag.one_by_position(&custom, |v| *v == 3), 0.75));
+        assert_eq!(bag.one_by_position(&custom, |v| *v % 2 == 0), 0.0);
+    }
+
+    #[test]
+    fn test_rounds() {
+        let bag = Bag::from_range(1, 5);
+        // Dealing the whole bag in two rounds always adds up to 1 + 2 + 3 + 4
+        let dealt = bag.rounds(2, 2).without_replacement();
+        assert_eq!(dealt.mean_score(|| 0, |total, draw| { *total += draw.iter().cloned().sum::<i32>(); true }, |total| *total as f64), 10.0);
+        let all_distinct = dealt.run(Vec::new, |seen: &mut Vec<i32>, draw| { seen.extend(draw); true }, |seen| {
+            let mut seen = seen.clone();
+            seen.sort();
+            seen == vec![1, 2, 3, 4]
+        });
+        assert_eq!(all_distinct.probability(), 1.0);
+
+        // Drawing with replacement, odds of the same item in both rounds
+        let mut repeated = bag.rounds(2, 1);
+        repeated.set_max_sims(100_000);
+        let same = repeated.run(Vec::new, |seen, draw| { seen.push(*draw[0]); true }, |seen| seen[0] == seen[1]);
+        assert_eq!(same.trials, 100_000);
+        assert!(close_enough(same.probability(), 0.25));
+
+        // Stopping after the first round, only one round ever gets played
+        let played = bag.rounds(5, 1).mean_score(|| 0, |rounds, _| { *rounds += 1; false }, |rounds| *rounds as f64);
+        assert_eq!(played, 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rounds_without_replacement_too_large() {
+        Bag::from_range(1, 5).rounds(3, 2).without_replacement().run(|| (), |_, _| true, |_| true);
+    }
+
+    #[test]
+    fn test_probability_of() {
+        assert!(close_enough(probability_of(vec![Color::Red, Color::Blue, Color::Blue, Color::Green], |c| *c == Color::Blue), 0.5));
+        assert_eq!(probability_of(1..4, |v| *v < 4), 1.0);
+        assert!(close_enough(probability_of_sample(1..5, 2, |values| values.contains(&&4)), 0.5));
+        assert_eq!(probability_of_sample(1..5, 4, |values| values.len() == 4), 1.0);
+    }
+
+    #[test]
+    fn test_ratio() {
+        use super::Ratio;
+
+        assert_eq!(Ratio::new(0, 7), Ratio { numerator: 0, denominator: 1 });
+        assert_eq!(Ratio::new(7, 7), Ratio { numerator: 1, denominator: 1 });
+        assert_eq!(Ratio::new(12, 18).to_string(), "2/3");
+        assert!(close_enough(Ratio::new(1, 3).to_f64(), 0.3333));
+
+        let result = SimulationResult::new(300, 1_000);
+        assert_eq!(result.ratio(), Ratio::new(3, 10));
+        assert_eq!(result.ratio().to_f64(), result.probability());
+
+        let bag = Bag::from_vec(vec![Color::Red, Color::Red, Color::Blue, Color::Green]);
+        assert_eq!(bag.one_exact(|c| *c == Color::Red), Ratio::new(1, 2));
+        assert_eq!(bag.one_exact(|c| *c == Color::Green).to_string(), "1/4");
+        // 1 of the 6 pairs is both reds, and 3 of the 4 triples hold a red and the blue
+        assert_eq!(bag.sample_exact(2, |cs| cs.iter().all(|c| **c == Color::Red)), Ratio::new(1, 6));
+        assert_eq!(bag.sample_exact(3, |cs| cs.contains(&&Color::Red) && cs.contains(&&Color::Blue)), Ratio::new(3, 4));
+        assert_eq!(bag.sample_exact(4, |cs| cs.len() == 4), Ratio::new(1, 1));
+        assert_eq!(bag.sample_exact(0, |cs| cs.is_empty()), Ratio::new(1, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "the denominator is 0")]
+    fn test_ratio_of_no_trials() {
+        SimulationResult::new(0, 0).ratio();
+    }
+
+    #[test]
+    fn test_from_date_range() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let hour = Duration::from_secs(60 * 60);
+        let hours = Bag::from_date_range(UNIX_EPOCH, UNIX_EPOCH + hour * 24, hour);
+        assert_eq!(hours.items.len(), 24);
+        assert_eq!(hours.items[23], UNIX_EPOCH + hour * 23);
+        // Steps that overshoot the end stop before it
+        assert_eq!(Bag::from_date_range(UNIX_EPOCH, UNIX_EPOCH + hour * 24, hour * 5).items.len(), 5);
+        assert!(Bag::from_date_range(UNIX_EPOCH + hour, UNIX_EPOCH, hour).items.is_empty());
+        // Any type that steps by addition works
+        assert_eq!(*Bag::from_date_range(1.0, 2.0, 0.25).items, vec![1.0, 1.25, 1.5, 1.75]);
+    }
+
+    #[test]
+    #[should_panic(expected = "step must move the date forward")]
+    fn test_from_date_range_without_a_step() {
+        Bag::from_date_range(0, 10, 0);
+    }
+
+    #[test]
+    fn test_bag_macro() {
+        let balls = bag!{ Color::Red: 2, Color::Blue: 1, Color::Green: 0 };
+        assert_eq!(*balls.items, vec![Color::Red, Color::Red, Color::Blue]);
+        let letters = bag!{ "a": 1, "b": 2, };
+        assert_eq!(*letters.items, vec!["a", "b", "b"]);
+        assert_eq!(Bag::from_counts(vec![('x', 3)]), bag!['x', 'x', 'x']);
+        let computed = bag![1 + 1, 2 * 3];
+        assert_eq!(*computed.items, vec![2, 6]);
+        let empty: Bag<i32> = bag![];
+        assert_eq!(empty.items.len(), 0);
+    }
+
+    #[test]
+    fn test_partition_sample() {
+        let bag = Bag::from_range(1, 7);
+        assert_eq!(bag.partition_sample(&[1, 2, 3], |groups| {
+            groups.iter().map(|g| g.len()).collect::<Vec<usize>>() == vec![1, 2, 3]
+        }), 1.0);
+        // Every item is dealt exactly once
+        assert_eq!(bag.partition_sample(&[3, 3], |groups| {
+            let mut dealt: Vec<i32> = groups.into_iter().flatten().cloned().collect();
+            dealt.sort();
+            dealt == vec![1, 2, 3, 4, 5, 6]
+        }), 1.0);
+        // The 1 goes to the first, larger group 4 times out of 6
+        assert!(close_enough(bag.partition_sample(&[4, 2], |groups| groups[0].contains(&&1)), 0.6667));
+        // Groups don't need to use up the bag
+        assert!(close_enough(bag.partition_sample(&[1, 1], |groups| groups[0][0] < groups[1][0]), 0.5));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_partition_sample_too_large() {
+        Bag::from_range(1, 4).partition_sample(&[2, 2], |_| true);
+    }
+
+    #[test]
+    fn test_synth() {
+        // 10 / 3 each, with the leftover member going to the first category
+        let thirds = Synth::new(10).category(Color::Red, 1.0).category(Color::Blue, 1.0).category(Color::Green, 1.0);
+        assert_eq!(thirds.counts(), vec![4, 3, 3]);
+        let balls = thirds.build(|c| Ball { color: c.clone() });
+        assert_eq!(balls.count_where(|b| b.color == Color::Red), 4);
+
+        // Largest remainders win: 2.5, 6.25, 1.25
+        let skewed = Synth::new(10).category('a', 0.25).category('b', 0.625).category('c', 0.125);
+        assert_eq!(skewed.counts(), vec![3, 6, 1]);
+        assert_eq!(Synth::new(7).category('a', 0.0).category('b', 2.0).counts(), vec![0, 7]);
+
+        let crossed = Synth::new(100).category('x', 0.5).category('y', 0.5)
+            .cross(Synth::new(0).category(1, 0.2).category(2, 0.8));
+        assert_eq!(crossed.categories.len(), 4);
+        assert_eq!(crossed.counts(), vec![10, 40, 10, 40]);
+        assert_eq!(crossed.build(|&(letter, number)| (letter, number)).count_where(|&(l, n)| l == 'y' && n == 2), 40);
+    }
+
+    #[test]
+    fn test_from_distribution() {
+        use rand::distributions::{Normal, Uniform};
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let uniform: Bag<u32> = Bag::from_distribution_with_rng(Uniform::new(0, 4), 10_000, &mut rng);
+        assert_eq!(uniform.items.len(), 10_000);
+        assert!(uniform.iter().all(|v| *v < 4));
+        assert!(close_enough(uniform.count_where(|v| *v == 0) as f64 / 10_000.0, 0.25));
+
+        let mut normal = Bag::from_distribution(Normal::new(10.0, 2.0), 20_000);
+        normal.set_max_sims(20_000);
+        assert!(normal.one(|v| *v > 10.0) > 0.47 && normal.one(|v| *v > 10.0) < 0.53);
+    }
+
+    #[test]
+    fn test_stats() {
+        let bag = Bag::from_vec(vec![4.0, 1.0, 7.0]);
+        let stats = bag.stats(|v| *v).unwrap();
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.mean, 4.0);
+        assert_eq!(stats.median, 4.0);
+        assert_eq!((stats.min, stats.max), (1.0, 7.0));
+        assert!((stats.std_dev - 6.0f64.sqrt()).abs() < 1e-12);
+        assert_eq!(Bag::from_vec(vec![2.0]).stats(|v| *v).unwrap().std_dev, 0.0);
+        assert_eq!(Bag::<f64>::default().stats(|v| *v), None);
+    }
+
+    #[test]
+    fn test_sample_max_and_min() {
+        let bag = Bag::from_vec(vec![3, 1, 2, 2]);
+        let highest = bag.sample_max(2, |v| *v as f64);
+        assert_eq!(highest.trials, 100_000);
+        // Only 2.0 and 3.0 can be the largest of two
+        assert_eq!(highest.outcomes.len(), 2);
+        assert!(close_enough(highest.probability(&3.0), 0.5));
+        assert!(close_enough(highest.at_most(&2.0), 0.5));
+        assert_eq!(highest.at_least(&2.0), 1.0);
+        let lowest = bag.sample_min(4, |v| *v as f64);
+        assert_eq!(lowest.probabilities(), vec![(&1.0, 1.0)]);
+    }
+
+    #[test]
+    fn test_contains_top() {
+        let bag = Bag::from_vec(vec![5, 9, 1, 9, 3]);
+        // The top 2 are both 9s
+        assert!(close_enough(bag.contains_top(1, 2, |v| *v as f64), 0.4));
+        // Of the tied 9s, only the first counts as the top 1
+        assert!(close_enough(bag.contains_top(1, 1, |v| *v as f64), 0.2));
+        // 1 - (3/5 * 2/4)
+        assert!(close_enough(bag.contains_top(2, 2, |v| *v as f64), 0.7));
+        assert_eq!(bag.contains_top(4, 2, |v| *v as f64), 1.0);
+        assert_eq!(bag.contains_top(3, 0, |v| *v as f64), 0.0);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let agreeing = Diagnostics::from_results(vec![SimulationResult::new(50, 100), SimulationResult::new(50, 100)]);
+        assert_eq!(agreeing.pooled, SimulationResult::new(100, 200));
+        assert_eq!(agreeing.spread, 0.0);
+        assert!(agreeing.r_hat < 1.0);
+        assert!(agreeing.is_converged(0.0));
+
+        let disagreeing = Diagnostics::from_results(vec![SimulationResult::new(10, 100), SimulationResult::new(90, 100)]);
+        assert!(disagreeing.r_hat > 1.1);
+        assert!(!disagreeing.is_converged(1.0));
+
+        let opposite = Diagnostics::from_results(vec![SimulationResult::new(0, 100), SimulationResult::new(100, 100)]);
+        assert!(opposite.r_hat.is_infinite());
+        let never = Diagnostics::from_results(vec![SimulationResult::new(0, 100), SimulationResult::new(0, 100)]);
+        assert_eq!(never.r_hat, 1.0);
+
+        let mut bag = Bag::from_range(1, 11);
+        bag.set_max_sims(5_000);
+        bag.set_seed(11);
+        let diagnostics = bag.diagnose_sample(5, 2, |values| values.contains(&&1));
+        assert_eq!(diagnostics.replications.len(), 5);
+        assert!(diagnostics.is_converged(0.02));
+        // The replications got different seeds, but seeded bags replicate reproducibly
+        assert!(diagnostics.replications.windows(2).any(|w| w[0] != w[1]));
+        assert_eq!(diagnostics, bag.diagnose_sample(5, 2, |values| values.contains(&&1)));
+    }
+
+    #[test]
+    fn test_replicate() {
+        let mut bag = Bag::from_range(1, 5);
+        bag.set_max_sims(2_000);
+        bag.set_seed(5);
+        let replicates = bag.replicate(4, |v| *v == 1);
+        assert_eq!(replicates.seeds.len(), 4);
+        assert!(replicates.min <= replicates.mean && replicates.mean <= replicates.max);
+        assert!(replicates.spread > 0.0 && replicates.spread < 0.05);
+        assert_eq!(replicates, bag.replicate(4, |v| *v == 1));
+
+        let single = bag.replicate_sample(1, 4, |values| values.len() == 4);
+        assert_eq!(single.estimates, vec![1.0]);
+        assert_eq!((single.spread, single.min, single.max), (0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        use super::{Report, ReportRow};
+
+        let mut result = SimulationResult::new(3, 1_000);
+        result.decided_early = true;
+        let mut bytes = Vec::new();
+        result.save(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 23);
+        assert_eq!(SimulationResult::load(&bytes[..]).unwrap(), result);
+        // Truncated, and not a saved result at all
+        assert!(SimulationResult::load(&bytes[..10]).is_err());
+        assert!(SimulationResult::load(&b"hello, world, this is not it"[..]).is_err());
+
+        let report = Report {
+            sample_size: 3,
+            rows: vec![
+                ReportRow { name: "ünïcode, \"quoted\"".to_string(), result },
+                ReportRow { name: String::new(), result: SimulationResult::new(0, 0) }
+            ]
+        };
+        let mut saved = Vec::new();
+        report.save(&mut saved).unwrap();
+        assert_eq!(Report::load(&saved[..]).unwrap(), report);
+        // A report isn't a result, and vice versa
+        assert!(SimulationResult::load(&saved[..]).is_err());
+        assert!(Report::load(&bytes[..]).is_err());
+        assert!(Report::load(&saved[..saved.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_witnesses() {
+        let mut bag = Bag::from_range(1, 5);
+        bag.set_max_sims(1_000);
+        let witnessed = bag.one_with_witnesses(2, |v| *v == 1);
+        assert_eq!(witnessed.result.trials, 1_000);
+        assert_eq!(witnessed.successes, vec![vec![&1], vec![&1]]);
+        assert_eq!(witnessed.failures.len(), 2);
+        assert!(witnessed.failures.iter().all(|sample| *sample[0] != 1));
+
+        // Never met, so there is nothing to show for it
+        let never = bag.sample_with_witnesses(2, 5, |values| values[0] == values[1]);
+        assert_eq!(never.result.successes, 0);
+        assert!(never.successes.is_empty());
+        assert_eq!(never.failures.len(), 5);
+        assert!(bag.one_with_witnesses(0, |_| true).successes.is_empty());
+    }
+
+    #[test]
+    fn test_sample_traced() {
+        let mut bag = Bag::from_range(0, 10);
+        bag.set_max_sims(500);
+        bag.set_seed(21);
+        let mut trace = Vec::new();
+        let traced = bag.sample_traced(3, |values| values.contains(&&0), &mut trace, TraceFormat::JsonLines).unwrap();
+        // Traces replay the same trials as sample
+        assert_eq!(traced, bag.sample_detailed(3, |values| values.contains(&&0)));
+
+        let trace = String::from_utf8(trace).unwrap();
+        let lines: Vec<&str> = trace.lines().collect();
+        assert_eq!(lines.len(), 500);
+        assert!(lines[0].starts_with("{\"trial\":0,\"indices\":["));
+        assert_eq!(lines.iter().filter(|line| line.ends_with("\"outcome\":true}")).count() as u64, traced.successes);
+
+        let mut csv = Vec::new();
+        bag.sample_traced(3, |values| values.contains(&&0), &mut csv, TraceFormat::Csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let first = csv.lines().nth(1).unwrap();
+        let indices: Vec<usize> = first.split(',').nth(1).unwrap().split(';').map(|i| i.parse().unwrap()).collect();
+        assert_eq!(indices.len(), 3);
+        assert_eq!(first.ends_with("true"), indices.contains(&0));
+    }
+
+    #[cfg(feature = "plot")]
+    #[test]
+    fn test_plot() {
+        use super::Budget;
+
+        let mut bag = Bag::from_vec(vec!["<a>", "b", "b"]);
+        bag.set_max_sims(1_000);
+        let outcomes = bag.sample_max(1, |v| v.len() as f64);
+        let histogram = outcomes.histogram_svg(300, 200);
+        assert!(histogram.starts_with("<svg") && histogram.ends_with("</svg>\n"));
+        assert_eq!(histogram.matches("fill=\"steelblue\"").count(), 2);
+
+        bag.set_track_convergence(true);
+        let convergence = bag.one_detailed(|v| *v == "<a>").convergence_svg(300, 200);
+        let points = convergence.split("points=\"").nth(1).unwrap().split('"').next().unwrap();
+        assert_eq!(points.split(' ').count(), 100);
+        assert!(convergence.contains("1000 simulations"));
+
+        // Without a fixed number of trials the points thin out to stay between 100 and 200
+        bag.set_budget(Budget::Precision { epsilon: 0.005, confidence: 0.95 });
+        let convergence = bag.one_detailed(|v| *v == "<a>").convergence_svg(300, 200);
+        let points = convergence.split("points=\"").nth(1).unwrap().split('"').next().unwrap().split(' ').count();
+        assert!((100..200).contains(&points), "{} points", points);
+    }
+
+    #[test]
+    fn test_render_ascii() {
+        let mut bag = Bag::from_vec(vec![1, 2, 2, 2]);
+        bag.set_seed(1);
+        let outcomes = bag.sample_max(4, |v| *v as f64);
+        assert_eq!(outcomes.render_ascii(4), "2 │████  100.00%\n");
+
+        let outcomes = bag.sample_max(1, |v| *v as f64);
+        let chart = outcomes.render_ascii(8);
+        let lines: Vec<&str> = chart.lines().collect();
+        assert_eq!(lines.len(), 2);
+        // A third of the longest bar, with every bar padded to the same width
+        assert!(lines[0].starts_with("1 │██▋"));
+        assert!(lines[1].starts_with("2 │████████ "));
+        assert_eq!(lines[0].chars().count(), lines[1].chars().count());
+    }
+
+    #[test]
+    fn test_validate() {
+        use super::validate::{binomial, hypergeometric, CountEvent, Validation};
+
+        // C(5, 2) C(5, 1) / C(10, 3)
+        assert!((hypergeometric(10, 5, 3, CountEvent::Exactly(2)) - 50.0 / 120.0).abs() < 1e-12);
+        assert!((hypergeometric(10, 5, 3, CountEvent::AtMost(3)) - 1.0).abs() < 1e-12);
+        assert_eq!(hypergeometric(10, 2, 3, CountEvent::AtLeast(3)), 0.0);
+        assert!((binomial(3, 0.5, CountEvent::AtMost(1)) - 0.5).abs() < 1e-12);
+        assert_eq!(binomial(3, 1.0, CountEvent::Exactly(3)), 1.0);
+        assert_eq!(binomial(3, 0.0, CountEvent::AtLeast(1)), 0.0);
+
+        let validation = Validation::new(SimulationResult::new(26, 100), 0.25);
+        assert!((validation.absolute_error - 0.01).abs() < 1e-12);
+        assert!((validation.relative_error - 0.04).abs() < 1e-12);
+        assert!(validation.is_consistent());
+        assert!(!Validation::new(SimulationResult::new(90, 100), 0.25).is_consistent());
+        assert!(Validation::new(SimulationResult::new(1, 100), 0.0).relative_error.is_infinite());
+
+        let bag = Bag::from_vec(vec![Color::Red, Color::Red, Color::Blue, Color::Green, Color::Blue]);
+        let validation = bag.validate_count(2, |c| *c == Color::Blue, CountEvent::Exactly(1));
+        assert!((validation.exact - 0.6).abs() < 1e-12);
+        assert!(close_enough(validation.simulated.probability(), validation.exact));
+    }
+
+    #[test]
+    fn test_self_test() {
+        let mut bag = Bag::from_range(0, 20);
+        bag.set_seed(8);
+        for kind in [RngKind::Std, RngKind::ChaCha, RngKind::Pcg, RngKind::Xoshiro, RngKind::XorShift] {
+            bag.set_rng_kind(kind);
+            let test = bag.self_test(20_000);
+            assert_eq!(test.degrees_of_freedom, 19);
+            assert!(test.is_uniform, "{:?} failed with p = {}", kind, test.p_value);
+        }
+        assert_eq!(Bag::from_vec(vec![1]).self_test(10).p_value, 1.0);
+
+        // The approximation against known chi-square quantiles
+        assert!((super::math::chi_square_p_value(30.144, 19.0) - 0.05).abs() < 0.002);
+        assert!((super::math::chi_square_p_value(18.338, 19.0) - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_streaming() {
+        let mut bag = Bag::from_range(1, 5);
+        bag.set_max_sims(50_500);
+        bag.set_parallelism(3);
+        let subscription = bag.sample_streaming(2, |values| values.contains(&&1));
+        let result = subscription.wait();
+        assert_eq!(result.trials, 50_500);
+        assert!(close_enough(result.probability(), 0.5));
+
+        // Polling sees the result grow in chunks, up to the final result
+        bag.set_parallelism(1);
+        let mut subscription = bag.one_streaming(|v| *v == 1);
+        let mut seen = Vec::new();
+        while !subscription.is_finished() {
+            seen.push(subscription.poll().trials);
+        }
+        assert!(seen.windows(2).all(|w| w[0] <= w[1]));
+        assert!(seen.iter().all(|trials| *trials % 1_000 == 0 || *trials == 50_500));
+        assert_eq!(subscription.progress(), 1.0);
+
+        // Early stopping ends every worker's stream early
+        bag.set_early_stopping(true);
+        bag.set_parallelism(2);
+        let certain = bag.one_streaming(|v| *v < 5).wait();
+        assert!(certain.decided_early);
+        assert!(certain.trials < 50_500);
+
+        // Parallel runs aggregate the same way
+        let parallel = bag.one_detailed(|v| *v < 5);
+        assert!(parallel.decided_early);
+        assert_eq!(parallel.successes, parallel.trials);
+    }
+
+    #[test]
+    fn test_estimator() {
+        let mut bag = Bag::from_range(1, 5);
+        bag.set_seed(2);
+        bag.set_max_sims(100_000);
+        let mut estimator = bag.estimator(|v| *v == 1);
+        assert_eq!(estimator.result(), SimulationResult::new(0, 0));
+        estimator.run_more(40_000);
+        let result = estimator.run_more(60_000);
+        assert_eq!(result.trials, 100_000);
+        assert!(close_enough(result.probability(), 0.25));
+        // Carrying on the generator gives the same answer as one run
+        assert_eq!(result, bag.one_detailed(|v| *v == 1));
+
+        let mut samples = bag.sample_estimator(4, |values| values.len() == 4);
+        assert_eq!(samples.run_more(10), SimulationResult::new(10, 10));
+        assert_eq!(samples.run_more(0), SimulationResult::new(10, 10));
+    }
+
+    #[test]
+    fn test_bayesian() {
+        use super::{Beta, Posterior};
+
+        // Known quantiles: Beta(2, 3) has its median at 0.3857, Beta(1, 1) is uniform
+        assert!((Beta::new(2.0, 3.0).quantile(0.5) - 0.3857).abs() < 0.0001);
+        assert!((Beta::uniform().quantile(0.25) - 0.25).abs() < 1e-6);
+        assert!((Beta::jeffreys().quantile(0.5) - 0.5).abs() < 1e-6);
+
+        let posterior = Posterior::new(Beta::uniform(), SimulationResult::new(3, 10));
+        assert_eq!(posterior.distribution, Beta::new(4.0, 8.0));
+        assert!((posterior.mean() - 1.0 / 3.0).abs() < 1e-12);
+        // Beta(4, 8) quantiles at 2.5% and 97.5%
+        let (low, high) = posterior.credible_interval();
+        assert!((low - 0.1093).abs() < 0.001 && (high - 0.6097).abs() < 0.001);
+        let (narrow_low, narrow_high) = posterior.credible_interval_at(0.5);
+        assert!(low < narrow_low && narrow_high < high);
+
+        let bag = Bag::from_range(1, 5);
+        let posterior = bag.sample_bayesian(2, |values| values.contains(&&1), Beta::uniform());
+        assert!(close_enough(posterior.mean(), 0.5));
+        assert_eq!(posterior.result.trials, 100_000);
+    }
+
+    #[test]
+    fn test_bayes() {
+        use super::bayes::posterior;
+
+        // Exact likelihoods give the textbook answer and no uncertainty
+        let certain = posterior(0.5, &SimulationResult::new(10, 10), &SimulationResult::new(0, 10));
+        assert_eq!(certain.probability, 1.0);
+        assert_eq!(certain.std_error, 0.0);
+
+        let result = posterior(0.25, &SimulationResult::new(800, 1_000), &SimulationResult::new(200, 1_000));
+        // 0.2 / (0.2 + 0.15)
+        assert!((result.probability - 4.0 / 7.0).abs() < 1e-12);
+        assert!(result.std_error > 0.0 && result.std_error < 0.05);
+        let (low, high) = result.confidence_interval();
+        assert!(low < result.probability && result.probability < high);
+
+        // More simulations, less uncertainty
+        let more = posterior(0.25, &SimulationResult::new(80_000, 100_000), &SimulationResult::new(20_000, 100_000));
+        assert!((more.std_error * 10.0 - result.std_error).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rare_event() {
+        use super::validate::{hypergeometric, CountEvent};
+
+        let mut bag = Bag::from_range(0, 1_000);
+        bag.set_max_sims(20_000);
+        bag.set_seed(4);
+        let special = |sample: &[&i32]| sample.iter().filter(|v| ***v < 10).count() as f64;
+        let rare = bag.rare_event(6, &[1.0, 2.0, 3.0, 4.0], special);
+        assert_eq!(rare.level_probabilities.len(), 4);
+        assert_eq!(rare.level_trials, vec![20_000; 4]);
+        let exact = hypergeometric(1_000, 10, 6, CountEvent::AtLeast(4));
+        assert!((rare.probability / exact - 1.0).abs() < 0.25, "{} vs {}", rare.probability, exact);
+
+        // Common events agree with plain simulation
+        let small = Bag::from_range(0, 10);
+        let common = small.rare_event(3, &[2.0], |sample| sample.iter().filter(|v| ***v < 5).count() as f64);
+        assert!(close_enough(common.probability, 0.5));
+
+        // An impossible level stops the splitting
+        let impossible = small.rare_event(2, &[1.0, 3.0, 4.0], |sample| sample.len() as f64);
+        assert_eq!(impossible.probability, 0.0);
+        assert_eq!(impossible.level_probabilities, vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_testing_helpers() {
+        use super::testing::with_fixed_seed;
+
+        let bag = Bag::from_range(0, 100);
+        let odds = |bag: &Bag<i32>| bag.sample(3, |s| s.iter().any(|v| **v < 10));
+        let first = with_fixed_seed(11, || odds(&bag));
+        assert_eq!(first, with_fixed_seed(11, || odds(&bag)));
+        assert_prob_close!(first, 1.0 - (90.0 * 89.0 * 88.0) / (100.0 * 99.0 * 98.0), 0.01);
+
+        // The bag's own seed wins over the fixed one
+        let mut seeded = bag.clone();
+        seeded.set_seed(5);
+        assert_eq!(with_fixed_seed(11, || odds(&seeded)), with_fixed_seed(12, || odds(&seeded)));
+
+        // The fixed seed is cleared again, even after a panic
+        let panicked = ::std::panic::catch_unwind(|| with_fixed_seed(11, || assert_prob_close!(0.5, 0.6, 0.05)));
+        assert!(panicked.is_err());
+        assert_eq!(::rng::fixed_seed(), None);
+    }
+
+    #[test]
+    fn test_sample_weighted_by() {
+        let mut bag = Bag::from_vec(vec![(1, 1.0), (2, 2.0), (3, 3.0), (4, 0.0)]);
+        bag.set_seed(8);
+        // First draw 3 with 3/6, then 2 with 2/3: the ordered pair (3, 2) has probability 1/3
+        let odds = bag.sample_weighted_by(2, |i| i.1, |s| s[0].0 == 3 && s[1].0 == 2);
+        assert!(close_enough(odds, 1.0 / 3.0));
+        // Unordered {1, 2}: 1/6 * 2/5 + 2/6 * 1/4
+        let odds = bag.sample_weighted_by(2, |i| i.1, |s| s.iter().all(|i| i.0 < 3));
+        assert!(close_enough(odds, 1.0 / 15.0 + 1.0 / 12.0));
+        // The zero weight item is never drawn, even when every other item is
+        assert_eq!(bag.sample_weighted_by(3, |i| i.1, |s| s.iter().any(|i| i.0 == 4)), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_sample_weighted_by_too_few_weights() {
+        let bag = Bag::from_vec(vec![(1, 1.0), (2, 0.0)]);
+        bag.sample_weighted_by(2, |i| i.1, |_| true);
+    }
+
+    #[test]
+    fn test_survey_sampling() {
+        let mut bag = Bag::from_range(0, 10);
+        bag.set_seed(13);
+        // Starts 0 and 1 give 3 items, starts 2 and 3 give 2
+        let three = bag.systematic_sample(4, |s| s.len() == 3);
+        assert!(close_enough(three, 0.5));
+        assert_eq!(bag.systematic_sample(1, |s| s.len() == 10), 1.0);
+        assert!(close_enough(bag.systematic_sample(20, |s| s.len() == 1), 0.5));
+
+        let evens = bag.cluster_sample(|v| v % 2, 1, |s| s.len() == 5 && s.iter().all(|v| *v % 2 == 0));
+        assert!(close_enough(evens, 0.5));
+        assert_eq!(bag.cluster_sample(|v| v % 3, 3, |s| s.len() == 10), 1.0);
+        assert_eq!(bag.cluster_sample(|v| v / 5, 0, |s| s.is_empty()), 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_cluster_sample_too_many_clusters() {
+        Bag::from_range(0, 10).cluster_sample(|v| v % 2, 3, |_| true);
+    }
+
+    #[test]
+    fn test_pair_sample() {
+        let mut balls = Bag::from_counts(vec![(Color::Red, 3), (Color::Blue, 1)]);
+        balls.set_seed(21);
+        let numbers = Bag::from_range(1, 5);
+        let odds = Bag::pair_sample(&balls, &numbers, |b, n| *b == Color::Red && *n > 2);
+        assert!(close_enough(odds, 0.75 * 0.5));
+        // The same seed gives the same pairs
+        assert_eq!(odds, Bag::pair_sample(&balls, &numbers, |b, n| *b == Color::Red && *n > 2));
+    }
+
+    #[test]
+    fn test_independence_test() {
+        let mut bag = Bag::from_vec((0..100).map(|i| (i % 2, if i < 80 { i % 2 } else { 1 - i % 2 })).collect());
+        bag.set_max_sims(1_000);
+        bag.set_seed(2);
+        // 80 of 100 items agree: the table is 40/10/10/40, chi-square 36
+        let result = bag.independence_test(|v| v.0, |v| v.1);
+        assert!((result.chi_square - 36.0).abs() < 1e-9);
+        assert!((result.cramers_v - 0.6).abs() < 1e-9);
+        assert_eq!(result.permutations, 1_000);
+        assert!(!result.is_independent);
+        assert!(result.p_value < 0.01);
+
+        let constant = bag.independence_test(|v| v.0, |_| 0);
+        assert!(constant.is_independent);
+        assert_eq!(constant.cramers_v, 0.0);
+    }
+
+    #[test]
+    fn test_entropy() {
+        let mut balls = Bag::from_counts(vec![(Color::Red, 2), (Color::Blue, 1), (Color::Green, 1)]);
+        assert_eq!(balls.entropy(|b| b.clone()), 1.5);
+        assert_eq!(balls.entropy(|_| 0), 0.0);
+        assert_eq!(Bag::from_vec(Vec::<i32>::new()).entropy(|v| *v), 0.0);
+        balls.set_max_sims(20_000);
+        balls.set_seed(6);
+        assert!((balls.simulated_entropy(|b| b.clone()) - 1.5).abs() < 0.01);
+
+        let frequencies = Bag::from_frequencies(vec![(Color::Red, 2), (Color::Blue, 1), (Color::Green, 1), (Color::Red, 0)]);
+        assert_eq!(frequencies.entropy(|b| b.clone()), 1.5);
+    }
+
+    #[test]
+    fn test_diversity() {
+        let mut balls = Bag::from_counts(vec![(Color::Red, 2), (Color::Blue, 1), (Color::Green, 1)]);
+        balls.set_seed(9);
+        let diversity = balls.diversity(|b| b.clone());
+        assert_eq!(diversity.simpson, 0.375);
+        assert_eq!(diversity.gini_simpson, 0.625);
+        assert!((diversity.inverse_simpson - 8.0 / 3.0).abs() < 1e-12);
+        assert_eq!(diversity.shannon, 1.5);
+        assert!(close_enough(diversity.simulated_differ, 0.625));
+
+        let same = balls.diversity(|_| 0);
+        assert_eq!((same.simpson, same.gini_simpson, same.simulated_differ), (1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_sample_overlap() {
+        let mut bag = Bag::from_range(0, 4);
+        bag.set_seed(10);
+        // Two pairs out of 4 share 0 items with probability 1/6, 2 items with 1/6 and 1 item otherwise
+        let overlap = bag.sample_overlap(2, 2);
+        assert!(close_enough(overlap.shared.probability(&0), 1.0 / 6.0));
+        assert!(close_enough(overlap.shared.probability(&2), 1.0 / 6.0));
+        assert!(close_enough(overlap.any_shared(), 5.0 / 6.0));
+        assert!(close_enough(overlap.expected_jaccard, (4.0 / 6.0) / 3.0 + 1.0 / 6.0));
+
+        let everything = bag.sample_overlap(4, 1);
+        assert_eq!(everything.shared.probability(&1), 1.0);
+        assert_eq!(everything.expected_jaccard, 0.25);
+    }
+
+    #[test]
+    fn test_event_matrix() {
+        let mut bag = Bag::from_range(1, 5);
+        bag.set_seed(14);
+        let events = Events::new()
+            .add("even", |values: Vec<&i32>| *values[0] % 2 == 0)
+            .add("small", |values: Vec<&i32>| *values[0] <= 2)
+            .add("never", |_| false);
+        let matrix = bag.event_matrix(1, &events);
+        assert_eq!(matrix.names, vec!["even", "small", "never"]);
+        assert!(close_enough(matrix.probability(0, 0), 0.5));
+        assert!(close_enough(matrix.get("even", "small").unwrap(), 0.25));
+        assert_eq!(matrix.joint[0][1], matrix.joint[1][0]);
+        assert_eq!(matrix.get("never", "even"), Some(0.0));
+        assert_eq!(matrix.get("even", "missing"), None);
+    }
+
+    #[test]
+    fn test_counting_helpers() {
+        let mut balls = Bag::from_counts(vec![(Color::Red, 3), (Color::Blue, 3)]);
+        balls.set_seed(15);
+        let is_red = |b: &Color| *b == Color::Red;
+        // Reds among 2 of 6: 0 with 1/5, 1 with 3/5, 2 with 1/5
+        let reds = balls.count_in_sample(2, is_red);
+        assert_eq!(reds.trials, balls.max_sims as u64);
+        assert!(close_enough(reds.probability(&0), 0.2));
+        assert!(close_enough(reds.probability(&1), 0.6));
+        assert!(close_enough(reds.at_least(&2), 0.2));
+        assert!(close_enough(balls.at_most(1, 2, is_red), 0.8));
+        assert!(close_enough(balls.exactly(1, 2, is_red), 0.6));
+        assert!(close_enough(balls.at_least(1, 2, is_red), 0.8));
+        assert_eq!(balls.at_least(0, 2, is_red), 1.0);
+    }
+
+    #[test]
+    fn test_draw_and_remove() {
+        let mut bag = Bag::from_range(0, 10);
+        let mut rng = StdRng::seed_from_u64(16);
+        let first = bag.draw_and_remove(4, &mut rng);
+        let second = bag.draw_and_remove(6, &mut rng);
+        assert!(bag.items.is_empty());
+        let mut all: Vec<i32> = first.into_iter().chain(second).collect();
+        all.sort();
+        assert_eq!(all, (0..10).collect::<Vec<i32>>());
+
+        // The same generator state draws the same items
+        let mut again = Bag::from_range(0, 10);
+        let mut rng = StdRng::seed_from_u64(16);
+        let mut other = StdRng::seed_from_u64(16);
+        assert_eq!(again.clone().draw_and_remove(3, &mut rng), again.draw_and_remove(3, &mut other));
+    }
+
+    #[test]
+    fn test_budget() {
+        use std::time::Duration;
+        use super::Budget;
+
+        let mut bag = Bag::from_range(0, 10);
+        bag.set_seed(17);
+        bag.set_budget(Budget::Trials(2_500));
+        assert_eq!(bag.max_sims, 2_500);
+        assert_eq!(bag.one_detailed(|v| *v < 5).trials, 2_500);
+
+        // Precision stops once the interval is narrow enough, in whole checks of 100 simulations
+        bag.set_budget(Budget::Precision { epsilon: 0.02, confidence: 0.95 });
+        let result = bag.one_detailed(|v| *v < 5);
+        let (low, high) = result.confidence_interval();
+        assert!(high - low <= 0.04);
+        assert!(result.trials > 2_000 && result.trials < 2_600);
+        assert!(result.trials % 100 == 0);
+        // Results without a single probability run enough trials for a probability of 1/2
+        assert_eq!(bag.count_in_sample(2, |v| *v < 5).trials, 2_401);
+
+        // Parallel workers split the precision between them
+        bag.set_parallelism(4);
+        let (low, high) = bag.one_detailed(|v| *v < 5).confidence_interval();
+        assert!(high - low <= 0.045);
+        bag.set_parallelism(1);
+
+        bag.set_budget(Budget::Time(Duration::from_millis(5)));
+        assert!(bag.sample_detailed(2, |s| s.len() == 2).trials > 0);
+        assert!(bag.report(1, &Events::new().add("small", |s: Vec<&i32>| *s[0] < 5)).rows[0].result.trials > 0);
+
+        // Setting max_sims goes back to counting simulations
+        bag.set_max_sims(300);
+        assert_eq!(bag.budget, None);
+        assert_eq!(bag.one_detailed(|v| *v < 5).trials, 300);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_budget_invalid_precision() {
+        use super::Budget;
+
+        Bag::from_range(0, 10).set_budget(Budget::Precision { epsilon: 0.0, confidence: 0.95 });
+    }
+
+    #[test]
+    fn test_estimate_given() {
+        let mut balls = Bag::from_vec(vec![(Color::Red, 1.0), (Color::Red, 2.0), (Color::Blue, 10.0), (Color::Green, 3.0)]);
+        balls.set_seed(18);
+        balls.set_max_sims(20_000);
+        let no_blue = |s: &[&(Color, f64)]| s.iter().all(|b| b.0 != Color::Blue);
+        let estimate = balls.estimate_given(2, |s| s.iter().map(|b| b.1).sum(), no_blue);
+        // Pairs without blue: {1, 2}, {1, 3}, {2, 3}, half of all 6 pairs
+        assert!((estimate.mean - 4.0).abs() < 0.05);
+        assert!(close_enough(estimate.acceptance_rate(), 0.5));
+        assert_eq!(estimate.trials, 20_000);
+        assert!(estimate.std_error > 0.0 && estimate.std_error < 0.02);
+
+        let never = balls.estimate_given(1, |s| s[0].1, |_| false);
+        assert_eq!(never.accepted, 0);
+        assert!(never.mean.is_nan());
+    }
+
+    #[test]
+    fn test_scenario_config() {
+        use std::time::Duration;
+        use super::{Budget, Scenario};
+
+        let events = || Events::new().add("red", |balls: Vec<&Color>| balls.contains(&&Color::Red));
+        let balls = Bag::from_counts(vec![(Color::Red, 1), (Color::Blue, 3)]);
+        let scenario = Scenario::new("red ball", balls.clone(), events())
+            .sample_size(1)
+            .sample_size(2)
+            .budget(Budget::Precision { epsilon: 0.01, confidence: 0.9 })
+            .seed(3);
+        let config = scenario.to_config();
+        let loaded = Scenario::from_config(&config, balls.clone(), events()).unwrap();
+        assert_eq!(loaded.to_config(), config);
+        assert_eq!(loaded.run(), scenario.run());
+        let report = loaded.run();
+        assert!(close_enough(report.get(2).unwrap().get("red").unwrap().probability(), 0.5));
+        assert!(report.get(3).is_none());
+        assert!(report.to_string().starts_with("red ball\n\nsample size 1\n"));
+
+        let timed = Scenario::from_config("name = t\nsample_sizes = 1\nbudget = seconds 0.25", balls.clone(), events()).unwrap();
+        assert_eq!(timed.budget, Some(Budget::Time(Duration::from_millis(250))));
+        assert_eq!(timed.seed, None);
+
+        for invalid in ["sample_sizes = 1", "name = x", "name = x\nsample_sizes = one",
+                        "name = x\nsample_sizes = 1\ncolor = red", "name = x\nsample_sizes = 1\nbudget = lots"] {
+            assert!(Scenario::from_config(invalid, balls.clone(), events()).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut bag = Bag::from_range(0, 20);
+        bag.set_seed(19);
+        bag.set_max_sims(2_000);
+        let fork = bag.fork();
+        assert_eq!(fork.one_detailed(|v| *v < 3), bag.one_detailed(|v| *v < 3));
+        bag.set_seed(20);
+        assert_eq!(fork.seed, Some(19));
+
+        let mut estimator = fork.sample_estimator(2, |s| s.iter().any(|v| **v == 0));
+        estimator.run_more(300);
+        let mut branches = (estimator.fork(), estimator.fork());
+        let first = branches.0.run_more(700);
+        assert_eq!(first, branches.1.run_more(700));
+        assert_eq!(estimator.result().trials, 300);
+        // Carrying on is the same as having run everything at once
+        assert_eq!(first, fork.sample_estimator(2, |s| s.iter().any(|v| **v == 0)).run_more(1_000));
+    }
+
+    #[test]
+    fn test_all_distinct() {
+        let mut balls = Bag::from_counts(vec![(Color::Red, 2), (Color::Blue, 1), (Color::Green, 1)]);
+        balls.set_seed(22);
+        // Pairs of 4 balls: all but the 2 reds together, 5 of 6
+        assert!((balls.all_distinct_exact(2, |b| b.clone()) - 5.0 / 6.0).abs() < 1e-12);
+        assert!(close_enough(balls.all_distinct(2, |b| b.clone()), 5.0 / 6.0));
+        // Triples need one of each color, 2 of 4
+        assert!((balls.all_distinct_exact(3, |b| b.clone()) - 0.5).abs() < 1e-12);
+        assert_eq!(balls.all_distinct_exact(4, |b| b.clone()), 0.0);
+        assert_eq!(balls.all_distinct_exact(5, |b| b.clone()), 0.0);
+        assert_eq!(balls.all_distinct_exact(0, |b| b.clone()), 1.0);
+        assert_eq!(balls.all_distinct(4, |b| b.clone()), 0.0);
+    }
+
+    #[test]
+    fn test_result_sinks() {
+        use std::io;
+        use super::{JsonLinesSink, ResultSink};
+
+        struct Recorder {
+            updates: Vec<u64>,
+            completed: Option<SimulationResult>
+        }
+
+        impl ResultSink for Recorder {
+            fn on_progress(&mut self, partial: &SimulationResult) -> io::Result<()> {
+                self.updates.push(partial.trials);
+                Ok(())
+            }
+
+            fn on_complete(&mut self, result: &SimulationResult) -> io::Result<()> {
+                self.completed = Some(result.clone());
+                Ok(())
+            }
+        }
+
+        let mut bag = Bag::from_range(0, 10);
+        bag.set_max_sims(2_500);
+        bag.set_seed(23);
+        let mut recorder = Recorder { updates: Vec::new(), completed: None };
+        let result = bag.sample_to_sink(2, |s| s.contains(&&0), &mut recorder).unwrap();
+        assert_eq!(recorder.updates, vec![1_000, 2_000, 2_500]);
+        assert_eq!(recorder.completed.as_ref(), Some(&result));
+        assert_eq!(result, bag.sample_detailed(2, |s| s.contains(&&0)));
+
+        // Parallel workers report to the calling thread, ending with the whole run
+        bag.set_parallelism(2);
+        let mut recorder = Recorder { updates: Vec::new(), completed: None };
+        let result = bag.one_to_sink(|v| *v < 3, &mut recorder).unwrap();
+        assert_eq!(recorder.updates.last(), Some(&2_500));
+        assert_eq!(recorder.completed, Some(result));
+
+        let mut sink = JsonLinesSink::new(Vec::new());
+        bag.one_to_sink(|v| *v < 3, &mut sink).unwrap();
+        let json = String::from_utf8(sink.into_inner()).unwrap();
+        assert!(json.lines().all(|line| line.starts_with("{\"trials\":") && line.ends_with('}')));
+        assert!(json.lines().last().unwrap().ends_with("\"complete\":true}"));
+
+        // Without any trials the probability is NaN, which JSON has no number for
+        let mut sink = JsonLinesSink::new(Vec::new());
+        sink.on_complete(&SimulationResult::new(0, 0)).unwrap();
+        let json = String::from_utf8(sink.into_inner()).unwrap();
+        assert!(json.starts_with("{\"trials\":0,\"successes\":0,\"probability\":null,"));
+        assert!(!json.contains("NaN"));
+    }
+
+    #[test]
+    fn test_mixture() {
+        let reds = Bag::from_counts(vec![(Color::Red, 3), (Color::Blue, 1)]);
+        let greens = Bag::from_counts(vec![(Color::Green, 1)]);
+        let mut mixture = Bag::mixture(vec![(reds, 1.0), (greens, 3.0)]);
+        mixture.set_seed(24);
+        assert!(close_enough(mixture.one(|b| *b == Color::Red), 0.25 * 0.75));
+        assert!(close_enough(mixture.one(|b| *b == Color::Green), 0.75));
+        mixture.set_max_sims(500);
+        assert_eq!(mixture.one_detailed(|_| true).trials, 500);
+
+        // A zero weight source is never picked, so its too small size doesn't matter
+        let mut only_reds = Bag::mixture(vec![(Bag::from_counts(vec![(Color::Red, 2)]), 1.0), (Bag::from_vec(vec![Color::Blue]), 0.0)]);
+        only_reds.set_seed(25);
+        assert_eq!(only_reds.sample(2, |s| s.iter().all(|b| **b == Color::Red)), 1.0);
+    }
+
+    #[test]
+    fn test_streak() {
+        let mut balls = Bag::from_counts(vec![(Color::Red, 2), (Color::Blue, 2)]);
+        balls.set_seed(26);
+        let is_red = |b: &Color| *b == Color::Red;
+        // All 4 in order: the reds are adjacent in 3 of the 6 arrangements
+        assert!(close_enough(balls.streak(4, 2, is_red), 0.5));
+        // In 2 draws both must be red, 1 of 6
+        assert!(close_enough(balls.streak(2, 2, is_red), 1.0 / 6.0));
+        assert_eq!(balls.streak(4, 3, is_red), 0.0);
+        assert_eq!(balls.streak(4, 1, is_red), 1.0);
+        assert_eq!(balls.streak(0, 0, is_red), 1.0);
+    }
+
+    #[test]
+    fn test_positional_marginals() {
+        let mut balls = Bag::from_counts(vec![(Color::Red, 1), (Color::Blue, 3)]);
+        balls.set_seed(27);
+        balls.set_max_sims(20_000);
+        let is_red = |b: &Color| *b == Color::Red;
+        let marginals = balls.positional_marginals(4, is_red);
+        assert_eq!(marginals.len(), 4);
+        assert!(marginals.iter().all(|p| close_enough(*p, 0.25)));
+        assert!((marginals.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+
+        // Drawing with replacement keeps every position at 1/4, removing the reds drawn runs out of them
+        let replaced = balls.positional_marginals_dynamic(3, |drawn, population| population.push(drawn.clone()), is_red);
+        assert!(replaced.iter().all(|p| close_enough(*p, 0.25)));
+        let emptied = balls.positional_marginals_dynamic(5, |_, _| {}, is_red);
+        assert_eq!(emptied[4], 0.0);
+    }
+
+    #[test]
+    fn test_query_batch() {
+        let mut my_bag = Bag::from_range(1, 11);
+        my_bag.set_seed(8);
+        let batch = my_bag.batch()
+            .add("has a 1", 2, |values| values.contains(&&1))
+            .add("sum over 15", 2, |values| values.iter().cloned().sum::<i32>() > 15)
+            .add("all odd", 3, |values| values.iter().all(|v| **v % 2 == 1));
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch.passes(), 2);
+        let answers = batch.run();
+        assert_eq!(answers.reports.len(), 2);
+        assert_eq!(answers.reports[0].sample_size, 2);
+        assert_eq!(answers.reports[0].rows.len(), 2);
+        assert!(close_enough(answers.get("has a 1").unwrap().probability(), 0.2));
+        // C(5, 3) / C(10, 3)
+        assert!(close_enough(answers.get("all odd").unwrap().probability(), 10.0 / 120.0));
+        assert!(answers.get("missing").is_none());
+
+        // Queries sharing a sample size see the same samples
+        let events = Events::new()
+            .add("has a 1", |values| values.contains(&&1))
+            .add("sum over 15", |values| values.iter().cloned().sum::<i32>() > 15);
+        assert_eq!(answers.reports[0], my_bag.report(2, &events));
+    }
+
+    #[test]
+    fn test_picks_needed() {
+        let mut bag = Bag::from_range(0, 100);
+        bag.set_seed(13);
+        // 1 - C(95, k) / C(100, k) first reaches 90% at 37, with 36 just short at 89.87%
+        let needed = bag.picks_needed(0.9, |values| values.iter().any(|v| **v < 5)).unwrap();
+        assert!(needed == 36 || needed == 37, "{}", needed);
+        assert_eq!(bag.picks_needed(0.0, |_| false), Some(0));
+        assert_eq!(bag.picks_needed(1.0, |values| values.len() >= 50), Some(50));
+        assert_eq!(bag.picks_needed(0.5, |values| values.len() > 100), None);
+    }
+
+    #[test]
+    fn test_picks_needed_exact_target_with_time_budget() {
+        use std::time::Duration;
+        use super::Budget;
+        let mut bag = Bag::from_range(1, 11);
+        bag.set_budget(Budget::Time(Duration::from_millis(20)));
+        bag.set_seed(14);
+        // 5 picks are exactly a coin flip, so no number of simulations rules them in or out
+        let needed = bag.picks_needed(0.5, |values| values.contains(&&1));
+        assert!(needed == Some(5) || needed == Some(6), "{:?}", needed);
+    }
+
+    #[test]
+    fn test_combined_probability() {
+        use super::CombinedProbability;
+
+        let a = SimulationResult::new(500, 1_000);
+        let b = SimulationResult::new(200, 1_000);
+        let both = a.and(&b);
+        assert!((both.probability - 0.1).abs() < 1e-12);
+        // Var(XY) = b^2 Var(X) + a^2 Var(Y) + Var(X) Var(Y)
+        let (va, vb): (f64, f64) = (0.25 / 1_000.0, 0.16 / 1_000.0);
+        assert!((both.std_error - (0.04 * va + 0.25 * vb + va * vb).sqrt()).abs() < 1e-12);
+
+        let either = a.or(&b);
+        assert!((either.probability - 0.6).abs() < 1e-12);
+        assert!((either.std_error - (0.64 * va + 0.25 * vb + va * vb).sqrt()).abs() < 1e-12);
+
+        // Chains keep propagating
+        let chained = a.and(&b).or(SimulationResult::new(100, 1_000));
+        assert!((chained.probability - (1.0 - 0.9 * 0.9)).abs() < 1e-12);
+        assert!(chained.std_error > both.std_error);
+
+        let conditional = both.given(&b);
+        assert!((conditional.probability - 0.5).abs() < 1e-12);
+        assert!(conditional.std_error > a.std_error());
+        let exact = CombinedProbability { probability: 0.25, std_error: 0.0 };
+        assert_eq!(exact.and(exact), CombinedProbability { probability: 0.0625, std_error: 0.0 });
+        assert_eq!(exact.confidence_interval(), (0.25, 0.25));
+    }
+
+    #[test]
+    fn test_weighted_range() {
+        // 1 - 100 weighted by value, P(v > 50) = 1 - 1275 / 5050
+        let mut values = Bag::from_weighted_range(1, 101, |v| v as f64);
+        values.set_seed(6);
+        values.set_max_sims(20_000);
+        assert_eq!(values.len(), 100);
+        assert!(close_enough(values.one(|v| *v > 50), 3775.0 / 5050.0));
+        assert_eq!(values.one(|v| (1..=100).contains(v)), 1.0);
+
+        // Blocks hold many values, and zero weights are never picked
+        let mut sparse = Bag::from_weighted_range(-100_000, 100_000, |v| if v == -7 || v == 99_999 { 1.0 } else { 0.0 });
+        sparse.set_seed(6);
+        sparse.set_max_sims(20_000);
+        sparse.set_parallelism(2);
+        assert_eq!(sparse.one(|v| *v == -7 || *v == 99_999), 1.0);
+        assert!(close_enough(sparse.sample(2, |picked| picked[0] == picked[1]), 0.5));
+    }
+
+    #[test]
+    fn test_weighted_range_computes_weights_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let calls = AtomicUsize::new(0);
+        let mut values = Bag::from_weighted_range(0, 10_000, |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            1.0
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        values.set_max_sims(0);
+        values.one(|_| true);
+        assert_eq!(calls.load(Ordering::SeqCst), 10_000);
+        values.one(|_| true);
+        assert_eq!(calls.load(Ordering::SeqCst), 10_000);
+        // Equal weights accept the first value tried, so every pick costs one call
+        values.set_max_sims(100);
+        values.one(|_| true);
+        assert_eq!(calls.load(Ordering::SeqCst), 10_100);
+    }
+
+    #[test]
+    fn test_precompute() {
+        let mut rows = Bag::from_frequencies(vec![(Color::Red, 7), (Color::Blue, 0), (Color::Green, 3)]);
+        rows.set_max_sims(20_000);
+        rows.set_seed(2);
+        let plain = rows.one(|c| *c == Color::Red);
+        rows.precompute();
+        let precomputed = rows.one(|c| *c == Color::Red);
+        assert!(close_enough(plain, 0.7) && close_enough(precomputed, 0.7));
+        assert_eq!(rows.one(|c| *c == Color::Blue), 0.0);
+
+        // Small samples redraw repeated individuals, larger ones fall back to drawing indices
+        // C(7, 2) / C(10, 2) and C(7, 6) / C(10, 6)
+        assert!(close_enough(rows.sample(2, |picked| picked.iter().all(|c| **c == Color::Red)), 21.0 / 45.0));
+        assert!(close_enough(rows.sample(6, |picked| picked.iter().all(|c| **c == Color::Red)), 7.0 / 210.0));
+        rows.set_parallelism(2);
+        assert_eq!(rows.sample(3, |picked| picked.len() == 3), 1.0);
+        assert_eq!(rows.sample_detailed(2, |picked| picked[0] == &Color::Green), rows.sample_detailed(2, |picked| picked[0] == &Color::Green));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut bag = Bag::from_range(1, 11);
+        bag.set_seed(12);
+        let values = bag.histogram(1, |s| *s[0] as f64, &[2.0, 4.0, 6.0, 10.0]);
+        assert_eq!(values.counts.iter().sum::<u64>() + values.below + values.above, values.trials);
+        // 1 is below, 2 - 3, 4 - 5 and 6 - 10 land in the bins, nothing is above the closed last bin
+        assert!(close_enough(values.below as f64 / values.trials as f64, 0.1));
+        assert_eq!(values.above, 0);
+        let bins = values.bins();
+        assert_eq!((bins[2].0, bins[2].1), (6.0, 10.0));
+        assert!(close_enough(bins[0].2, 0.2) && close_enough(bins[1].2, 0.2) && close_enough(bins[2].2, 0.5));
+
+        let narrow = bag.histogram(1, |s| *s[0] as f64, &[0.0, 5.0]);
+        // The last bin includes 5
+        assert!(close_enough(narrow.probabilities()[0], 0.5));
+        assert!(close_enough(narrow.above as f64 / narrow.trials as f64, 0.5));
+    }
+
+    #[test]
+    fn test_profile() {
+        use std::time::Duration;
+
+        let bag = Bag::from_range(1, 101);
+        let slow = bag.profile(|v| {
+            thread::sleep(Duration::from_micros(if *v == 1 { 500 } else { 50 }));
+            *v % 2 == 0
+        }, 50);
+        assert_eq!(slow.trials, 50);
+        assert!(slow.predicate_median >= Duration::from_micros(50));
+        assert!(slow.predicate_median <= slow.predicate_p90 && slow.predicate_p90 <= slow.predicate_max);
+        assert!(slow.predicate_time >= Duration::from_micros(50 * 50));
+        // Sleeping dwarfs grabbing items
+        assert!(slow.rng_share() < 0.5);
+        assert!(slow.projected_runtime(1_000) >= slow.per_trial() * 999);
+        assert!(slow.to_string().contains("1,000,000 sims"));
+    }
+
+    #[test]
+    fn test_whole_bag_sample() {
+        let calls = Arc::new(Mutex::new(0));
+        let mut bag = Bag::from_range(0, 1_000);
+        bag.set_max_sims(5_000);
+        let counted = Arc::clone(&calls);
+        let result = bag.sample_detailed(1_000, move |values| {
+            *counted.lock().unwrap() += 1;
+            values.iter().map(|v| **v as i64).sum::<i64>() == 499_500
+        });
+        assert_eq!(result, SimulationResult::new(5_000, 5_000));
+        assert_eq!(*calls.lock().unwrap(), 1);
+        assert_eq!(bag.sample(1_000, |values| values.len() < 1_000), 0.0);
+        // Nearly the whole bag still simulates
+        assert!(close_enough(bag.sample(999, |values| values.contains(&&0)), 0.999));
+    }
+
+    #[test]
+    fn test_restocking() {
+        let mut balls = Bag::from_counts(vec![(Color::Red, 1), (Color::Blue, 3)]);
+        balls.set_seed(9);
+        // Putting every ball back is the same as drawing from the full bag each round
+        let mut restocked = balls.rounds(2, 1).restocking(|ball| vec![ball.clone()]);
+        restocked.set_max_sims(20_000);
+        let red_second = |reds: &Vec<bool>| reds[1];
+        let update = |reds: &mut Vec<bool>, drawn: Vec<&Color>| { reds.push(*drawn[0] == Color::Red); true };
+        assert!(close_enough(restocked.run(Vec::new, update, red_second).probability(), 0.25));
+
+        // Keeping them out is the same as without replacement, 1/4 * 3/3 + 3/4 * 1/3
+        let kept_out = balls.rounds(3, 1).restocking(|_| Vec::new());
+        assert!(close_enough(kept_out.run(Vec::new, update, |reds| reds.iter().any(|r| *r)).probability(), 0.75));
+
+        // Two reds go back for every red drawn
+        let growing = balls.rounds(2, 1).restocking(|ball| vec![ball.clone(); if *ball == Color::Red { 2 } else { 1 }]);
+        // 1/4 * 2/5 + 3/4 * 1/4
+        assert!(close_enough(growing.run(Vec::new, update, red_second).probability(), 0.1 + 0.1875));
+    }
+
+    #[test]
+    #[should_panic(expected = "larger than the 0 items left")]
+    fn test_restocking_runs_out() {
+        Bag::from_range(1, 3).rounds(2, 1).restocking(|_| Vec::new()).run(|| (), |_, _| true, |_| true);
+        Bag::from_range(1, 3).rounds(2, 2).restocking(|_| Vec::new()).run(|| (), |_, _| true, |_| true);
+    }
+
+    #[test]
+    fn test_result_cache() {
+        use std::fs;
+        use super::ResultCache;
+
+        let mut bag = Bag::from_range(1, 11);
+        bag.set_max_sims(1_000);
+        let mut cache = ResultCache::new();
+        let even = cache.get_or_run(&bag, "even", |bag| bag.one_detailed(|v| v % 2 == 0)).unwrap();
+        assert_eq!(cache.get_or_run(&bag, "even", |_| panic!("simulated again")).unwrap(), even);
+        // A different query, composition or budget is simulated
+        let odd = cache.get_or_run(&bag, "odd", |_| SimulationResult::new(1, 2)).unwrap();
+        assert_eq!(odd, SimulationResult::new(1, 2));
+        let mut bigger = Bag::from_range(1, 12);
+        bigger.set_max_sims(1_000);
+        assert_eq!(cache.get_or_run(&bigger, "even", |_| SimulationResult::new(3, 4)).unwrap(), SimulationResult::new(3, 4));
+        bag.set_max_sims(2_000);
+        assert_eq!(cache.get_or_run(&bag, "even", |_| SimulationResult::new(5, 6)).unwrap(), SimulationResult::new(5, 6));
+        assert_eq!(cache.len(), 4);
+
+        // A file-backed cache survives being reopened
+        let path = std::env::temp_dir().join(format!("mendel-cache-{}.bin", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut saved = ResultCache::open(&path).unwrap();
+        assert!(saved.is_empty());
+        saved.get_or_run(&bag, "even", |_| SimulationResult::new(7, 8)).unwrap();
+        let mut reopened = ResultCache::open(&path).unwrap();
+        assert_eq!(reopened.get_or_run(&bag, "even", |_| panic!("simulated again")).unwrap(), SimulationResult::new(7, 8));
+        fs::write(&path, b"not a cache").unwrap();
+        assert!(ResultCache::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_convergence() {
+        use super::Budget;
+
+        let mut bag = Bag::from_range(1, 11);
+        bag.set_seed(5);
+        bag.set_max_sims(1_055);
+        let untracked = bag.sample_detailed(2, |values| values.contains(&&1));
+        assert_eq!(untracked.convergence, None);
+        assert_eq!(untracked.convergence_csv(), None);
+
+        bag.set_track_convergence(true);
+        let tracked = bag.sample_detailed(2, |values| values.contains(&&1));
+        let points = tracked.convergence.as_ref().unwrap();
+        // Every 10 trials, and the 5 trials past the last of them
+        assert_eq!(points.len(), 106);
+        assert_eq!(points[0].trials, 10);
+        assert_eq!(points.last().unwrap().trials, 1_055);
+        assert_eq!(points.last().unwrap().estimate, tracked.probability());
+        assert_eq!((tracked.successes, tracked.trials), (untracked.successes, untracked.trials));
+        assert_eq!(tracked.convergence_csv().unwrap().lines().count(), 107);
+
+        bag.set_budget(Budget::Precision { epsilon: 0.01, confidence: 0.95 });
+        let adaptive = bag.one_detailed(|v| *v <= 3);
+        let points = adaptive.convergence.unwrap();
+        assert!((100..=200).contains(&points.len()), "{} points", points.len());
+        assert!(points.windows(2).all(|w| w[0].trials < w[1].trials));
+
+        // A parallel run records the totals as the workers report their chunks
+        bag.set_max_sims(20_000);
+        bag.set_parallelism(4);
+        let parallel = bag.one_detailed(|v| *v <= 3);
+        let points = parallel.convergence.as_ref().unwrap();
+        assert_eq!(points.len(), 20);
+        assert!(points.windows(2).all(|w| w[0].trials < w[1].trials));
+        assert_eq!(points.last().unwrap().trials, parallel.trials);
+    }
+
+    #[test]
+    fn test_worker_seeds() {
+        use super::worker_seed;
+
+        assert_eq!(worker_seed(7, 3), worker_seed(7, 3));
+        let seeds: Vec<u64> = (0..16).map(|worker| worker_seed(7, worker)).collect();
+        assert!(seeds.iter().enumerate().all(|(i, a)| seeds[i + 1..].iter().all(|b| a != b)));
+        assert!(worker_seed(8, 0) != seeds[0]);
+
+        // Every worker runs its own share with its own seed, whatever the generator
+        let mut bag = Bag::from_range(1, 101);
+        bag.set_seed(7);
+        bag.set_rng_kind(RngKind::Pcg);
+        bag.set_max_sims(3_001);
+        bag.set_parallelism(3);
+        let parallel = bag.sample_detailed(2, |values| values.contains(&&1));
+        let shares: SimulationResult = [1_001u32, 1_000, 1_000].iter().enumerate().map(|(worker, &share)| {
+            let mut single = Bag::from_range(1, 101);
+            single.set_seed(seeds[worker]);
+            single.set_rng_kind(RngKind::Pcg);
+            single.set_max_sims(share);
+            single.sample_detailed(2, |values| values.contains(&&1))
+        }).sum();
+        assert_eq!(parallel, shares);
+        assert_eq!(bag.sample_detailed(2, |values| values.contains(&&1)), parallel);
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = Bag::from_counts(vec![(Color::Red, 2), (Color::Blue, 2)]);
+        let after = Bag::from_counts(vec![(Color::Green, 3), (Color::Red, 1)]);
+        let diff = before.diff(&after, |c| c.clone());
+        assert_eq!(diff.changes.len(), 3);
+        assert_eq!(diff.changes[0].key, Color::Green);
+        assert_eq!((diff.changes[0].before, diff.changes[0].after), (0.0, 0.75));
+        assert_eq!(diff.get(&Color::Blue).unwrap().change(), -0.5);
+        assert_eq!(diff.get(&Color::Red).unwrap().change(), -0.25);
+        assert!((diff.total_variation() - 0.75).abs() < 1e-12);
+        assert!(diff.event.is_none());
+        assert_eq!(before.diff(&before, |c| c.clone()).total_variation(), 0.0);
+
+        let mut unchanged = before.diff_with_event(&before, |c| c.clone(), 1, |balls| *balls[0] == Color::Red);
+        let event = unchanged.event.take().unwrap();
+        assert!(event.difference.abs() < 0.03);
+        let diff = before.diff_with_event(&after, |c| c.clone(), 1, |balls| *balls[0] == Color::Red);
+        assert!(close_enough(diff.event.unwrap().difference, -0.25));
+    }
+
+    #[test]
+    fn test_diagnose_runs_equal_replications() {
+        use std::time::Duration;
+        use super::Budget;
+
+        let mut bag = Bag::from_range(1, 11);
+        bag.set_seed(5);
+        bag.set_max_sims(5_000);
+        bag.set_early_stopping(true);
+        // Early stopping would end some replications as soon as they look certain
+        let diagnostics = bag.diagnose(3, |v| *v > 0);
+        assert!(diagnostics.replications.iter().all(|r| r.trials == 5_000 && !r.decided_early));
+        assert_eq!(diagnostics.r_hat, 1.0);
+
+        bag.set_budget(Budget::Time(Duration::from_millis(5)));
+        let diagnostics = bag.diagnose_sample(3, 2, |values| values.contains(&&1));
+        let trials = diagnostics.replications[0].trials;
+        assert!(diagnostics.replications.iter().all(|r| r.trials == trials));
+    }
+
+    #[test]
+    fn test_simulated_entropy_weighted_by() {
+        let mut bag = Bag::from_vec(vec![(Color::Red, 5.0), (Color::Blue, 2.0), (Color::Red, 1.0), (Color::Green, 0.0)]);
+        bag.set_seed(3);
+        bag.set_max_sims(50_000);
+        let exact = bag.entropy_weighted_by(|c| c.1, |c| c.0.clone());
+        // Red weighs 6 of 8 and Blue 2 of 8, Green is never grabbed
+        assert!((exact - (-0.75 * 0.75f64.log2() - 0.25 * 0.25f64.log2())).abs() < 1e-12);
+        assert!(close_enough(bag.simulated_entropy_weighted_by(|c| c.1, |c| c.0.clone()), exact));
+        // Unweighted, every item is as likely and the entropy is 1.5 bits
+        assert_eq!(bag.entropy_weighted_by(|_| 1.0, |c| c.0.clone()), bag.entropy(|c| c.0.clone()));
+    }
+
 }
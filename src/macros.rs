@@ -0,0 +1,39 @@
+/// Builds a `Bag` concisely, either from a list of items or from items and their counts.
+///
+/// `bag![a, b, c]` expands to [`Bag::from_vec`](struct.Bag.html#method.from_vec), and
+/// `bag!{ a: 2, b: 3 }` to [`Bag::from_counts`](struct.Bag.html#method.from_counts). Counted items
+/// have to be paths (such as enum variants or constants) or literals.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate mendel;
+///
+/// #[derive(Clone, PartialEq)]
+/// enum Ball { Red, Blue, Green }
+///
+/// # fn main() {
+/// use Ball::*;
+///
+/// let balls = bag!{ Red: 5, Blue: 3, Green: 2 };
+/// assert_eq!(balls.items.len(), 10);
+/// let odds_of_red = balls.one(|b| *b == Red);
+/// assert!(0.49 < odds_of_red && odds_of_red < 0.51);
+///
+/// let numbers = bag![1, 2, 3];
+/// assert_eq!(*numbers.items, vec![1, 2, 3]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! bag {
+    ($($item:path : $count:expr),+ $(,)*) => {
+        $crate::Bag::from_counts(vec![$(($item, $count)),+])
+    };
+    ($($item:literal : $count:expr),+ $(,)*) => {
+        $crate::Bag::from_counts(vec![$(($item, $count)),+])
+    };
+    ($($item:expr),* $(,)*) => {
+        $crate::Bag::from_vec(vec![$($item),*])
+    };
+}
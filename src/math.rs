@@ -0,0 +1,146 @@
+/// The standard normal cumulative distribution function.
+pub(crate) fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / 2f64.sqrt()))
+}
+
+/// The `q` quantile of the standard normal distribution, found by bisection.
+pub(crate) fn normal_quantile(q: f64) -> f64 {
+    let (mut low, mut high) = (-40.0, 40.0);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if normal_cdf(mid) < q {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// The error function, via Abramowitz and Stegun formula 7.1.26 (absolute error below 1.5e-7).
+fn erf(x: f64) -> f64 {
+    let sign = if x < 0.0 { -1.0 } else { 1.0 };
+    let x = x.abs();
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t * (0.254_829_592 + t * (-0.284_496_736 + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    sign * (1.0 - poly * (-x * x).exp())
+}
+
+/// The two-sided p-value of a standard normal test statistic.
+pub(crate) fn two_sided_p_value(z: f64) -> f64 {
+    (2.0 * (1.0 - normal_cdf(z.abs()))).clamp(0.0, 1.0)
+}
+
+/// The natural logarithm of the binomial coefficient `n` choose `k`.
+pub(crate) fn ln_choose(n: u64, k: u64) -> f64 {
+    if k > n {
+        return f64::NEG_INFINITY;
+    }
+    let k = k.min(n - k);
+    (1..k + 1).map(|i| ((n - k + i) as f64 / i as f64).ln()).sum()
+}
+
+/// The probability of exactly `x` successes in `draws` draws without replacement from `total`
+/// items of which `successes` are successes.
+pub(crate) fn hypergeometric_pmf(total: u64, successes: u64, draws: u64, x: u64) -> f64 {
+    if x > successes || x > draws || draws - x > total - successes {
+        return 0.0;
+    }
+    (ln_choose(successes, x) + ln_choose(total - successes, draws - x) - ln_choose(total, draws)).exp()
+}
+
+/// The probability of exactly `x` successes in `trials` independent trials with success probability `p`.
+pub(crate) fn binomial_pmf(trials: u64, p: f64, x: u64) -> f64 {
+    if x > trials {
+        return 0.0;
+    }
+    if p == 0.0 || p == 1.0 {
+        let certain = if p == 0.0 { 0 } else { trials };
+        return if x == certain { 1.0 } else { 0.0 };
+    }
+    (ln_choose(trials, x) + x as f64 * p.ln() + (trials - x) as f64 * (1.0 - p).ln()).exp()
+}
+
+/// The probability of a chi-square statistic at least as large as `statistic` with `dof` degrees of
+/// freedom, via the Wilson-Hilferty normal approximation.
+pub(crate) fn chi_square_p_value(statistic: f64, dof: f64) -> f64 {
+    let scale = 2.0 / (9.0 * dof);
+    let z = ((statistic / dof).cbrt() - (1.0 - scale)) / scale.sqrt();
+    (1.0 - normal_cdf(z)).clamp(0.0, 1.0)
+}
+
+/// The natural logarithm of the gamma function, via the Lanczos approximation.
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [76.180_091_729_471_46, -86.505_320_329_416_77, 24.014_098_240_830_91,
+                                    -1.231_739_572_450_155, 0.001_208_650_973_866_179, -0.000_005_395_239_384_953];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let series: f64 = COEFFICIENTS.iter().enumerate().map(|(i, c)| c / (x + 1.0 + i as f64)).sum();
+    -tmp + (2.506_628_274_631_000_5 * (1.000_000_000_190_015 + series) / x).ln()
+}
+
+/// The regularized incomplete beta function, the cumulative distribution function of Beta(`a`, `b`) at `x`.
+pub(crate) fn beta_cdf(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges quickly on this side of the mean, use symmetry for the other
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - front * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+/// Lentz's method for the continued fraction of the incomplete beta function.
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-30;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..300 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 + even * d;
+        d = if d.abs() < TINY { 1.0 / TINY } else { 1.0 / d };
+        c = 1.0 + even / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        h *= d * c;
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 + odd * d;
+        d = if d.abs() < TINY { 1.0 / TINY } else { 1.0 / d };
+        c = 1.0 + odd / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    h
+}
+
+/// The `q` quantile of Beta(`a`, `b`), found by bisection.
+pub(crate) fn beta_quantile(q: f64, a: f64, b: f64) -> f64 {
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if beta_cdf(mid, a, b) < q {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
@@ -0,0 +1,115 @@
+use bag::{draw_sample, pick_one, Bag};
+use budget::Budget;
+use engine::Settings;
+use result::SimulationResult;
+use weighted::CumulativeWeights;
+
+/// A population mixed from several source bags, see [`Bag::mixture`](struct.Bag.html#method.mixture).
+///
+/// Every trial first picks one source bag, with probability proportional to its weight, and then
+/// grabs from it. The sources keep their own items, so nothing is copied.
+pub struct Mixture<T> {
+    pub sources: Vec<(Bag<T>, f64)>,
+    weights: CumulativeWeights,
+    settings: Settings
+}
+
+impl<T> Bag<T> {
+
+    /// Constructs a mixture of bags, each picked with probability proportional to its weight.
+    ///
+    /// This models populations that are a blend of different ones, such as parts from two suppliers
+    /// with different defect rates. Simulations use the default settings, which can be changed on the
+    /// mixture, not those of the sources.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a source bag is empty, a weight is negative or not finite, or every weight is zero.
+    ///
+    /// # Examples
+    ///
+    /// 70% of parts come from a supplier with a 1 in 10 defect rate, the rest from one with 1 in 4:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let supplier_a = Bag::from_counts(vec![("defective", 1), ("good", 9)]);
+    /// let supplier_b = Bag::from_counts(vec![("defective", 1), ("good", 3)]);
+    /// let parts = Bag::mixture(vec![(supplier_a, 0.7), (supplier_b, 0.3)]);
+    /// let odds = parts.one(|part| *part == "defective");
+    /// // 0.7 * 0.1 + 0.3 * 0.25
+    /// assert!(0.140 < odds && odds < 0.150);
+    /// ```
+    pub fn mixture(sources: Vec<(Bag<T>, f64)>) -> Mixture<T> {
+        assert!(sources.iter().all(|(bag, _)| !bag.items.is_empty()), "source bags can't be empty");
+        let weights = CumulativeWeights::new(sources.iter().map(|&(_, weight)| weight));
+        Mixture { sources, weights, settings: Settings::default() }
+    }
+}
+
+impl<T> Mixture<T> {
+
+    /// Predicts probability of criteria being met for an item grabbed from a randomly picked source.
+    pub fn one<F>(&self, f: F) -> f64 where
+        F: Fn(&T) -> bool {
+        self.one_detailed(f).probability()
+    }
+
+    /// Like [`one`](#method.one), but returns the full `SimulationResult`.
+    pub fn one_detailed<F>(&self, f: F) -> SimulationResult where
+        F: Fn(&T) -> bool {
+        self.settings.run(|rng| {
+            let source = &self.sources[self.weights.pick(rng)].0;
+            f(pick_one(rng, &source.items))
+        })
+    }
+
+    /// Predicts probability of criteria being met for `sample_size` items grabbed from a randomly
+    /// picked source. The whole sample comes from the same source, like a shipment from one supplier.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_size` is larger than a picked source bag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let supplier_a = Bag::from_counts(vec![("defective", 1), ("good", 9)]);
+    /// let supplier_b = Bag::from_counts(vec![("defective", 1), ("good", 3)]);
+    /// let parts = Bag::mixture(vec![(supplier_a, 0.7), (supplier_b, 0.3)]);
+    /// let odds = parts.sample(2, |shipment| shipment.contains(&&"defective"));
+    /// // 0.7 * 2/10 + 0.3 * 2/4
+    /// assert!(0.28 < odds && odds < 0.30);
+    /// ```
+    pub fn sample<F>(&self, sample_size: usize, f: F) -> f64 where
+        F: Fn(Vec<&T>) -> bool {
+        self.sample_detailed(sample_size, f).probability()
+    }
+
+    /// Like [`sample`](#method.sample), but returns the full `SimulationResult`.
+    pub fn sample_detailed<F>(&self, sample_size: usize, f: F) -> SimulationResult where
+        F: Fn(Vec<&T>) -> bool {
+        self.settings.run(|rng| {
+            let source = &self.sources[self.weights.pick(rng)].0;
+            f(draw_sample(rng, &source.items, sample_size))
+        })
+    }
+
+    /// Set the maximum amount of simulations to run when generating probabilities.
+    pub fn set_max_sims(&mut self, max_sims: u32) {
+        self.settings.set_max_sims(max_sims);
+    }
+
+    /// Set how much to simulate, see [`Bag::set_budget`](struct.Bag.html#method.set_budget).
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.settings.set_budget(budget);
+    }
+
+    /// Seed the random number generator so simulations are reproducible, see
+    /// [`Bag::set_seed`](struct.Bag.html#method.set_seed).
+    pub fn set_seed(&mut self, seed: u64) {
+        self.settings.seed = Some(seed);
+    }
+}
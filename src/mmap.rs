@@ -0,0 +1,193 @@
+use std::fs::File;
+use std::io;
+use std::marker::PhantomData;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+use libc;
+use rand::{seq, Rng};
+
+use bag::Bag;
+use budget::Budget;
+use engine::Settings;
+use result::SimulationResult;
+
+/// A read-only memory mapping of a whole file. Empty files aren't mapped at all.
+struct Mapping {
+    ptr: *mut libc::c_void,
+    len: usize
+}
+
+// The mapping is read-only and lives until it's dropped, so it's safe to share between threads
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+
+    fn open(path: &Path) -> io::Result<Mapping> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len() as usize;
+        if len == 0 {
+            return Ok(Mapping { ptr: ptr::null_mut(), len });
+        }
+        // The mapping stays valid after the file is closed
+        let ptr = unsafe {
+            libc::mmap(ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0)
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mapping { ptr, len })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        if self.len == 0 {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len) }
+        }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
+/// A population backed by a memory-mapped file of fixed-size records, see
+/// [`Bag::from_mmap`](struct.Bag.html#method.from_mmap).
+///
+/// Records are decoded only when a simulation picks them, and the operating system pages the
+/// file in and out as needed, so the population never has to fit in memory.
+pub struct MappedBag<T, D> {
+    mapping: Mapping,
+    record_size: usize,
+    decoder: D,
+    settings: Settings,
+    item: PhantomData<fn() -> T>
+}
+
+impl<T> Bag<T> {
+
+    /// Backs a population with the file at `path`, read as consecutive records of `record_size`
+    /// bytes that `decoder` turns into items. Only available with the `mmap` feature, on unix.
+    ///
+    /// Fails if the file can't be opened or mapped, or if its length isn't a whole number of records.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs;
+    /// use mendel::Bag;
+    ///
+    /// let path = std::env::temp_dir().join("mendel-from-mmap-example.bin");
+    /// fs::write(&path, (1..11u8).collect::<Vec<u8>>()).unwrap();
+    ///
+    /// let my_bag = Bag::from_mmap(&path, 1, |record| record[0]).unwrap();
+    /// assert_eq!(my_bag.len(), 10);
+    /// let odds_of_even = my_bag.one(|v| v % 2 == 0);
+    /// assert!(0.49 < odds_of_even && odds_of_even < 0.51);
+    /// # fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn from_mmap<P, D>(path: P, record_size: usize, decoder: D) -> io::Result<MappedBag<T, D>> where
+        P: AsRef<Path>,
+        D: Fn(&[u8]) -> T {
+        assert!(record_size > 0, "record_size must be positive");
+        let mapping = Mapping::open(path.as_ref())?;
+        if mapping.len % record_size != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("file length {} is not a multiple of the record size {}", mapping.len, record_size)));
+        }
+        Ok(MappedBag { mapping, record_size, decoder, settings: Settings::default(), item: PhantomData })
+    }
+}
+
+impl<T, D> MappedBag<T, D> where
+    D: Fn(&[u8]) -> T {
+
+    /// Returns the number of records in the file.
+    pub fn len(&self) -> usize {
+        self.mapping.len / self.record_size
+    }
+
+    /// Returns `true` if the file holds no records.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Decodes the record at `index`.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn get(&self, index: usize) -> T {
+        assert!(index < self.len(), "index {} is out of bounds for {} records", index, self.len());
+        let start = index * self.record_size;
+        (self.decoder)(&self.mapping.bytes()[start..start + self.record_size])
+    }
+
+    /// Predicts probability of criteria being met for the first random record grabbed from the file.
+    ///
+    /// See [`Bag::one`](struct.Bag.html#method.one).
+    pub fn one<F>(&self, f: F) -> f64 where
+        D: Sync,
+        F: Fn(&T) -> bool + Sync {
+        self.one_detailed(f).probability()
+    }
+
+    /// Like [`one`](#method.one), but returns the full `SimulationResult`.
+    pub fn one_detailed<F>(&self, f: F) -> SimulationResult where
+        D: Sync,
+        F: Fn(&T) -> bool + Sync {
+        self.settings.run_parallel(|rng| f(&self.get(rng.gen_range(0, self.len()))))
+    }
+
+    /// Predicts probability of criteria being met for the first `sample_size` random records grabbed from the file.
+    ///
+    /// See [`Bag::sample`](struct.Bag.html#method.sample). The records are decoded, so `f` gets them by value.
+    pub fn sample<F>(&self, sample_size: usize, f: F) -> f64 where
+        D: Sync,
+        F: Fn(Vec<T>) -> bool + Sync {
+        self.sample_detailed(sample_size, f).probability()
+    }
+
+    /// Like [`sample`](#method.sample), but returns the full `SimulationResult`.
+    pub fn sample_detailed<F>(&self, sample_size: usize, f: F) -> SimulationResult where
+        D: Sync,
+        F: Fn(Vec<T>) -> bool + Sync {
+        let len = self.len();
+        assert!(sample_size <= len, "sample_size {} is larger than the population of {}", sample_size, len);
+        self.settings.run_parallel(|rng| {
+            f(seq::sample_indices(rng, len, sample_size).into_iter().map(|index| self.get(index)).collect())
+        })
+    }
+
+    /// Set the maximum amount of simulations to run when generating probabilities.
+    pub fn set_max_sims(&mut self, max_sims: u32) {
+        self.settings.set_max_sims(max_sims);
+    }
+
+    /// Set how much to simulate, see [`Bag::set_budget`](struct.Bag.html#method.set_budget).
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.settings.set_budget(budget);
+    }
+
+    /// Seed the random number generator so simulations are reproducible, see
+    /// [`Bag::set_seed`](struct.Bag.html#method.set_seed).
+    pub fn set_seed(&mut self, seed: u64) {
+        self.settings.seed = Some(seed);
+    }
+
+    /// Split simulations across `threads` worker threads, see
+    /// [`Bag::set_parallelism`](struct.Bag.html#method.set_parallelism).
+    pub fn set_parallelism(&mut self, threads: usize) {
+        self.settings.threads = threads.max(1);
+    }
+}
@@ -0,0 +1,105 @@
+use rand::seq;
+
+use bag::Bag;
+use distribution::Distribution;
+
+impl<T> Bag<T> {
+
+    /// Simulates the distribution of the largest value among `sample_size` random items.
+    ///
+    /// The items' values are computed and sorted once up front, so every simulation only draws
+    /// positions and no value is computed twice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_size` is zero or larger than the number of items, or if a value is NaN.
+    ///
+    /// # Examples
+    ///
+    /// Drawing 3 cards numbered 1 - 10, what are the odds the highest is the 10? Or at least a 9?
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let cards = Bag::from_range(1, 11);
+    /// let highest = cards.sample_max(3, |v| *v as f64);
+    /// assert!(highest.probability(&10.0) > 0.29 && highest.probability(&10.0) < 0.31);
+    /// // 1 - C(8, 3) / C(10, 3)
+    /// assert!(highest.at_least(&9.0) > 0.52 && highest.at_least(&9.0) < 0.55);
+    /// ```
+    pub fn sample_max<F>(&self, sample_size: usize, value_fn: F) -> Distribution<f64> where
+        F: Fn(&T) -> f64 {
+        self.order_statistic(sample_size, value_fn, |positions| positions.iter().cloned().max())
+    }
+
+    /// Simulates the distribution of the smallest value among `sample_size` random items, see
+    /// [`sample_max`](#method.sample_max).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let lowest = Bag::from_range(1, 11).sample_min(2, |v| *v as f64);
+    /// // Both picks from 2 - 10: 9/10 * 8/9
+    /// assert!(lowest.at_least(&2.0) > 0.78 && lowest.at_least(&2.0) < 0.82);
+    /// ```
+    pub fn sample_min<F>(&self, sample_size: usize, value_fn: F) -> Distribution<f64> where
+        F: Fn(&T) -> f64 {
+        self.order_statistic(sample_size, value_fn, |positions| positions.iter().cloned().min())
+    }
+
+    /// Predicts the probability that at least one of the `top` highest scoring items of the bag is
+    /// among `sample_size` random items.
+    ///
+    /// Ties in score are broken by the items' order in the bag, so exactly `top` items count as the top.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_size` is larger than the number of items, or if a score is NaN.
+    ///
+    /// # Examples
+    ///
+    /// Buying 10 of 100 raffle tickets, what are the odds of holding one of the 3 winning numbers?
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// // The winning draw is already made: each ticket's score is its place in the draw
+    /// let tickets = Bag::from_range(1, 101);
+    /// let odds = tickets.contains_top(10, 3, |t| (*t * 37 % 101) as f64);
+    /// // 1 - C(97, 10) / C(100, 10)
+    /// assert!(0.26 < odds && odds < 0.29);
+    /// ```
+    pub fn contains_top<F>(&self, sample_size: usize, top: usize, score_fn: F) -> f64 where
+        F: Fn(&T) -> f64 {
+        let scores: Vec<f64> = self.items.iter().map(score_fn).collect();
+        assert!(sample_size <= scores.len(), "sample_size {} is larger than the population of {}", sample_size, scores.len());
+        let mut ranked: Vec<usize> = (0..scores.len()).collect();
+        // Stable, so ties keep the bag's order
+        ranked.sort_by(|&a, &b| scores[b].partial_cmp(&scores[a]).expect("scores must not be NaN"));
+        let mut is_top = vec![false; scores.len()];
+        for &i in ranked.iter().take(top) {
+            is_top[i] = true;
+        }
+        self.settings().run(|rng| {
+            seq::sample_indices(rng, scores.len(), sample_size).into_iter().any(|i| is_top[i])
+        }).probability()
+    }
+
+    /// Counts, over the simulations, which position of the sorted values `select` picks from each draw.
+    fn order_statistic<F, S>(&self, sample_size: usize, value_fn: F, select: S) -> Distribution<f64> where
+        F: Fn(&T) -> f64,
+        S: Fn(&[usize]) -> Option<usize> {
+        assert!(sample_size > 0, "sample_size must be positive");
+        let mut values: Vec<f64> = self.items.iter().map(value_fn).collect();
+        assert!(sample_size <= values.len(), "sample_size {} is larger than the population of {}", sample_size, values.len());
+        values.sort_by(|a, b| a.partial_cmp(b).expect("values must not be NaN"));
+        let mut counts = vec![0u64; values.len()];
+        let trials = self.settings().repeat(|rng| {
+            let positions = seq::sample_indices(rng, values.len(), sample_size);
+            counts[select(&positions).unwrap()] += 1;
+        });
+        Distribution::from_counts(values.into_iter().zip(counts).collect(), trials)
+    }
+}
@@ -0,0 +1,73 @@
+use rand::seq;
+
+use bag::Bag;
+use distribution::Distribution;
+
+/// How much two independent samples of a bag overlap, see
+/// [`Bag::sample_overlap`](struct.Bag.html#method.sample_overlap).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Overlap {
+    /// The simulated distribution of the number of items both samples share.
+    pub shared: Distribution<usize>,
+    /// The simulated mean Jaccard similarity of the samples: shared items over the items in either.
+    pub expected_jaccard: f64
+}
+
+impl Overlap {
+
+    /// The probability the samples share at least one item.
+    pub fn any_shared(&self) -> f64 {
+        self.shared.at_least(&1)
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Simulates how many items two independent samples of `first_size` and `second_size` items
+    /// have in common.
+    ///
+    /// Each sample is drawn without replacement, but the two are drawn independently of each other,
+    /// like two committees chosen separately from the same people.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either sample is empty or larger than the bag.
+    ///
+    /// # Examples
+    ///
+    /// If two committees of 5 are chosen from 30 people, how likely do they share a member?
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let people = Bag::from_range(0, 30);
+    /// let overlap = people.sample_overlap(5, 5);
+    /// // 1 - C(25, 5) / C(30, 5)
+    /// assert!((overlap.any_shared() - 0.623).abs() < 0.01);
+    /// ```
+    pub fn sample_overlap(&self, first_size: usize, second_size: usize) -> Overlap {
+        let len = self.items.len();
+        assert!(first_size > 0 && second_size > 0, "samples must have items");
+        assert!(first_size.max(second_size) <= len, "sample_size {} is larger than the population of {}",
+                first_size.max(second_size), len);
+        let mut counts = vec![0u64; first_size.min(second_size) + 1];
+        let mut jaccard_total = 0.0;
+        let mut in_first = vec![false; len];
+        let trials = self.settings().repeat(|rng| {
+            let first = seq::sample_indices(rng, len, first_size);
+            for &i in &first {
+                in_first[i] = true;
+            }
+            let shared = seq::sample_indices(rng, len, second_size).into_iter().filter(|&i| in_first[i]).count();
+            for &i in &first {
+                in_first[i] = false;
+            }
+            counts[shared] += 1;
+            jaccard_total += shared as f64 / (first_size + second_size - shared) as f64;
+        });
+        Overlap {
+            shared: Distribution::from_counts(counts.into_iter().enumerate().collect(), trials),
+            expected_jaccard: jaccard_total / trials as f64
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use bag::{pick_one, Bag};
+
+impl<T> Bag<T> {
+
+    /// Predicts probability of criteria being met for a pair of items, one grabbed from each bag.
+    ///
+    /// The two grabs are independent, which is the same as grabbing a random pair out of every
+    /// possible pairing of the bags' items. The simulation uses the settings of `first`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either bag is empty.
+    ///
+    /// # Examples
+    ///
+    /// Odds a random boy and a random girl were born in the same month:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let boys = Bag::from_range(1, 13);
+    /// let girls = Bag::from_range(1, 13);
+    /// let same_month = Bag::pair_sample(&boys, &girls, |boy, girl| boy == girl);
+    /// assert!(0.07 < same_month && same_month < 0.10);
+    /// ```
+    pub fn pair_sample<U, F>(first: &Bag<T>, second: &Bag<U>, f: F) -> f64 where
+        F: Fn(&T, &U) -> bool {
+        assert!(!first.items.is_empty() && !second.items.is_empty(), "both bags need items to pair");
+        first.settings().run(|rng| {
+            let a = pick_one(rng, &first.items);
+            f(a, pick_one(rng, &second.items))
+        }).probability()
+    }
+}
@@ -0,0 +1,613 @@
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str;
+
+use bag::Bag;
+
+/// Every Parquet file starts and ends with these bytes.
+const MAGIC: &[u8] = b"PAR1";
+
+/// Deepest nesting of metadata structures accepted, so corrupt files fail instead of overflowing the stack.
+const MAX_DEPTH: usize = 32;
+
+// Physical types of columns
+const BOOLEAN: i64 = 0;
+const INT32: i64 = 1;
+const INT64: i64 = 2;
+const INT96: i64 = 3;
+const FLOAT: i64 = 4;
+const DOUBLE: i64 = 5;
+const BYTE_ARRAY: i64 = 6;
+const FIXED_LEN_BYTE_ARRAY: i64 = 7;
+
+// Repetition types of columns
+const OPTIONAL: i64 = 1;
+const REPEATED: i64 = 2;
+
+// Value encodings
+const PLAIN: i64 = 0;
+const PLAIN_DICTIONARY: i64 = 2;
+const RLE: i64 = 3;
+const RLE_DICTIONARY: i64 = 8;
+
+// Compression codecs
+const UNCOMPRESSED: i64 = 0;
+const SNAPPY: i64 = 1;
+
+// Page types
+const DATA_PAGE: i64 = 0;
+const DICTIONARY_PAGE: i64 = 2;
+const DATA_PAGE_V2: i64 = 3;
+
+/// A value in a column of a Parquet file, see [`ParquetRow`](struct.ParquetRow.html).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParquetValue {
+    /// A missing value of an optional column.
+    Null,
+    Boolean(bool),
+    /// A 32 or 64-bit integer.
+    Int(i64),
+    /// A 32 or 64-bit float.
+    Float(f64),
+    /// A byte array, such as a UTF-8 string, a fixed-length byte array or a legacy 96-bit timestamp.
+    Bytes(Vec<u8>)
+}
+
+/// A row of a Parquet file, handed to the `row_to_item` function of
+/// [`Bag::from_parquet`](struct.Bag.html#method.from_parquet).
+pub struct ParquetRow<'a> {
+    names: &'a [String],
+    values: Vec<ParquetValue>
+}
+
+impl<'a> ParquetRow<'a> {
+
+    /// The value of the column called `name`, or `None` if there's no such column.
+    pub fn get(&self, name: &str) -> Option<&ParquetValue> {
+        self.names.iter().position(|n| n == name).map(|i| &self.values[i])
+    }
+
+    /// The value of the column called `name`, if it's an integer.
+    pub fn int(&self, name: &str) -> Option<i64> {
+        match self.get(name) {
+            Some(&ParquetValue::Int(value)) => Some(value),
+            _ => None
+        }
+    }
+
+    /// The value of the column called `name`, if it's a float.
+    pub fn float(&self, name: &str) -> Option<f64> {
+        match self.get(name) {
+            Some(&ParquetValue::Float(value)) => Some(value),
+            _ => None
+        }
+    }
+
+    /// The value of the column called `name`, if it's a boolean.
+    pub fn boolean(&self, name: &str) -> Option<bool> {
+        match self.get(name) {
+            Some(&ParquetValue::Boolean(value)) => Some(value),
+            _ => None
+        }
+    }
+
+    /// The value of the column called `name`, if it's a UTF-8 string.
+    pub fn str(&self, name: &str) -> Option<&str> {
+        match self.get(name) {
+            Some(ParquetValue::Bytes(bytes)) => str::from_utf8(bytes).ok(),
+            _ => None
+        }
+    }
+
+    /// The values of every column, in the order of the file's schema.
+    pub fn values(&self) -> &[ParquetValue] {
+        &self.values
+    }
+}
+
+fn invalid<S: Into<String>>(message: S) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn truncated() -> io::Error {
+    invalid("the Parquet file is truncated")
+}
+
+/// An unsigned little-endian integer of up to 8 bytes.
+fn little_endian(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |value, byte| (value << 8) | *byte as u64)
+}
+
+/// A value of the Thrift compact protocol, which the metadata of Parquet files is written in.
+/// Doubles and maps aren't used by the metadata that's read, so they're skipped.
+enum Thrift {
+    Bool(bool),
+    Int(i64),
+    Binary(Vec<u8>),
+    List(Vec<Thrift>),
+    Struct(Vec<(i16, Thrift)>),
+    Skipped
+}
+
+impl Thrift {
+
+    fn field(&self, id: i16) -> Option<&Thrift> {
+        match *self {
+            Thrift::Struct(ref fields) => fields.iter().find(|field| field.0 == id).map(|field| &field.1),
+            _ => None
+        }
+    }
+
+    fn int(&self, id: i16) -> Option<i64> {
+        match self.field(id) {
+            Some(&Thrift::Int(value)) => Some(value),
+            _ => None
+        }
+    }
+
+    fn bool(&self, id: i16) -> Option<bool> {
+        match self.field(id) {
+            Some(&Thrift::Bool(value)) => Some(value),
+            _ => None
+        }
+    }
+
+    fn binary(&self, id: i16) -> Option<&[u8]> {
+        match self.field(id) {
+            Some(Thrift::Binary(bytes)) => Some(bytes),
+            _ => None
+        }
+    }
+
+    fn list(&self, id: i16) -> &[Thrift] {
+        match self.field(id) {
+            Some(Thrift::List(items)) => items,
+            _ => &[]
+        }
+    }
+
+    fn required(&self, id: i16, what: &str) -> io::Result<i64> {
+        self.int(id).ok_or_else(|| invalid(format!("the Parquet metadata is missing {}", what)))
+    }
+
+    fn required_field(&self, id: i16, what: &str) -> io::Result<&Thrift> {
+        self.field(id).ok_or_else(|| invalid(format!("the Parquet metadata is missing {}", what)))
+    }
+
+    fn size(&self, id: i16, what: &str) -> io::Result<usize> {
+        let value = self.required(id, what)?;
+        if value < 0 {
+            return Err(invalid(format!("the Parquet metadata has a negative {}", what)));
+        }
+        Ok(value as usize)
+    }
+}
+
+/// Reads bytes front to back, including the varints and structures of the Thrift compact protocol.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Reader<'a> {
+
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, pos: 0 }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        let byte = *self.bytes.get(self.pos).ok_or_else(truncated)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if len > self.bytes.len() - self.pos {
+            return Err(truncated());
+        }
+        self.pos += len;
+        Ok(&self.bytes[self.pos - len..self.pos])
+    }
+
+    fn varint(&mut self) -> io::Result<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("a varint in the Parquet file is too long"))
+    }
+
+    fn zigzag(&mut self) -> io::Result<i64> {
+        let value = self.varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    fn thrift_struct(&mut self) -> io::Result<Thrift> {
+        self.value(12, 0)
+    }
+
+    fn value(&mut self, kind: u8, depth: usize) -> io::Result<Thrift> {
+        if depth > MAX_DEPTH {
+            return Err(invalid("the Parquet metadata is nested too deeply"));
+        }
+        Ok(match kind {
+            1 => Thrift::Bool(true),
+            2 => Thrift::Bool(false),
+            3 => Thrift::Int(self.byte()? as i8 as i64),
+            4..=6 => Thrift::Int(self.zigzag()?),
+            7 => {
+                self.take(8)?;
+                Thrift::Skipped
+            },
+            8 => {
+                let len = self.varint()? as usize;
+                Thrift::Binary(self.take(len)?.to_vec())
+            },
+            9 | 10 => {
+                let header = self.byte()?;
+                let size = if header >> 4 == 15 { self.varint()? as usize } else { (header >> 4) as usize };
+                let mut items = Vec::new();
+                for _ in 0..size {
+                    items.push(self.element(header & 0x0f, depth + 1)?);
+                }
+                Thrift::List(items)
+            },
+            11 => {
+                let size = self.varint()?;
+                if size > 0 {
+                    let kinds = self.byte()?;
+                    for _ in 0..size {
+                        self.element(kinds >> 4, depth + 1)?;
+                        self.element(kinds & 0x0f, depth + 1)?;
+                    }
+                }
+                Thrift::Skipped
+            },
+            12 => {
+                let mut fields = Vec::new();
+                let mut id = 0i16;
+                loop {
+                    let header = self.byte()?;
+                    if header == 0 {
+                        break;
+                    }
+                    // Field ids are usually a small step from the previous one
+                    id = match header >> 4 {
+                        0 => self.zigzag()? as i16,
+                        delta => id.wrapping_add(delta as i16)
+                    };
+                    fields.push((id, self.value(header & 0x0f, depth + 1)?));
+                }
+                Thrift::Struct(fields)
+            },
+            _ => return Err(invalid(format!("the Parquet metadata has an unknown type {}", kind)))
+        })
+    }
+
+    /// An element of a list or a map, where booleans take a byte of their own.
+    fn element(&mut self, kind: u8, depth: usize) -> io::Result<Thrift> {
+        if kind == 1 || kind == 2 {
+            return Ok(Thrift::Bool(self.byte()? == 1));
+        }
+        self.value(kind, depth)
+    }
+}
+
+/// A column of a flat schema.
+struct Column {
+    name: String,
+    physical: i64,
+    type_length: usize,
+    optional: bool
+}
+
+/// The columns of the file's schema, all leaves of its root.
+fn read_columns(metadata: &Thrift) -> io::Result<Vec<Column>> {
+    let schema = metadata.list(2);
+    if schema.is_empty() {
+        return Err(invalid("the Parquet file has no schema"));
+    }
+    schema[1..].iter().map(|element| {
+        if element.int(5).unwrap_or(0) > 0 || element.int(3) == Some(REPEATED) {
+            return Err(invalid("nested and repeated Parquet columns aren't supported"));
+        }
+        let name = element.binary(4).and_then(|name| str::from_utf8(name).ok())
+            .ok_or_else(|| invalid("a Parquet column has no name"))?;
+        Ok(Column {
+            name: name.to_string(),
+            physical: element.required(1, "a column type")?,
+            type_length: element.int(2).unwrap_or(0).max(0) as usize,
+            optional: element.int(3) == Some(OPTIONAL)
+        })
+    }).collect()
+}
+
+/// Decompresses a page, borrowing it if it isn't compressed.
+fn decompress(codec: i64, page: &[u8], size: usize) -> io::Result<Cow<'_, [u8]>> {
+    match codec {
+        SNAPPY => snappy(page, size).map(Cow::Owned),
+        _ => Ok(Cow::Borrowed(page))
+    }
+}
+
+/// Decompresses a Snappy block of `size` bytes.
+fn snappy(block: &[u8], size: usize) -> io::Result<Vec<u8>> {
+    let corrupt = || invalid("a Snappy compressed Parquet page is corrupt");
+    let mut reader = Reader::new(block);
+    if reader.varint()? as usize != size {
+        return Err(corrupt());
+    }
+    let mut output = Vec::with_capacity(size);
+    while !reader.is_done() {
+        let tag = reader.byte()?;
+        if tag & 3 == 0 {
+            let mut len = (tag >> 2) as usize;
+            // Long literals store their length in the next 1 to 4 bytes
+            if len >= 60 {
+                len = little_endian(reader.take(len - 59)?) as usize;
+            }
+            output.extend_from_slice(reader.take(len + 1)?);
+        } else {
+            let (len, offset) = match tag & 3 {
+                1 => (4 + ((tag >> 2) & 7) as usize, ((tag as usize >> 5) << 8) | reader.byte()? as usize),
+                2 => ((tag >> 2) as usize + 1, little_endian(reader.take(2)?) as usize),
+                _ => ((tag >> 2) as usize + 1, little_endian(reader.take(4)?) as usize)
+            };
+            if offset == 0 || offset > output.len() {
+                return Err(corrupt());
+            }
+            // Copies can overlap what they're copying, so go byte by byte
+            let start = output.len() - offset;
+            for i in start..start + len {
+                let byte = output[i];
+                output.push(byte);
+            }
+        }
+        if output.len() > size {
+            return Err(corrupt());
+        }
+    }
+    if output.len() != size {
+        return Err(corrupt());
+    }
+    Ok(output)
+}
+
+/// Decodes `count` values of the hybrid of run-length encoding and bit-packing that levels and
+/// dictionary indices use.
+fn rle_hybrid(bytes: &[u8], bit_width: u8, count: usize) -> io::Result<Vec<u32>> {
+    if bit_width > 32 {
+        return Err(invalid(format!("a Parquet page has a bit width of {}", bit_width)));
+    }
+    let width = bit_width as usize;
+    let mut reader = Reader::new(bytes);
+    let mut values = Vec::with_capacity(count);
+    while values.len() < count {
+        let header = reader.varint()? as usize;
+        if header & 1 == 0 {
+            let value = little_endian(reader.take((width + 7) / 8)?) as u32;
+            let run = (header >> 1).min(count - values.len());
+            values.resize(values.len() + run, value);
+        } else {
+            // Groups of 8 values, packed from the least significant bit
+            let groups = header >> 1;
+            let packed = reader.take(groups.checked_mul(width).ok_or_else(truncated)?)?;
+            for i in 0..groups.saturating_mul(8).min(count - values.len()) {
+                let value = (0..width).filter(|b| packed[(i * width + b) / 8] >> ((i * width + b) % 8) & 1 == 1)
+                    .fold(0u32, |value, b| value | 1 << b);
+                values.push(value);
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Decodes `count` plain encoded values of `column`.
+fn plain(bytes: &[u8], column: &Column, count: usize) -> io::Result<Vec<ParquetValue>> {
+    if column.physical == BOOLEAN {
+        // Booleans are packed 8 to a byte, from the least significant bit
+        if bytes.len() < (count + 7) / 8 {
+            return Err(truncated());
+        }
+        return Ok((0..count).map(|i| ParquetValue::Boolean(bytes[i / 8] >> (i % 8) & 1 == 1)).collect());
+    }
+    let mut reader = Reader::new(bytes);
+    (0..count).map(|_| Ok(match column.physical {
+        INT32 => ParquetValue::Int(little_endian(reader.take(4)?) as u32 as i32 as i64),
+        INT64 => ParquetValue::Int(little_endian(reader.take(8)?) as i64),
+        FLOAT => ParquetValue::Float(f32::from_bits(little_endian(reader.take(4)?) as u32) as f64),
+        DOUBLE => ParquetValue::Float(f64::from_bits(little_endian(reader.take(8)?))),
+        BYTE_ARRAY => {
+            let len = little_endian(reader.take(4)?) as usize;
+            ParquetValue::Bytes(reader.take(len)?.to_vec())
+        },
+        FIXED_LEN_BYTE_ARRAY => ParquetValue::Bytes(reader.take(column.type_length)?.to_vec()),
+        INT96 => ParquetValue::Bytes(reader.take(12)?.to_vec()),
+        physical => return Err(invalid(format!("Parquet type {} isn't supported", physical)))
+    })).collect()
+}
+
+/// Reads a 4-byte length and the run-length encoded values of that many bytes after it.
+fn prefixed_rle(bytes: &[u8], bit_width: u8, count: usize) -> io::Result<(Vec<u32>, &[u8])> {
+    let mut reader = Reader::new(bytes);
+    let len = little_endian(reader.take(4)?) as usize;
+    let values = rle_hybrid(reader.take(len)?, bit_width, count)?;
+    Ok((values, &bytes[4 + len..]))
+}
+
+/// Decodes the values of a data page, with nulls wherever `levels` says a value isn't defined.
+fn decode_page(column: &Column, encoding: i64, bytes: &[u8], count: usize, levels: Option<Vec<u32>>,
+               dictionary: &[ParquetValue], values: &mut Vec<ParquetValue>) -> io::Result<()> {
+    let defined = levels.as_ref().map_or(count, |levels| levels.iter().filter(|level| **level == 1).count());
+    let decoded = match encoding {
+        PLAIN => plain(bytes, column, defined)?,
+        PLAIN_DICTIONARY | RLE_DICTIONARY => {
+            let (&bit_width, indices) = bytes.split_first().ok_or_else(truncated)?;
+            rle_hybrid(indices, bit_width, defined)?.into_iter().map(|index| {
+                dictionary.get(index as usize).cloned().ok_or_else(|| invalid("a Parquet dictionary index is out of range"))
+            }).collect::<io::Result<_>>()?
+        },
+        RLE if column.physical == BOOLEAN => {
+            prefixed_rle(bytes, 1, defined)?.0.into_iter().map(|value| ParquetValue::Boolean(value == 1)).collect()
+        },
+        _ => return Err(invalid(format!("Parquet encoding {} isn't supported", encoding)))
+    };
+    match levels {
+        Some(levels) => {
+            let mut decoded = decoded.into_iter();
+            values.extend(levels.into_iter().map(|level| {
+                if level == 1 { decoded.next().unwrap() } else { ParquetValue::Null }
+            }));
+        },
+        None => values.extend(decoded)
+    }
+    Ok(())
+}
+
+/// Decodes every value of `column` in a row group, from the column chunk's metadata.
+fn read_chunk(bytes: &[u8], column: &Column, chunk: &Thrift) -> io::Result<Vec<ParquetValue>> {
+    if chunk.binary(1).is_some() {
+        return Err(invalid("Parquet columns stored in other files aren't supported"));
+    }
+    let meta = chunk.required_field(3, "column metadata")?;
+    let codec = meta.int(4).unwrap_or(UNCOMPRESSED);
+    if codec != UNCOMPRESSED && codec != SNAPPY {
+        return Err(invalid(format!("Parquet compression codec {} isn't supported, only uncompressed and Snappy", codec)));
+    }
+    let total = meta.size(5, "a column's value count")?;
+    let data_offset = meta.size(9, "a column's data offset")?;
+    // The dictionary, if there is one, comes first
+    let start = match meta.int(11) {
+        Some(offset) if offset > 0 && (offset as usize) < data_offset => offset as usize,
+        _ => data_offset
+    };
+    if start > bytes.len() {
+        return Err(truncated());
+    }
+    let mut reader = Reader { bytes, pos: start };
+    let mut dictionary = Vec::new();
+    let mut values = Vec::new();
+    while values.len() < total {
+        let header = reader.thrift_struct()?;
+        let size = header.size(2, "a page's uncompressed size")?;
+        let page = reader.take(header.size(3, "a page's compressed size")?)?;
+        match header.required(1, "a page type")? {
+            DICTIONARY_PAGE => {
+                let count = header.required_field(7, "a dictionary page header")?.size(1, "a dictionary's size")?;
+                dictionary = plain(&decompress(codec, page, size)?, column, count)?;
+            },
+            DATA_PAGE => {
+                let page_header = header.required_field(5, "a data page header")?;
+                let count = page_header.size(1, "a page's value count")?;
+                let encoding = page_header.required(2, "a page's encoding")?;
+                let data = decompress(codec, page, size)?;
+                let (levels, rest) = if column.optional {
+                    if page_header.int(3) != Some(RLE) {
+                        return Err(invalid("only run-length encoded Parquet definition levels are supported"));
+                    }
+                    let (levels, rest) = prefixed_rle(&data, 1, count)?;
+                    (Some(levels), rest)
+                } else {
+                    (None, &data[..])
+                };
+                decode_page(column, encoding, rest, count, levels, &dictionary, &mut values)?;
+            },
+            DATA_PAGE_V2 => {
+                let page_header = header.required_field(8, "a data page header")?;
+                let count = page_header.size(1, "a page's value count")?;
+                let encoding = page_header.required(4, "a page's encoding")?;
+                let definition_len = page_header.size(5, "a page's definition levels length")?;
+                let levels_len = definition_len + page_header.size(6, "a page's repetition levels length")?;
+                if levels_len > page.len() || levels_len > size {
+                    return Err(truncated());
+                }
+                // Levels are never compressed in version 2 pages, only the values after them
+                let levels = if column.optional {
+                    Some(rle_hybrid(&page[levels_len - definition_len..levels_len], 1, count)?)
+                } else {
+                    None
+                };
+                let data = if page_header.bool(7).unwrap_or(true) {
+                    decompress(codec, &page[levels_len..], size - levels_len)?
+                } else {
+                    Cow::Borrowed(&page[levels_len..])
+                };
+                decode_page(column, encoding, &data, count, levels, &dictionary, &mut values)?;
+            },
+            // Index pages hold no values
+            _ => {}
+        }
+    }
+    Ok(values)
+}
+
+impl<T> Bag<T> {
+
+    /// Loads a population from the Parquet file at `path`, turning every row into an item with
+    /// `row_to_item`. Only available with the `arrow` feature.
+    ///
+    /// Columns are looked up by name on the [`ParquetRow`](struct.ParquetRow.html). Flat schemas of
+    /// required and optional columns are supported, with plain or dictionary encoded pages that are
+    /// uncompressed or Snappy compressed, which covers what pyarrow and Spark write by default.
+    ///
+    /// Fails if the file can't be read, or isn't a Parquet file of that form. Nested or repeated
+    /// columns, other compression codecs and other encodings are reported as invalid data.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use mendel::Bag;
+    ///
+    /// // A file of people with an `age` column
+    /// let people = Bag::from_parquet("people.parquet", |row| row.int("age").unwrap_or(0)).unwrap();
+    /// let odds_of_adult = people.one(|age| *age >= 18);
+    /// ```
+    pub fn from_parquet<P, F>(path: P, row_to_item: F) -> io::Result<Bag<T>> where
+        P: AsRef<Path>,
+        F: Fn(&ParquetRow) -> T {
+        let bytes = fs::read(path)?;
+        let len = bytes.len();
+        if len < 12 || &bytes[..4] != MAGIC || &bytes[len - 4..] != MAGIC {
+            return Err(invalid("not a Parquet file"));
+        }
+        let metadata_len = little_endian(&bytes[len - 8..len - 4]) as usize;
+        if metadata_len > len - 12 {
+            return Err(truncated());
+        }
+        let metadata = Reader::new(&bytes[len - 8 - metadata_len..len - 8]).thrift_struct()?;
+        let columns = read_columns(&metadata)?;
+        let names: Vec<String> = columns.iter().map(|column| column.name.clone()).collect();
+
+        let mut items = Vec::new();
+        for group in metadata.list(4) {
+            let rows = group.size(3, "a row group's row count")?;
+            let chunks = group.list(1);
+            if chunks.len() != columns.len() {
+                return Err(invalid("a Parquet row group doesn't have every column"));
+            }
+            let mut values = Vec::with_capacity(columns.len());
+            for (column, chunk) in columns.iter().zip(chunks) {
+                let column_values = read_chunk(&bytes, column, chunk)?;
+                if column_values.len() != rows {
+                    return Err(invalid(format!("Parquet column {} has {} values for {} rows", column.name, column_values.len(), rows)));
+                }
+                values.push(column_values.into_iter());
+            }
+            for _ in 0..rows {
+                let row = ParquetRow { names: &names, values: values.iter_mut().map(|v| v.next().unwrap()).collect() };
+                items.push(row_to_item(&row));
+            }
+        }
+        Ok(Bag::from_vec(items))
+    }
+}
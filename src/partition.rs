@@ -0,0 +1,48 @@
+use rand::seq;
+
+use bag::Bag;
+
+impl<T> Bag<T> {
+
+    /// Predicts probability of criteria being met when random items are split into groups of the
+    /// given sizes, such as dealing players into teams.
+    ///
+    /// Each simulation draws `group_sizes.iter().sum()` items without replacement and hands `f` one
+    /// group per size, in the order the sizes were given.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the groups need more items than the bag holds.
+    ///
+    /// # Examples
+    ///
+    /// Splitting 10 players into two teams of 5, what are the odds both captains (1 and 2) end up on the same team?
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let players = Bag::from_range(1, 11);
+    /// let odds = players.partition_sample(&[5, 5], |teams| {
+    ///     teams.iter().any(|team| team.contains(&&1) && team.contains(&&2))
+    /// });
+    /// // 4/9
+    /// assert!(0.43 < odds && odds < 0.46);
+    /// ```
+    pub fn partition_sample<F>(&self, group_sizes: &[usize], f: F) -> f64 where
+        F: Fn(Vec<Vec<&T>>) -> bool {
+        let needed: usize = group_sizes.iter().sum();
+        assert!(needed <= self.items.len(), "groups of {} items are larger than the population of {}",
+                needed, self.items.len());
+        self.settings().run(|rng| {
+            // The indices come back in random order, so consecutive runs of them are random groups
+            let indices = seq::sample_indices(rng, self.items.len(), needed);
+            let mut rest = &indices[..];
+            let groups = group_sizes.iter().map(|&size| {
+                let (group, remaining) = rest.split_at(size);
+                rest = remaining;
+                group.iter().map(|&i| &self.items[i]).collect()
+            }).collect();
+            f(groups)
+        }).probability()
+    }
+}
@@ -0,0 +1,181 @@
+use std::io::{self, Read, Write};
+
+use cache::ResultCache;
+use report::{Report, ReportRow};
+use result::SimulationResult;
+
+/// Every saved file starts with these bytes...
+const MAGIC: &[u8; 4] = b"MNDL";
+
+/// ...followed by the format version...
+const VERSION: u8 = 1;
+
+/// ...and what kind of value it holds.
+const KIND_RESULT: u8 = 1;
+const KIND_REPORT: u8 = 2;
+const KIND_CACHE: u8 = 3;
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn write_u64<W: Write>(writer: &mut W, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn write_header<W: Write>(writer: &mut W, kind: u8) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION, kind])
+}
+
+fn read_header<R: Read>(reader: &mut R, kind: u8) -> io::Result<()> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid_data("not a saved mendel value"));
+    }
+    if read_u8(reader)? != VERSION {
+        return Err(invalid_data("unsupported format version"));
+    }
+    if read_u8(reader)? != kind {
+        return Err(invalid_data("saved value is of a different kind"));
+    }
+    Ok(())
+}
+
+fn write_result<W: Write>(writer: &mut W, result: &SimulationResult) -> io::Result<()> {
+    write_u64(writer, result.successes)?;
+    write_u64(writer, result.trials)?;
+    writer.write_all(&[result.decided_early as u8])
+}
+
+fn read_result<R: Read>(reader: &mut R) -> io::Result<SimulationResult> {
+    let successes = read_u64(reader)?;
+    let trials = read_u64(reader)?;
+    let decided_early = read_u8(reader)? != 0;
+    if successes > trials {
+        return Err(invalid_data("saved result has more successes than trials"));
+    }
+    Ok(SimulationResult { successes, trials, decided_early, convergence: None })
+}
+
+impl SimulationResult {
+
+    /// Writes the result in mendel's compact binary format, 23 bytes in total.
+    ///
+    /// A saved result doubles as a checkpoint: load it and [`merge`](#method.merge) it with the
+    /// results of further simulations to resume an expensive estimate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, SimulationResult};
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_max_sims(10_000);
+    /// let mut checkpoint = Vec::new();
+    /// my_bag.one_detailed(|v| v % 2 == 0).save(&mut checkpoint).unwrap();
+    ///
+    /// // Later on, add more simulations to the saved ones
+    /// let saved = SimulationResult::load(&checkpoint[..]).unwrap();
+    /// let resumed = saved.merge(&my_bag.one_detailed(|v| v % 2 == 0));
+    /// assert_eq!(resumed.trials, 20_000);
+    /// ```
+    pub fn save<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write_header(&mut writer, KIND_RESULT)?;
+        write_result(&mut writer, self)
+    }
+
+    /// Reads a result written by [`save`](#method.save).
+    ///
+    /// Fails with `InvalidData` if the bytes aren't a saved result.
+    pub fn load<R: Read>(mut reader: R) -> io::Result<Self> {
+        read_header(&mut reader, KIND_RESULT)?;
+        read_result(&mut reader)
+    }
+}
+
+impl Report {
+
+    /// Writes the report in mendel's compact binary format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, Events, Report};
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_max_sims(1_000);
+    /// let report = my_bag.report(2, &Events::new().add("has a 1", |v| v.contains(&&1)));
+    /// let mut saved = Vec::new();
+    /// report.save(&mut saved).unwrap();
+    /// assert_eq!(Report::load(&saved[..]).unwrap(), report);
+    /// ```
+    pub fn save<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write_header(&mut writer, KIND_REPORT)?;
+        write_u64(&mut writer, self.sample_size as u64)?;
+        write_u64(&mut writer, self.rows.len() as u64)?;
+        for row in &self.rows {
+            write_u64(&mut writer, row.name.len() as u64)?;
+            writer.write_all(row.name.as_bytes())?;
+            write_result(&mut writer, &row.result)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a report written by [`save`](#method.save).
+    ///
+    /// Fails with `InvalidData` if the bytes aren't a saved report.
+    pub fn load<R: Read>(mut reader: R) -> io::Result<Self> {
+        read_header(&mut reader, KIND_REPORT)?;
+        let sample_size = read_u64(&mut reader)? as usize;
+        let row_count = read_u64(&mut reader)?;
+        let mut rows = Vec::new();
+        for _ in 0..row_count {
+            let name_len = read_u64(&mut reader)?;
+            let mut name = Vec::new();
+            reader.by_ref().take(name_len).read_to_end(&mut name)?;
+            if name.len() as u64 != name_len {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "saved report is truncated"));
+            }
+            let name = String::from_utf8(name).map_err(|_| invalid_data("saved event name is not UTF-8"))?;
+            rows.push(ReportRow { name, result: read_result(&mut reader)? });
+        }
+        Ok(Report { sample_size, rows })
+    }
+}
+
+/// Writes the cached results of a `ResultCache`, as its count and then `(key, result)` pairs.
+pub(crate) fn write_cache<W: Write>(mut writer: W, cache: &ResultCache) -> io::Result<()> {
+    write_header(&mut writer, KIND_CACHE)?;
+    write_u64(&mut writer, cache.entries.len() as u64)?;
+    for (key, result) in &cache.entries {
+        write_u64(&mut writer, *key)?;
+        write_result(&mut writer, result)?;
+    }
+    Ok(())
+}
+
+/// Reads the `(key, result)` pairs written by `write_cache`.
+pub(crate) fn read_cache<R: Read>(mut reader: R) -> io::Result<Vec<(u64, SimulationResult)>> {
+    read_header(&mut reader, KIND_CACHE)?;
+    let count = read_u64(&mut reader)?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let key = read_u64(&mut reader)?;
+        entries.push((key, read_result(&mut reader)?));
+    }
+    Ok(entries)
+}
@@ -0,0 +1,114 @@
+use std::fmt::{Display, Write};
+
+use distribution::Distribution;
+use result::SimulationResult;
+
+/// Space around the plotting area, in pixels, for the axes' labels.
+const MARGIN: f64 = 40.0;
+
+/// Escapes text for use in SVG.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Opens an SVG document with a white background and the left and bottom axes.
+fn start(width: u32, height: u32) -> String {
+    let (w, h) = (width as f64, height as f64);
+    let mut svg = String::new();
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+             width, height, width, height).unwrap();
+    writeln!(svg, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>").unwrap();
+    writeln!(svg, "<path d=\"M{m} {m} V{b} H{r}\" stroke=\"black\" fill=\"none\"/>", m = MARGIN, b = h - MARGIN, r = w - MARGIN).unwrap();
+    svg
+}
+
+/// Writes a label at (`x`, `y`).
+fn label(svg: &mut String, x: f64, y: f64, anchor: &str, text: &str) {
+    writeln!(svg, "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"11\" font-family=\"sans-serif\" text-anchor=\"{}\">{}</text>",
+             x, y, anchor, escape(text)).unwrap();
+}
+
+impl<K: PartialOrd + Display> Distribution<K> {
+
+    /// Renders the distribution as an SVG bar chart of each outcome's probability.
+    /// Only available with the `plot` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let highest = Bag::from_range(1, 7).sample_max(2, |v| *v as f64);
+    /// let svg = highest.histogram_svg(480, 320);
+    /// assert!(svg.starts_with("<svg"));
+    /// ```
+    pub fn histogram_svg(&self, width: u32, height: u32) -> String {
+        let mut svg = start(width, height);
+        let plot_width = width as f64 - 2.0 * MARGIN;
+        let plot_height = height as f64 - 2.0 * MARGIN;
+        let bottom = height as f64 - MARGIN;
+        let probabilities = self.probabilities();
+        let highest = probabilities.iter().map(|&(_, p)| p).fold(0.0, f64::max);
+        label(&mut svg, MARGIN - 4.0, MARGIN + 4.0, "end", &format!("{:.2}", highest));
+        label(&mut svg, MARGIN - 4.0, bottom, "end", "0");
+        let slot = plot_width / probabilities.len().max(1) as f64;
+        for (i, &(outcome, p)) in probabilities.iter().enumerate() {
+            let bar_height = if highest > 0.0 { p / highest * plot_height } else { 0.0 };
+            let x = MARGIN + i as f64 * slot;
+            writeln!(svg, "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"steelblue\"><title>{}: {:.4}</title></rect>",
+                     x + slot * 0.1, bottom - bar_height, slot * 0.8, bar_height, escape(&outcome.to_string()), p).unwrap();
+            label(&mut svg, x + slot / 2.0, bottom + 14.0, "middle", &outcome.to_string());
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+impl SimulationResult {
+
+    /// Renders how the running estimate converged as the simulations added up, as an SVG line chart.
+    /// Only available with the `plot` feature.
+    ///
+    /// The dashed line is the final estimate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result didn't track its convergence, see
+    /// [`Bag::set_track_convergence`](struct.Bag.html#method.set_track_convergence).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_max_sims(10_000);
+    /// my_bag.set_track_convergence(true);
+    /// let svg = my_bag.one_detailed(|v| v % 2 == 0).convergence_svg(480, 320);
+    /// assert!(svg.contains("<polyline"));
+    /// ```
+    pub fn convergence_svg(&self, width: u32, height: u32) -> String {
+        let convergence = self.convergence.as_ref().expect("the result didn't track its convergence");
+        let trials = self.trials;
+        let points: Vec<(u64, f64)> = convergence.iter().map(|p| (p.trials, p.estimate)).collect();
+
+        let mut svg = start(width, height);
+        let plot_width = width as f64 - 2.0 * MARGIN;
+        let plot_height = height as f64 - 2.0 * MARGIN;
+        let bottom = height as f64 - MARGIN;
+        let x = |trial: u64| MARGIN + trial as f64 / trials.max(1) as f64 * plot_width;
+        let y = |p: f64| bottom - p * plot_height;
+        let line: Vec<String> = points.iter().map(|&(t, p)| format!("{:.1},{:.1}", x(t), y(p))).collect();
+        writeln!(svg, "<polyline points=\"{}\" stroke=\"steelblue\" fill=\"none\"/>", line.join(" ")).unwrap();
+        if let Some(&(_, last)) = points.last() {
+            writeln!(svg, "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"gray\" stroke-dasharray=\"4 4\"/>",
+                     MARGIN, y(last), MARGIN + plot_width, y(last)).unwrap();
+            label(&mut svg, MARGIN + plot_width, y(last) - 4.0, "end", &format!("{:.4}", last));
+        }
+        label(&mut svg, MARGIN - 4.0, MARGIN + 4.0, "end", "1");
+        label(&mut svg, MARGIN - 4.0, bottom, "end", "0");
+        label(&mut svg, MARGIN + plot_width, bottom + 14.0, "end", &format!("{} simulations", trials));
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
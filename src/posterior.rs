@@ -0,0 +1,115 @@
+use bag::{draw_sample, pick_one, Bag};
+use math::beta_quantile;
+use result::SimulationResult;
+
+/// A Beta distribution, the prior belief about a probability for
+/// [`Bag::one_bayesian`](struct.Bag.html#method.one_bayesian).
+///
+/// `alpha` and `beta` act like prior successes and failures: Beta(1, 1) is uniform (no prior
+/// knowledge), and Beta(30, 70) is a fairly confident belief that the probability is near 0.3.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Beta {
+    pub alpha: f64,
+    pub beta: f64
+}
+
+impl Beta {
+
+    /// # Panics
+    ///
+    /// Panics if `alpha` or `beta` isn't positive and finite.
+    pub fn new(alpha: f64, beta: f64) -> Self {
+        assert!(alpha > 0.0 && alpha.is_finite() && beta > 0.0 && beta.is_finite(),
+                "alpha and beta must be positive and finite, got {} and {}", alpha, beta);
+        Beta { alpha, beta }
+    }
+
+    /// The uniform prior Beta(1, 1), every probability equally likely.
+    pub fn uniform() -> Self {
+        Beta::new(1.0, 1.0)
+    }
+
+    /// The Jeffreys prior Beta(0.5, 0.5).
+    pub fn jeffreys() -> Self {
+        Beta::new(0.5, 0.5)
+    }
+
+    /// The mean of the distribution.
+    pub fn mean(&self) -> f64 {
+        self.alpha / (self.alpha + self.beta)
+    }
+
+    /// The `q` quantile of the distribution.
+    pub fn quantile(&self, q: f64) -> f64 {
+        beta_quantile(q, self.alpha, self.beta)
+    }
+}
+
+/// The posterior belief about a probability after simulating, see
+/// [`Bag::one_bayesian`](struct.Bag.html#method.one_bayesian).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Posterior {
+    pub prior: Beta,
+    pub result: SimulationResult,
+    /// The prior updated with the simulated successes and failures.
+    pub distribution: Beta
+}
+
+impl Posterior {
+
+    /// Updates `prior` with the successes and failures of a simulation.
+    pub fn new(prior: Beta, result: SimulationResult) -> Self {
+        let distribution = Beta::new(prior.alpha + result.successes as f64,
+                                     prior.beta + (result.trials - result.successes) as f64);
+        Posterior { prior, result, distribution }
+    }
+
+    /// The posterior mean of the probability.
+    pub fn mean(&self) -> f64 {
+        self.distribution.mean()
+    }
+
+    /// The equal-tailed 95% credible interval of the probability.
+    pub fn credible_interval(&self) -> (f64, f64) {
+        self.credible_interval_at(0.95)
+    }
+
+    /// The equal-tailed credible interval of the probability holding `level` of the posterior.
+    pub fn credible_interval_at(&self, level: f64) -> (f64, f64) {
+        assert!(level > 0.0 && level < 1.0, "level must be between 0 and 1, got {}", level);
+        let tail = (1.0 - level) / 2.0;
+        (self.distribution.quantile(tail), self.distribution.quantile(1.0 - tail))
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Simulates the odds of `f` (as with [`one`](#method.one)) and combines them with a `prior`
+    /// belief, returning the posterior distribution of the probability.
+    ///
+    /// An informative prior keeps small simulation budgets from giving wild estimates, and the
+    /// credible interval reads as "the probability is in this range with 95% certainty".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, Beta};
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_max_sims(100);
+    /// let posterior = my_bag.one_bayesian(|v| v % 2 == 0, Beta::new(50.0, 50.0));
+    /// let (low, high) = posterior.credible_interval();
+    /// assert!(low < posterior.mean() && posterior.mean() < high);
+    /// assert!(0.3 < posterior.mean() && posterior.mean() < 0.7);
+    /// ```
+    pub fn one_bayesian<F>(&self, f: F, prior: Beta) -> Posterior where
+        F: Fn(&T) -> bool {
+        Posterior::new(prior, self.settings().run(|rng| f(pick_one(rng, &self.items))))
+    }
+
+    /// Like [`one_bayesian`](#method.one_bayesian), but for samples as with [`sample`](#method.sample).
+    pub fn sample_bayesian<F>(&self, sample_size: usize, f: F, prior: Beta) -> Posterior where
+        F: Fn(Vec<&T>) -> bool {
+        Posterior::new(prior, self.settings().run(|rng| f(draw_sample(rng, &self.items, sample_size))))
+    }
+}
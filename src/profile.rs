@@ -0,0 +1,111 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use bag::{pick_one, Bag};
+
+/// Trials run before profiling starts, so caches and branch predictors settle.
+const WARM_UP_TRIALS: u32 = 100;
+
+/// Where the time of a simulation goes, see [`Bag::profile`](struct.Bag.html#method.profile).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    /// Number of trials profiled, after the warm-up.
+    pub trials: u32,
+    /// Total time spent evaluating the criteria.
+    pub predicate_time: Duration,
+    /// Total time spent grabbing random items.
+    pub rng_time: Duration,
+    /// The median time of a single evaluation of the criteria.
+    pub predicate_median: Duration,
+    /// The 90th percentile time of a single evaluation of the criteria.
+    pub predicate_p90: Duration,
+    /// The slowest single evaluation of the criteria.
+    pub predicate_max: Duration
+}
+
+impl Profile {
+
+    /// The share of the time spent grabbing random items rather than evaluating the criteria.
+    pub fn rng_share(&self) -> f64 {
+        let total = (self.predicate_time + self.rng_time).as_secs_f64();
+        if total == 0.0 { 0.0 } else { self.rng_time.as_secs_f64() / total }
+    }
+
+    /// The average time of a whole trial.
+    pub fn per_trial(&self) -> Duration {
+        (self.predicate_time + self.rng_time) / self.trials
+    }
+
+    /// The expected time of a run of `sims` simulations on a single thread.
+    pub fn projected_runtime(&self, sims: u64) -> Duration {
+        self.per_trial().mul_f64(sims as f64)
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "trials profiled   {}", self.trials)?;
+        writeln!(f, "per trial         {:?}", self.per_trial())?;
+        writeln!(f, "criteria          median {:?}, p90 {:?}, max {:?}", self.predicate_median, self.predicate_p90, self.predicate_max)?;
+        writeln!(f, "random grabs      {:.1}% of the time", self.rng_share() * 100.0)?;
+        write!(f, "1,000,000 sims    {:?}", self.projected_runtime(1_000_000))
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Times `trials` evaluations of the criteria `f` (as with [`one`](#method.one)) to show where
+    /// the time of a simulation goes, before committing to a long run.
+    ///
+    /// A short warm-up runs first and isn't counted. Every grab and every evaluation is timed on
+    /// its own, so for criteria that take only nanoseconds the clock's overhead is a large part of
+    /// the times.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `trials` is zero or the bag is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let profile = my_bag.profile(|v| v % 2 == 0, 1_000);
+    /// assert_eq!(profile.trials, 1_000);
+    /// assert!(profile.predicate_median <= profile.predicate_max);
+    /// assert!(profile.projected_runtime(1_000_000) < Duration::from_secs(60));
+    /// println!("{}", profile);
+    /// ```
+    pub fn profile<F>(&self, f: F, trials: u32) -> Profile where
+        F: Fn(&T) -> bool {
+        assert!(trials > 0, "trials must be positive");
+        assert!(!self.items.is_empty(), "the bag is empty");
+        let mut rng = self.settings().rng();
+        for _ in 0..WARM_UP_TRIALS {
+            f(pick_one(&mut rng, &self.items));
+        }
+        let mut rng_time = Duration::from_secs(0);
+        let mut predicate_times = Vec::with_capacity(trials as usize);
+        for _ in 0..trials {
+            let started = Instant::now();
+            let item = pick_one(&mut rng, &self.items);
+            let picked = Instant::now();
+            f(item);
+            predicate_times.push(picked.elapsed());
+            rng_time += picked - started;
+        }
+        let predicate_time = predicate_times.iter().sum();
+        predicate_times.sort_unstable();
+        let quantile = |q: f64| predicate_times[((trials - 1) as f64 * q).round() as usize];
+        Profile {
+            trials,
+            predicate_time,
+            rng_time,
+            predicate_median: quantile(0.5),
+            predicate_p90: quantile(0.9),
+            predicate_max: predicate_times[predicate_times.len() - 1]
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::sync::OnceLock;
+
+use rand::Rng;
+
+use bag::Bag;
+use budget::Budget;
+use engine::Settings;
+use result::SimulationResult;
+use weighted::AliasTable;
+
+/// The most blocks a weighted range is split into, which bounds its memory.
+const MAX_BLOCKS: u64 = 4_096;
+
+/// A range of integers picked proportionally to a weight function, without ever storing the range,
+/// see [`Bag::from_weighted_range`](struct.Bag.html#method.from_weighted_range).
+///
+/// Its first simulation calls the weight function once for every value in the range, which takes
+/// time in proportion to the range's length. Later simulations reuse those block totals, and each
+/// pick only calls the weight function for the values it tries.
+pub struct WeightedRange<W> {
+    min: i64,
+    len: u64,
+    weight_fn: W,
+    blocks: OnceLock<Blocks>,
+    settings: Settings
+}
+
+/// The range split into blocks of consecutive values, picked by their total weight.
+struct Blocks {
+    size: u64,
+    table: AliasTable,
+    /// The largest weight in each block, a value within a block is accepted with its share of it.
+    max_weights: Vec<f64>
+}
+
+impl Bag<i64> {
+
+    /// Constructs a range of integers [`min`, `max`) where each value is picked with probability
+    /// proportional to `weight_fn`, without storing the range.
+    ///
+    /// The range is split into at most 4,096 blocks of consecutive values. On the first simulation,
+    /// every weight is computed once to build an alias table of the blocks' total weights. Each pick
+    /// then takes a block from the table and a value within it by rejection, so memory stays the same
+    /// however large the range, and picks are fast as long as weights don't vary wildly from value to
+    /// value within a block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is empty. Simulating panics if a weight is negative or not finite, or if
+    /// every weight is zero.
+    ///
+    /// # Examples
+    ///
+    /// Ten million values, where multiples of 10 are 9 times as likely as the rest:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut values = Bag::from_weighted_range(0, 10_000_000, |v| if v % 10 == 0 { 9.0 } else { 1.0 });
+    /// values.set_max_sims(10_000);
+    /// let odds = values.one(|v| v % 10 == 0);
+    /// assert!(0.47 < odds && odds < 0.53);
+    /// ```
+    pub fn from_weighted_range<W>(min: i64, max: i64, weight_fn: W) -> WeightedRange<W> where
+        W: Fn(i64) -> f64 {
+        assert!(min < max, "the range {}..{} is empty", min, max);
+        WeightedRange { min, len: (max as i128 - min as i128) as u64, weight_fn, blocks: OnceLock::new(), settings: Settings::default() }
+    }
+}
+
+impl<W> WeightedRange<W> where
+    W: Fn(i64) -> f64 {
+
+    /// Returns the number of values in the range.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Always `false`, a range is never empty.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    fn value(&self, offset: u64) -> i64 {
+        (self.min as i128 + offset as i128) as i64
+    }
+
+    /// The blocks, computing every weight the first time.
+    fn blocks(&self) -> &Blocks {
+        self.blocks.get_or_init(|| {
+            let size = (self.len + MAX_BLOCKS - 1) / MAX_BLOCKS;
+            let count = (self.len + size - 1) / size;
+            let (mut totals, mut max_weights) = (Vec::with_capacity(count as usize), Vec::with_capacity(count as usize));
+            for block in 0..count {
+                let (mut total, mut largest) = (0.0, 0.0f64);
+                for offset in block * size..((block + 1) * size).min(self.len) {
+                    let weight = (self.weight_fn)(self.value(offset));
+                    assert!(weight >= 0.0 && weight.is_finite(), "weights must be finite and non-negative, got {}", weight);
+                    total += weight;
+                    largest = largest.max(weight);
+                }
+                totals.push(total);
+                max_weights.push(largest);
+            }
+            Blocks { size, table: AliasTable::new(&totals), max_weights }
+        })
+    }
+
+    /// Picks a value proportionally to its weight.
+    fn pick<R: Rng>(&self, blocks: &Blocks, rng: &mut R) -> i64 {
+        let block = blocks.table.pick(rng) as u64;
+        let start = block * blocks.size;
+        let end = (start + blocks.size).min(self.len);
+        loop {
+            let value = self.value(rng.gen_range(start, end));
+            if rng.gen::<f64>() * blocks.max_weights[block as usize] < (self.weight_fn)(value) {
+                return value;
+            }
+        }
+    }
+
+    /// Predicts probability of criteria being met for a random value picked from the range.
+    pub fn one<F>(&self, f: F) -> f64 where
+        W: Sync,
+        F: Fn(&i64) -> bool + Sync {
+        self.one_detailed(f).probability()
+    }
+
+    /// Like [`one`](#method.one), but returns the full `SimulationResult`.
+    pub fn one_detailed<F>(&self, f: F) -> SimulationResult where
+        W: Sync,
+        F: Fn(&i64) -> bool + Sync {
+        let blocks = self.blocks();
+        self.settings.run_parallel(|rng| f(&self.pick(blocks, rng)))
+    }
+
+    /// Predicts probability of criteria being met for `sample_size` values picked independently from
+    /// the range, so the same value can come up more than once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// // 1 - 100, where each value is weighted by itself
+    /// let mut values = Bag::from_weighted_range(1, 101, |v| v as f64);
+    /// values.set_max_sims(10_000);
+    /// // (1 - 1275 / 5050)^2
+    /// let odds = values.sample(2, |picked| picked.iter().all(|v| *v > 50));
+    /// assert!(0.54 < odds && odds < 0.58);
+    /// ```
+    pub fn sample<F>(&self, sample_size: usize, f: F) -> f64 where
+        W: Sync,
+        F: Fn(&[i64]) -> bool + Sync {
+        self.sample_detailed(sample_size, f).probability()
+    }
+
+    /// Like [`sample`](#method.sample), but returns the full `SimulationResult`.
+    pub fn sample_detailed<F>(&self, sample_size: usize, f: F) -> SimulationResult where
+        W: Sync,
+        F: Fn(&[i64]) -> bool + Sync {
+        let blocks = self.blocks();
+        self.settings.run_parallel(|rng| {
+            let picked: Vec<i64> = (0..sample_size).map(|_| self.pick(blocks, rng)).collect();
+            f(&picked)
+        })
+    }
+
+    /// Set the maximum amount of simulations to run when generating probabilities.
+    pub fn set_max_sims(&mut self, max_sims: u32) {
+        self.settings.set_max_sims(max_sims);
+    }
+
+    /// Set how much to simulate, see [`Bag::set_budget`](struct.Bag.html#method.set_budget).
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.settings.set_budget(budget);
+    }
+
+    /// Seed the random number generator so simulations are reproducible, see
+    /// [`Bag::set_seed`](struct.Bag.html#method.set_seed).
+    pub fn set_seed(&mut self, seed: u64) {
+        self.settings.seed = Some(seed);
+    }
+
+    /// Split simulations across `threads` worker threads, see
+    /// [`Bag::set_parallelism`](struct.Bag.html#method.set_parallelism).
+    pub fn set_parallelism(&mut self, threads: usize) {
+        self.settings.threads = threads.max(1);
+    }
+}
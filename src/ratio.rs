@@ -0,0 +1,151 @@
+use std::fmt;
+
+use bag::Bag;
+use result::SimulationResult;
+
+/// An exact fraction in lowest terms, for counts that shouldn't go through floating point, see
+/// [`SimulationResult::ratio`](struct.SimulationResult.html#method.ratio).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ratio {
+    pub numerator: u64,
+    pub denominator: u64
+}
+
+/// The greatest common divisor of `a` and `b`, by Euclid's algorithm.
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let rest = a % b;
+        a = b;
+        b = rest;
+    }
+    a
+}
+
+impl Ratio {
+
+    /// Constructs `numerator / denominator`, reduced to lowest terms.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `denominator` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Ratio;
+    ///
+    /// let ratio = Ratio::new(30, 100);
+    /// assert_eq!((ratio.numerator, ratio.denominator), (3, 10));
+    /// assert_eq!(ratio.to_string(), "3/10");
+    /// ```
+    pub fn new(numerator: u64, denominator: u64) -> Self {
+        assert!(denominator > 0, "the denominator is 0");
+        let divisor = gcd(numerator, denominator);
+        Ratio { numerator: numerator / divisor, denominator: denominator / divisor }
+    }
+
+    /// The fraction as the nearest `f64`.
+    pub fn to_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+impl fmt::Display for Ratio {
+    /// Formats as `numerator/denominator`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+impl SimulationResult {
+
+    /// The estimated probability as the exact fraction of successes over trials, in lowest terms.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no trials were run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::SimulationResult;
+    ///
+    /// let result = SimulationResult::new(250, 1_000);
+    /// assert_eq!(result.ratio().to_string(), "1/4");
+    /// ```
+    pub fn ratio(&self) -> Ratio {
+        Ratio::new(self.successes, self.trials)
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Computes the exact probability of a random item meeting the criteria in `f`, as the fraction
+    /// of the items that do, by checking every item instead of simulating.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bag is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 13);
+    /// assert_eq!(my_bag.one_exact(|v| v % 3 == 0).to_string(), "1/3");
+    /// ```
+    pub fn one_exact<F>(&self, f: F) -> Ratio where
+        F: Fn(&T) -> bool {
+        assert!(!self.items.is_empty(), "the bag is empty");
+        Ratio::new(self.items.iter().filter(|v| f(v)).count() as u64, self.items.len() as u64)
+    }
+
+    /// Computes the exact probability of `sample_size` random items meeting the criteria in `f`, as
+    /// the fraction of every possible sample that does, by checking each of them instead of simulating.
+    ///
+    /// Every combination of `sample_size` items is checked once, with its items in the bag's order, so
+    /// `f` shouldn't depend on the order of the sample. The number of combinations grows quickly (20
+    /// items have 184,756 samples of 10), so this is only practical for small bags.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_size` is larger than the bag, or there are more samples than fit in a `u64`.
+    ///
+    /// # Examples
+    ///
+    /// Odds of a pair when drawing 2 cards from two suits of 3 ranks:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let cards = Bag::from_vec(vec![1, 2, 3, 1, 2, 3]);
+    /// assert_eq!(cards.sample_exact(2, |hand| hand[0] == hand[1]).to_string(), "1/5");
+    /// ```
+    pub fn sample_exact<F>(&self, sample_size: usize, f: F) -> Ratio where
+        F: Fn(Vec<&T>) -> bool {
+        let len = self.items.len();
+        assert!(sample_size <= len, "sample_size {} is larger than the population of {}", sample_size, len);
+        // len choose sample_size, where every partial product is itself a binomial coefficient
+        let samples = (0..sample_size as u64)
+            .try_fold(1u64, |samples, i| samples.checked_mul(len as u64 - i).map(|product| product / (i + 1)))
+            .expect("there are too many samples to enumerate");
+        let mut indices: Vec<usize> = (0..sample_size).collect();
+        let mut successes = 0;
+        loop {
+            if f(indices.iter().map(|i| &self.items[*i]).collect()) {
+                successes += 1;
+            }
+            // Move the last index that can still move, and put the ones after it right behind it
+            match (0..sample_size).rev().find(|i| indices[*i] < len - sample_size + i) {
+                Some(i) => {
+                    indices[i] += 1;
+                    for j in i + 1..sample_size {
+                        indices[j] = indices[j - 1] + 1;
+                    }
+                },
+                None => return Ratio::new(successes, samples)
+            }
+        }
+    }
+}
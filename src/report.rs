@@ -0,0 +1,231 @@
+use std::fmt;
+
+use bag::{draw_sample, Bag};
+use result::SimulationResult;
+
+/// A boxed criteria over a sample of items.
+pub(crate) type SamplePredicate<'a, T> = Box<dyn Fn(Vec<&T>) -> bool + 'a>;
+
+/// A collection of named events to evaluate together, see [`Bag::report`](struct.Bag.html#method.report).
+pub struct Events<'a, T: 'a> {
+    pub(crate) events: Vec<(String, SamplePredicate<'a, T>)>
+}
+
+impl<'a, T> Events<'a, T> {
+
+    /// Constructs an empty collection of events.
+    pub fn new() -> Self {
+        Events { events: Vec::new() }
+    }
+
+    /// Adds a named event.
+    pub fn add<F>(mut self, name: &str, f: F) -> Self where
+        F: Fn(Vec<&T>) -> bool + 'a {
+        self.events.push((name.to_string(), Box::new(f)));
+        self
+    }
+
+    /// Returns the number of events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns `true` if there are no events.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl<'a, T> Default for Events<'a, T> {
+    fn default() -> Self {
+        Events::new()
+    }
+}
+
+/// A single event of a [`Report`](struct.Report.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReportRow {
+    pub name: String,
+    pub result: SimulationResult
+}
+
+/// The results of several named events, evaluated over the same simulated samples.
+///
+/// Displays as an aligned table, or can be exported with [`to_csv`](#method.to_csv).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub sample_size: usize,
+    pub rows: Vec<ReportRow>
+}
+
+const HEADERS: [&str; 6] = ["event", "probability", "successes", "trials", "ci_low", "ci_high"];
+
+impl Report {
+
+    /// Returns the result of the event called `name`.
+    pub fn get(&self, name: &str) -> Option<&SimulationResult> {
+        self.rows.iter().find(|row| row.name == name).map(|row| &row.result)
+    }
+
+    fn cells(&self) -> Vec<[String; 6]> {
+        self.rows.iter().map(|row| {
+            let (low, high) = row.result.confidence_interval();
+            [row.name.clone(), format!("{:.4}", row.result.probability()), row.result.successes.to_string(),
+             row.result.trials.to_string(), format!("{:.4}", low), format!("{:.4}", high)]
+        }).collect()
+    }
+
+    /// Formats the report as CSV, with a header row.
+    ///
+    /// Event names containing commas or quotes are quoted.
+    pub fn to_csv(&self) -> String {
+        let mut csv = HEADERS.join(",");
+        csv.push('\n');
+        for cells in self.cells() {
+            let mut cells = cells;
+            cells[0] = csv_field(&cells[0]);
+            csv.push_str(&cells.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// Quotes `field` if it would otherwise break a CSV row.
+pub(crate) fn csv_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cells = self.cells();
+        let mut widths: Vec<usize> = HEADERS.iter().map(|h| h.len()).collect();
+        for row in &cells {
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let lines = Some(HEADERS.iter().map(|h| h.to_string()).collect::<Vec<String>>()).into_iter()
+            .chain(cells.into_iter().map(|row| row.to_vec()));
+        for (i, line) in lines.enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            // Event names are left aligned, numbers right aligned
+            write!(f, "{:<w$}", line[0], w = widths[0])?;
+            for (cell, width) in line.iter().zip(widths.iter()).skip(1) {
+                write!(f, "  {:>w$}", cell, w = *width)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The joint probabilities of every pair of named events, evaluated over the same simulated samples,
+/// see [`Bag::event_matrix`](struct.Bag.html#method.event_matrix).
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventMatrix {
+    /// The events' names, in the order they were added.
+    pub names: Vec<String>,
+    pub trials: u64,
+    /// `joint[i][j]` is the number of trials where both event `i` and event `j` happened, so the
+    /// diagonal holds each event's own successes.
+    pub joint: Vec<Vec<u64>>
+}
+
+impl EventMatrix {
+
+    /// The probability of both the `i`th and the `j`th event happening.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    pub fn probability(&self, i: usize, j: usize) -> f64 {
+        self.joint[i][j] as f64 / self.trials as f64
+    }
+
+    /// The probability of both the events called `first` and `second` happening.
+    pub fn get(&self, first: &str, second: &str) -> Option<f64> {
+        let i = self.names.iter().position(|name| name == first)?;
+        let j = self.names.iter().position(|name| name == second)?;
+        Some(self.probability(i, j))
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Evaluates several named events over the same `sample_size` random items, returning a `Report`
+    /// with each event's probability, counts and 95% confidence interval.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, Events};
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let events = Events::new()
+    ///     .add("has a 1", |values| values.iter().any(|v| **v == 1))
+    ///     .add("all even", |values| values.iter().all(|v| **v % 2 == 0));
+    /// let report = my_bag.report(2, &events);
+    /// let has_a_one = report.get("has a 1").unwrap().probability();
+    /// assert!(0.19 < has_a_one && has_a_one < 0.21);
+    /// println!("{}", report);
+    /// ```
+    pub fn report(&self, sample_size: usize, events: &Events<T>) -> Report {
+        let mut successes = vec![0u64; events.len()];
+        let trials = self.settings().repeat(|rng| {
+            let sample = draw_sample(rng, &self.items, sample_size);
+            for (count, (_, f)) in successes.iter_mut().zip(events.events.iter()) {
+                if f(sample.clone()) {
+                    *count += 1;
+                }
+            }
+        });
+        let rows = events.events.iter().zip(successes)
+            .map(|((name, _), s)| ReportRow { name: name.clone(), result: SimulationResult::new(s, trials) })
+            .collect();
+        Report { sample_size, rows }
+    }
+
+    /// Evaluates several named events over the same `sample_size` random items, returning the joint
+    /// probability of every pair of them.
+    ///
+    /// Comparing `P(A and B)` with `P(A) * P(B)`, found on the diagonal, shows which events depend on
+    /// each other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, Events};
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let events = Events::new()
+    ///     .add("has a 1", |values| values.iter().any(|v| **v == 1))
+    ///     .add("has a 2", |values| values.iter().any(|v| **v == 2));
+    /// let matrix = my_bag.event_matrix(2, &events);
+    /// // 1 / C(10, 2)
+    /// let both = matrix.get("has a 1", "has a 2").unwrap();
+    /// assert!(0.017 < both && both < 0.027);
+    /// ```
+    pub fn event_matrix(&self, sample_size: usize, events: &Events<T>) -> EventMatrix {
+        let mut joint = vec![vec![0u64; events.len()]; events.len()];
+        let mut happened = vec![false; events.len()];
+        let trials = self.settings().repeat(|rng| {
+            let sample = draw_sample(rng, &self.items, sample_size);
+            for (flag, (_, f)) in happened.iter_mut().zip(events.events.iter()) {
+                *flag = f(sample.clone());
+            }
+            for (row, _) in joint.iter_mut().zip(happened.iter()).filter(|&(_, h)| *h) {
+                for (count, _) in row.iter_mut().zip(happened.iter()).filter(|&(_, h)| *h) {
+                    *count += 1;
+                }
+            }
+        });
+        let names = events.events.iter().map(|(name, _)| name.clone()).collect();
+        EventMatrix { names, trials, joint }
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+use std::iter::Sum;
+
+use convergence::ConvergencePoint;
+
+/// z-score used for 95% confidence intervals and margins of error.
+pub(crate) const Z_95: f64 = 1.96;
+
+/// The raw outcome of a simulation: how many of the trials met the criteria.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationResult {
+    pub successes: u64,
+    pub trials: u64,
+    /// `true` if early stopping ended the run before its `max_sims`, see
+    /// [`Bag::set_early_stopping`](struct.Bag.html#method.set_early_stopping).
+    pub decided_early: bool,
+    /// The running estimate as the trials added up, if the run tracked it, see
+    /// [`Bag::set_track_convergence`](struct.Bag.html#method.set_track_convergence).
+    pub convergence: Option<Vec<ConvergencePoint>>
+}
+
+impl SimulationResult {
+
+    /// Constructs a new `SimulationResult` from counts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more successes than trials.
+    pub fn new(successes: u64, trials: u64) -> Self {
+        assert!(successes <= trials, "{} successes is more than {} trials", successes, trials);
+        SimulationResult { successes, trials, decided_early: false, convergence: None }
+    }
+
+    /// The estimated probability, or `NaN` if no trials were run.
+    pub fn probability(&self) -> f64 {
+        self.successes as f64 / self.trials as f64
+    }
+
+    /// The standard error of the estimated probability.
+    pub fn std_error(&self) -> f64 {
+        let p = self.probability();
+        (p * (1.0 - p) / self.trials as f64).sqrt()
+    }
+
+    /// The 95% confidence interval of the probability, as `(low, high)`.
+    ///
+    /// Uses the Wilson score interval, which stays sensible for probabilities near 0 or 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::SimulationResult;
+    ///
+    /// let result = SimulationResult::new(0, 1_000);
+    /// let (low, high) = result.confidence_interval();
+    /// assert_eq!(low, 0.0);
+    /// assert!(0.0 < high && high < 0.01);
+    /// ```
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        self.wilson_interval(Z_95)
+    }
+
+    /// The Wilson score interval for the z-score `z`.
+    pub(crate) fn wilson_interval(&self, z: f64) -> (f64, f64) {
+        let n = self.trials as f64;
+        let p = self.probability();
+        let z2 = z * z;
+        let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let half_width = z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+        ((center - half_width).max(0.0), (center + half_width).min(1.0))
+    }
+
+    /// Combines two results of the same simulation into one, by adding their counts.
+    ///
+    /// This lets a simulation be sharded across threads, processes or machines. The combined
+    /// estimate is only valid if each shard used an independent random stream, e.g. different seeds.
+    /// The combined result is `decided_early` if either shard was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, SimulationResult};
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// let shards: Vec<SimulationResult> = (0..4).map(|seed| {
+    ///     my_bag.set_seed(seed);
+    ///     my_bag.one_detailed(|v| v % 2 == 0)
+    /// }).collect();
+    /// let combined: SimulationResult = shards.iter().sum();
+    /// assert_eq!(combined.trials, 4 * my_bag.max_sims as u64);
+    /// assert_eq!(combined, shards[0].merge(&shards[1]).merge(&shards[2]).merge(&shards[3]));
+    /// ```
+    pub fn merge(&self, other: &SimulationResult) -> SimulationResult {
+        SimulationResult {
+            successes: self.successes + other.successes,
+            trials: self.trials + other.trials,
+            decided_early: self.decided_early || other.decided_early,
+            convergence: None
+        }
+    }
+
+    /// Formats the probability with exactly `decimals` digits after the decimal point.
+    ///
+    /// Always uses a `.` as the decimal separator, whatever the system's locale.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::SimulationResult;
+    ///
+    /// assert_eq!(SimulationResult::new(1, 3).as_fixed(3), "0.333");
+    /// ```
+    pub fn as_fixed(&self, decimals: usize) -> String {
+        format!("{:.*}", decimals, self.probability())
+    }
+
+    /// Formats the probability as a percentage with `decimals` digits after the decimal point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::SimulationResult;
+    ///
+    /// assert_eq!(SimulationResult::new(4762, 10_000).as_percent(1), "47.6%");
+    /// ```
+    pub fn as_percent(&self, decimals: usize) -> String {
+        format!("{:.*}%", decimals, self.probability() * 100.0)
+    }
+
+    /// Formats the probability as odds, such as "about 1 in 21".
+    ///
+    /// An event that never happened is reported relative to the number of trials that were run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::SimulationResult;
+    ///
+    /// assert_eq!(SimulationResult::new(476, 10_000).as_odds_ratio(), "about 1 in 21");
+    /// assert_eq!(SimulationResult::new(0, 10_000).as_odds_ratio(), "less than 1 in 10000");
+    /// ```
+    pub fn as_odds_ratio(&self) -> String {
+        if self.successes == 0 {
+            format!("less than 1 in {}", self.trials)
+        } else {
+            format!("about 1 in {}", (1.0 / self.probability()).round())
+        }
+    }
+}
+
+impl Sum for SimulationResult {
+    fn sum<I: Iterator<Item = SimulationResult>>(iter: I) -> Self {
+        iter.fold(SimulationResult::new(0, 0), |total, result| total.merge(&result))
+    }
+}
+
+impl<'a> Sum<&'a SimulationResult> for SimulationResult {
+    fn sum<I: Iterator<Item = &'a SimulationResult>>(iter: I) -> Self {
+        iter.fold(SimulationResult::new(0, 0), |total, result| total.merge(result))
+    }
+}
+
+impl fmt::Display for SimulationResult {
+    /// Formats as the probability and its 95% confidence interval, honouring the precision flag (default 4).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let decimals = f.precision().unwrap_or(4);
+        let (low, high) = self.confidence_interval();
+        write!(f, "{:.*} (95% CI {:.*} - {:.*})", decimals, self.probability(), decimals, low, decimals, high)
+    }
+}
@@ -0,0 +1,260 @@
+use std::cell::Cell;
+
+use rand::prng::{ChaChaRng, XorShiftRng};
+use rand::{thread_rng, Error, Rng, RngCore, SeedableRng, StdRng, ThreadRng};
+
+/// The seed every unseeded simulation uses when the `deterministic` feature is enabled.
+///
+/// It's the bytes of "mendel". Seeded simulations (see `Bag::set_seed`) are unaffected.
+pub const DETERMINISTIC_SEED: u64 = 0x6d65_6e64_656c;
+
+thread_local! {
+    /// The seed of unseeded simulations on this thread, set by `testing::with_fixed_seed`.
+    static FIXED_SEED: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// The seed unseeded simulations on this thread are fixed to, if any.
+pub(crate) fn fixed_seed() -> Option<u64> {
+    FIXED_SEED.with(Cell::get)
+}
+
+pub(crate) fn set_fixed_seed(seed: Option<u64>) {
+    FIXED_SEED.with(|fixed| fixed.set(seed));
+}
+
+/// The seed of worker `worker` (counting from 0) of a parallel run seeded with `seed`, see
+/// [`Bag::set_parallelism`](struct.Bag.html#method.set_parallelism).
+///
+/// It's output number `worker + 1` of the SplitMix64 generator started from `seed`, so every
+/// worker's stream is fixed by the seed and the worker's number alone, whatever the generator
+/// kind and however the threads get scheduled. Replicates and other independent runs derived from
+/// a seeded bag use the same scheme.
+///
+/// # Examples
+///
+/// A parallel run's second worker simulates exactly like a single-threaded bag with its seed:
+///
+/// ```
+/// use mendel::{worker_seed, Bag};
+///
+/// let mut parallel = Bag::from_range(1, 11);
+/// parallel.set_seed(42);
+/// parallel.set_max_sims(2_000);
+/// parallel.set_parallelism(2);
+/// let mut workers = Vec::new();
+/// for worker in 0..2 {
+///     let mut single = Bag::from_range(1, 11);
+///     single.set_seed(worker_seed(42, worker));
+///     single.set_max_sims(1_000);
+///     workers.push(single.one_detailed(|v| v % 2 == 0));
+/// }
+/// assert_eq!(parallel.one_detailed(|v| v % 2 == 0), workers[0].merge(&workers[1]));
+/// ```
+pub fn worker_seed(seed: u64, worker: u64) -> u64 {
+    let mut state = seed.wrapping_add(worker.wrapping_mul(SPLITMIX_GAMMA));
+    splitmix64(&mut state)
+}
+
+/// The seed of a run: its own, the thread's fixed seed, or a fresh random one (or
+/// `DETERMINISTIC_SEED` with the `deterministic` feature).
+pub(crate) fn run_seed(seed: Option<u64>) -> u64 {
+    match seed.or_else(fixed_seed) {
+        Some(seed) => seed,
+        None if cfg!(feature = "deterministic") => DETERMINISTIC_SEED,
+        None => thread_rng().gen()
+    }
+}
+
+/// The random number generator algorithms a `Bag` can simulate with, see
+/// [`Bag::set_rng_kind`](struct.Bag.html#method.set_rng_kind).
+///
+/// Roughly from highest statistical quality to fastest.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RngKind {
+    /// rand's `StdRng` (HC-128), the same algorithm as the thread RNG. The default.
+    #[default]
+    Std,
+    /// The ChaCha stream cipher with 20 rounds.
+    ChaCha,
+    /// PCG32 (XSH RR 64/32), a small, fast generator with good statistical quality.
+    Pcg,
+    /// xoshiro256**, a very fast generator with good statistical quality.
+    Xoshiro,
+    /// Marsaglia's Xorshift128. The fastest, but fails several statistical tests.
+    XorShift
+}
+
+/// The random number generator used for a single simulation run.
+///
+/// Bags only store an optional seed and build one of these at the start of every run, so a
+/// configured bag holds no mutable RNG state and stays `Send + Sync`.
+///
+/// Cloning copies the stream position, so the clone produces the same numbers from there on. Only the
+/// thread RNG is shared instead.
+#[derive(Clone)]
+pub(crate) enum SimRng {
+    Thread(ThreadRng),
+    Std(Box<StdRng>),
+    ChaCha(Box<ChaChaRng>),
+    Pcg(Pcg32),
+    Xoshiro(Xoshiro256),
+    XorShift(XorShiftRng)
+}
+
+impl SimRng {
+
+    /// Builds the generator for a run: seeded runs are reproducible, unseeded runs use the thread's
+    /// fixed seed if there is one, and are otherwise seeded from the thread RNG (or use
+    /// `DETERMINISTIC_SEED` with the `deterministic` feature).
+    pub(crate) fn new(seed: Option<u64>, kind: RngKind) -> SimRng {
+        let seed = match seed.or_else(fixed_seed) {
+            Some(seed) => seed,
+            None if cfg!(feature = "deterministic") => DETERMINISTIC_SEED,
+            // The thread RNG already is a StdRng, so there's no need to seed another
+            None if kind == RngKind::Std => return SimRng::Thread(thread_rng()),
+            None => thread_rng().gen()
+        };
+        match kind {
+            RngKind::Std => SimRng::Std(Box::new(StdRng::seed_from_u64(seed))),
+            RngKind::ChaCha => SimRng::ChaCha(Box::new(ChaChaRng::seed_from_u64(seed))),
+            RngKind::Pcg => SimRng::Pcg(Pcg32::new(seed)),
+            RngKind::Xoshiro => SimRng::Xoshiro(Xoshiro256::new(seed)),
+            RngKind::XorShift => SimRng::XorShift(XorShiftRng::seed_from_u64(seed))
+        }
+    }
+}
+
+macro_rules! dispatch {
+    ($rng:expr, $inner:ident => $call:expr) => {
+        match *$rng {
+            SimRng::Thread(ref mut $inner) => $call,
+            SimRng::Std(ref mut $inner) => $call,
+            SimRng::ChaCha(ref mut $inner) => $call,
+            SimRng::Pcg(ref mut $inner) => $call,
+            SimRng::Xoshiro(ref mut $inner) => $call,
+            SimRng::XorShift(ref mut $inner) => $call
+        }
+    };
+}
+
+impl RngCore for SimRng {
+    fn next_u32(&mut self) -> u32 {
+        dispatch!(self, rng => rng.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        dispatch!(self, rng => rng.next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dispatch!(self, rng => rng.fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        dispatch!(self, rng => rng.try_fill_bytes(dest))
+    }
+}
+
+/// The increment of SplitMix64's state.
+const SPLITMIX_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Expands a `u64` seed into well mixed state words.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(SPLITMIX_GAMMA);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Fills `dest` from a generator's `next_u64`, in little endian order.
+fn fill_via_u64<R: RngCore>(rng: &mut R, dest: &mut [u8]) {
+    for chunk in dest.chunks_mut(8) {
+        let bytes = rng.next_u64().to_le_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
+
+/// The PCG32 generator (XSH RR 64/32) by Melissa O'Neill.
+#[derive(Clone)]
+pub(crate) struct Pcg32 {
+    state: u64,
+    increment: u64
+}
+
+impl Pcg32 {
+    const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+
+    fn new(seed: u64) -> Self {
+        let mut mix = seed;
+        let mut rng = Pcg32 { state: 0, increment: (splitmix64(&mut mix) << 1) | 1 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(splitmix64(&mut mix));
+        rng.next_u32();
+        rng
+    }
+}
+
+impl RngCore for Pcg32 {
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(Pcg32::MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let low = self.next_u32() as u64;
+        (self.next_u32() as u64) << 32 | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill_via_u64(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// The xoshiro256** generator by David Blackman and Sebastiano Vigna.
+#[derive(Clone)]
+pub(crate) struct Xoshiro256 {
+    s: [u64; 4]
+}
+
+impl Xoshiro256 {
+    fn new(seed: u64) -> Self {
+        // splitmix64 never produces four zero words, which xoshiro can't start from
+        let mut mix = seed;
+        Xoshiro256 { s: [splitmix64(&mut mix), splitmix64(&mut mix), splitmix64(&mut mix), splitmix64(&mut mix)] }
+    }
+}
+
+impl RngCore for Xoshiro256 {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let result = self.s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.s[1] << 17;
+        self.s[2] ^= self.s[0];
+        self.s[3] ^= self.s[1];
+        self.s[1] ^= self.s[2];
+        self.s[0] ^= self.s[3];
+        self.s[2] ^= t;
+        self.s[3] = self.s[3].rotate_left(45);
+        result
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill_via_u64(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
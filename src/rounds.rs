@@ -0,0 +1,202 @@
+use rand::seq;
+
+use bag::{draw_sample, Bag};
+use engine::Settings;
+use result::SimulationResult;
+use rng::SimRng;
+
+/// A game-like simulation where every trial draws from the bag over several rounds, see
+/// [`Bag::rounds`](struct.Bag.html#method.rounds).
+///
+/// Each trial starts from a fresh state, which is updated with every round's draw until the
+/// rounds run out or the update ends the trial, and the final state decides the outcome.
+pub struct Rounds<'a, T: 'a> {
+    items: &'a [T],
+    settings: Settings,
+    rounds: usize,
+    draws_per_round: usize,
+    replacement: Replacement<'a, T>
+}
+
+/// What goes back into the bag for an item drawn, see `Rounds::restocking`.
+type RestockRule<'a, T> = Box<dyn Fn(&T) -> Vec<T> + 'a>;
+
+/// What happens to the items drawn in a round before the next one.
+enum Replacement<'a, T: 'a> {
+    /// They're put back.
+    All,
+    /// They're kept out.
+    None,
+    /// They're kept out, and a rule restocks the working copy of the bag made by `stock`.
+    Restock {
+        stock: Box<dyn Fn() -> Vec<T> + 'a>,
+        rule: RestockRule<'a, T>
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Sets up a simulation of `rounds` rounds of `draws_per_round` random items each.
+    ///
+    /// By default every round draws from the full bag, as if the items were put back after each
+    /// round. See [`Rounds::without_replacement`](struct.Rounds.html#method.without_replacement) and
+    /// [`Rounds::restocking`](struct.Rounds.html#method.restocking).
+    ///
+    /// # Examples
+    ///
+    /// Rolling a die up to 3 times and stopping at the first 6, what are the odds of getting one?
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let die = Bag::from_range(1, 7);
+    /// let odds = die.rounds(3, 1).run(
+    ///     || false,
+    ///     |got_six, roll| {
+    ///         *got_six = *roll[0] == 6;
+    ///         !*got_six
+    ///     },
+    ///     |got_six| *got_six
+    /// ).probability();
+    /// // 1 - (5/6)^3
+    /// assert!(0.41 < odds && odds < 0.43);
+    /// ```
+    pub fn rounds(&self, rounds: usize, draws_per_round: usize) -> Rounds<'_, T> {
+        Rounds { items: &self.items, settings: self.settings(), rounds, draws_per_round, replacement: Replacement::All }
+    }
+}
+
+impl<'a, T> Rounds<'a, T> {
+
+    /// Items drawn in a round are kept out of the later rounds of the same trial, like cards dealt from a deck.
+    ///
+    /// # Panics
+    ///
+    /// Simulations panic if all rounds together draw more items than the bag holds.
+    pub fn without_replacement(mut self) -> Self {
+        self.replacement = Replacement::None;
+        self
+    }
+
+    /// Items drawn in a round are taken out, and `rule` decides what goes back into the bag for
+    /// each of them before the next round: nothing, the item itself, or something else entirely.
+    ///
+    /// This models processes like inventory or quality control, where the stock changes as it's
+    /// used. Every trial starts again from the full bag.
+    ///
+    /// # Panics
+    ///
+    /// Simulations panic if a round draws more items than are left in the bag.
+    ///
+    /// # Examples
+    ///
+    /// A bin holds 2 defective and 8 good parts. Every day 2 parts are inspected and put back, with
+    /// defective ones swapped for good ones. What are the odds the third day finds no defects?
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let bin = Bag::from_counts(vec![("defective", 2), ("good", 8)]);
+    /// let odds = bin.rounds(3, 2)
+    ///     .restocking(|_| vec!["good"])
+    ///     .run(|| (0, false), |(day, clean), parts| {
+    ///         *day += 1;
+    ///         *clean = parts.iter().all(|p| **p == "good");
+    ///         true
+    ///     }, |&(_, clean)| clean)
+    ///     .probability();
+    /// // 68581 / 91125, against 28 / 45 on the first day
+    /// assert!(0.74 < odds && odds < 0.77);
+    /// ```
+    pub fn restocking<R>(mut self, rule: R) -> Self where
+        T: Clone,
+        R: Fn(&T) -> Vec<T> + 'a {
+        let items = self.items;
+        self.replacement = Replacement::Restock { stock: Box::new(move || items.to_vec()), rule: Box::new(rule) };
+        self
+    }
+
+    /// Plays out a single trial and returns its final state.
+    ///
+    /// `update` gets the state and the round's draw, and returns whether to keep playing.
+    fn play<S, I, U>(&self, rng: &mut SimRng, init: &I, update: &U) -> S where
+        I: Fn() -> S,
+        U: Fn(&mut S, Vec<&T>) -> bool {
+        let mut state = init();
+        match self.replacement {
+            Replacement::All => {
+                for _ in 0..self.rounds {
+                    if !update(&mut state, draw_sample(rng, self.items, self.draws_per_round)) {
+                        break;
+                    }
+                }
+            }
+            Replacement::None => {
+                let needed = self.rounds * self.draws_per_round;
+                assert!(needed <= self.items.len(), "{} rounds of {} draws is larger than the population of {}",
+                        self.rounds, self.draws_per_round, self.items.len());
+                // The indices come back in random order, so consecutive chunks are the rounds' draws
+                let indices = seq::sample_indices(rng, self.items.len(), needed);
+                for round in indices.chunks(self.draws_per_round.max(1)).take(self.rounds) {
+                    if !update(&mut state, round.iter().map(|&i| &self.items[i]).collect()) {
+                        break;
+                    }
+                }
+            }
+            Replacement::Restock { ref stock, ref rule } => {
+                let mut stock = stock();
+                for _ in 0..self.rounds {
+                    assert!(self.draws_per_round <= stock.len(), "a round of {} draws is larger than the {} items left",
+                            self.draws_per_round, stock.len());
+                    let mut indices = seq::sample_indices(rng, stock.len(), self.draws_per_round);
+                    // Taking the highest indices out first leaves the lower ones in place
+                    indices.sort_unstable_by(|a, b| b.cmp(a));
+                    let drawn: Vec<T> = indices.into_iter().map(|i| stock.swap_remove(i)).collect();
+                    if !update(&mut state, drawn.iter().collect()) {
+                        break;
+                    }
+                    for item in &drawn {
+                        stock.extend(rule(item));
+                    }
+                }
+            }
+        }
+        state
+    }
+
+    /// Runs the simulations: each trial's state starts as `init()`, goes through `update` after every
+    /// round (returning `false` ends the trial early) and is finally judged by `f`.
+    pub fn run<S, I, U, F>(&self, init: I, update: U, f: F) -> SimulationResult where
+        I: Fn() -> S,
+        U: Fn(&mut S, Vec<&T>) -> bool,
+        F: Fn(&S) -> bool {
+        self.settings.run(|rng| f(&self.play(rng, &init, &update)))
+    }
+
+    /// Like [`run`](#method.run), but maps each trial's final state to a score and returns the average score.
+    ///
+    /// # Examples
+    ///
+    /// The average total of 3 rolls of a die:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let die = Bag::from_range(1, 7);
+    /// let average = die.rounds(3, 1).mean_score(|| 0, |total, roll| { *total += *roll[0]; true }, |total| *total as f64);
+    /// assert!(10.4 < average && average < 10.6);
+    /// ```
+    pub fn mean_score<S, I, U, G>(&self, init: I, update: U, score: G) -> f64 where
+        I: Fn() -> S,
+        U: Fn(&mut S, Vec<&T>) -> bool,
+        G: Fn(&S) -> f64 {
+        let mut total = 0.0;
+        let rounds = self.settings.repeat(|rng| total += score(&self.play(rng, &init, &update)));
+        total / rounds as f64
+    }
+
+    /// Set the maximum amount of simulations to run.
+    pub fn set_max_sims(&mut self, max_sims: u32) {
+        self.settings.set_max_sims(max_sims);
+    }
+}
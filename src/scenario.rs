@@ -0,0 +1,219 @@
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use bag::Bag;
+use budget::Budget;
+use report::{Events, Report};
+
+/// A named, reusable experiment: a bag, the sample sizes to draw, the events to watch, and how much
+/// to simulate.
+///
+/// The definition other than the bag and the events, which are code, can be saved with
+/// [`to_config`](#method.to_config) and loaded with [`from_config`](#method.from_config), so it can
+/// be checked in and rerun the same way. The config is a `key = value` line for each of `name`,
+/// `sample_sizes`, and optionally `seed` and `budget`:
+///
+/// ```text
+/// # Lines starting with a # are comments
+/// name = two draws
+/// sample_sizes = 2, 3
+/// seed = 7
+/// budget = trials 10000
+/// ```
+///
+/// A budget is one of `trials <count>`, `seconds <duration>` or `precision <epsilon> <confidence>`.
+pub struct Scenario<'a, T: 'a> {
+    pub name: String,
+    pub bag: Bag<T>,
+    pub sample_sizes: Vec<usize>,
+    /// The budget of every run, the bag's own if not set.
+    pub budget: Option<Budget>,
+    /// The seed of every run, the bag's own if not set.
+    pub seed: Option<u64>,
+    pub events: Events<'a, T>
+}
+
+/// The reports of every sample size of a [`Scenario`](struct.Scenario.html) run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScenarioReport {
+    pub name: String,
+    /// One report per sample size, in the scenario's order.
+    pub reports: Vec<Report>
+}
+
+impl ScenarioReport {
+
+    /// Returns the report for `sample_size`.
+    pub fn get(&self, sample_size: usize) -> Option<&Report> {
+        self.reports.iter().find(|report| report.sample_size == sample_size)
+    }
+}
+
+impl fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for report in &self.reports {
+            write!(f, "\n\nsample size {}\n{}", report.sample_size, report)?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn format_budget(budget: &Budget) -> String {
+    match *budget {
+        Budget::Trials(trials) => format!("trials {}", trials),
+        Budget::Time(duration) => format!("seconds {}", duration.as_secs_f64()),
+        Budget::Precision { epsilon, confidence } => format!("precision {} {}", epsilon, confidence)
+    }
+}
+
+fn parse_budget(value: &str) -> Option<Budget> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    match words[..] {
+        ["trials", trials] => trials.parse().ok().map(Budget::Trials),
+        ["seconds", seconds] => seconds.parse().ok().filter(|s: &f64| *s >= 0.0 && s.is_finite()).map(|s| Budget::Time(Duration::from_secs_f64(s))),
+        ["precision", epsilon, confidence] => {
+            let (epsilon, confidence) = (epsilon.parse().ok()?, confidence.parse().ok()?);
+            if epsilon > 0.0 && confidence > 0.0 && confidence < 1.0 {
+                Some(Budget::Precision { epsilon, confidence })
+            } else {
+                None
+            }
+        }
+        _ => None
+    }
+}
+
+impl<'a, T> Scenario<'a, T> {
+
+    /// Constructs a scenario with no sample sizes yet, using the bag's own budget and seed.
+    pub fn new(name: &str, bag: Bag<T>, events: Events<'a, T>) -> Self {
+        Scenario { name: name.to_string(), bag, sample_sizes: Vec::new(), budget: None, seed: None, events }
+    }
+
+    /// Adds a sample size to run the events over.
+    pub fn sample_size(mut self, sample_size: usize) -> Self {
+        self.sample_sizes.push(sample_size);
+        self
+    }
+
+    /// Sets the budget of every run.
+    pub fn budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Sets the seed of every run, making the scenario's reports reproducible.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Runs every sample size, with the same seed if one is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a sample size is larger than the bag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, Budget, Events, Scenario};
+    ///
+    /// let events = Events::new().add("has a 1", |values| values.iter().any(|v| **v == 1));
+    /// let scenario = Scenario::new("small draws", Bag::from_range(1, 11), events)
+    ///     .sample_size(1)
+    ///     .sample_size(5)
+    ///     .budget(Budget::Trials(10_000))
+    ///     .seed(7);
+    /// let report = scenario.run();
+    /// let odds = report.get(5).unwrap().get("has a 1").unwrap().probability();
+    /// assert!(0.48 < odds && odds < 0.52);
+    /// assert_eq!(report, scenario.run());
+    /// ```
+    pub fn run(&self) -> ScenarioReport {
+        let mut bag = self.bag.clone();
+        if let Some(budget) = self.budget {
+            bag.set_budget(budget);
+        }
+        if let Some(seed) = self.seed {
+            bag.set_seed(seed);
+        }
+        let reports = self.sample_sizes.iter().map(|&k| bag.report(k, &self.events)).collect();
+        ScenarioReport { name: self.name.clone(), reports }
+    }
+
+    /// Formats the scenario's definition, other than its bag and events, as a config.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, Events, Scenario};
+    ///
+    /// let scenario = Scenario::new("pairs", Bag::from_range(1, 11), Events::new()).sample_size(2).seed(7);
+    /// assert_eq!(scenario.to_config(), "name = pairs\nsample_sizes = 2\nseed = 7\n");
+    /// ```
+    pub fn to_config(&self) -> String {
+        let sizes: Vec<String> = self.sample_sizes.iter().map(|k| k.to_string()).collect();
+        let mut config = format!("name = {}\nsample_sizes = {}\n", self.name, sizes.join(", "));
+        if let Some(seed) = self.seed {
+            config.push_str(&format!("seed = {}\n", seed));
+        }
+        if let Some(ref budget) = self.budget {
+            config.push_str(&format!("budget = {}\n", format_budget(budget)));
+        }
+        config
+    }
+
+    /// Loads a scenario's definition from a config written by [`to_config`](#method.to_config) or by
+    /// hand, supplying the bag and events it runs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidData` error for unknown keys, invalid values, or a missing `name` or
+    /// `sample_sizes`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, Budget, Events, Scenario};
+    ///
+    /// let config = "# Checked in next to the code\nname = pairs\nsample_sizes = 2, 3\nbudget = trials 5000\n";
+    /// let events = Events::new().add("has a 1", |values| values.iter().any(|v| **v == 1));
+    /// let scenario = Scenario::from_config(config, Bag::from_range(1, 11), events).unwrap();
+    /// assert_eq!(scenario.sample_sizes, vec![2, 3]);
+    /// assert_eq!(scenario.budget, Some(Budget::Trials(5_000)));
+    /// assert_eq!(scenario.run().reports.len(), 2);
+    /// ```
+    pub fn from_config(config: &str, bag: Bag<T>, events: Events<'a, T>) -> io::Result<Self> {
+        let (mut name, mut sample_sizes, mut seed, mut budget) = (None, None, None, None);
+        for (number, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || invalid_data(format!("line {} is not a valid setting: {}", number + 1, line));
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts.next().ok_or_else(invalid)?.trim();
+            match key {
+                "name" => name = Some(value.to_string()),
+                "sample_sizes" => {
+                    let sizes: Result<Vec<usize>, _> = value.split(',').map(|k| k.trim().parse()).collect();
+                    sample_sizes = Some(sizes.map_err(|_| invalid())?);
+                }
+                "seed" => seed = Some(value.parse().map_err(|_| invalid())?),
+                "budget" => budget = Some(parse_budget(value).ok_or_else(invalid)?),
+                _ => return Err(invalid_data(format!("line {} has an unknown key \"{}\"", number + 1, key)))
+            }
+        }
+        let name = name.ok_or_else(|| invalid_data("the config has no name".to_string()))?;
+        let sample_sizes = sample_sizes.ok_or_else(|| invalid_data("the config has no sample_sizes".to_string()))?;
+        Ok(Scenario { name, bag, sample_sizes, budget, seed, events })
+    }
+}
@@ -0,0 +1,76 @@
+use std::thread;
+
+use bag::Bag;
+
+/// Computes the probability curve of an event as a parameter of the population varies.
+///
+/// `build` constructs the bag for each parameter value, and the probability of `f` being met by
+/// `sample_size` random items from it (as with [`Bag::sample`](struct.Bag.html#method.sample)) is
+/// returned alongside each value, in order.
+///
+/// # Examples
+///
+/// Odds of no blue balls in 2 picks as the number of blue balls (out of 10) grows:
+///
+/// ```
+/// use mendel::{sensitivity, Bag};
+///
+/// let curve = sensitivity(0..=10, |blue| {
+///     let balls: Vec<bool> = (0..10).map(|i| i < blue).collect();
+///     Bag::from_vec(balls)
+/// }, 2, |balls| balls.iter().all(|is_blue| !**is_blue));
+/// assert_eq!(curve[0], (0, 1.0));
+/// assert_eq!(curve[10], (10, 0.0));
+/// assert!(curve.windows(2).all(|w| w[0].1 >= w[1].1));
+/// ```
+pub fn sensitivity<P, T, I, B, F>(values: I, build: B, sample_size: usize, f: F) -> Vec<(P, f64)> where
+    P: Clone,
+    I: IntoIterator<Item = P>,
+    T: Sync,
+    B: Fn(P) -> Bag<T>,
+    F: Fn(Vec<&T>) -> bool + Sync {
+    values.into_iter()
+        .map(|value| {
+            let bag = build(value.clone());
+            (value, bag.sample(sample_size, &f))
+        })
+        .collect()
+}
+
+/// Like [`sensitivity`](fn.sensitivity.html), but evaluates the parameter values in parallel over
+/// `threads` worker threads.
+///
+/// # Examples
+///
+/// ```
+/// use mendel::{sensitivity_parallel, Bag};
+///
+/// let curve = sensitivity_parallel(1..=4, |max| Bag::from_range(1, max + 1), 1, |v| *v[0] == 1, 2);
+/// assert_eq!(curve.len(), 4);
+/// assert_eq!(curve[0], (1, 1.0));
+/// ```
+pub fn sensitivity_parallel<P, T, I, B, F>(values: I, build: B, sample_size: usize, f: F, threads: usize) -> Vec<(P, f64)> where
+    P: Clone + Send,
+    T: Sync,
+    I: IntoIterator<Item = P>,
+    B: Fn(P) -> Bag<T> + Sync,
+    F: Fn(Vec<&T>) -> bool + Sync {
+    let values: Vec<P> = values.into_iter().collect();
+    let chunk_size = ((values.len() + threads.max(1) - 1) / threads.max(1)).max(1);
+    let mut values = values.into_iter();
+    let mut chunks: Vec<Vec<P>> = Vec::new();
+    loop {
+        let chunk: Vec<P> = values.by_ref().take(chunk_size).collect();
+        if chunk.is_empty() {
+            break;
+        }
+        chunks.push(chunk);
+    }
+    let (build, f) = (&build, &f);
+    thread::scope(|scope| {
+        let handles: Vec<_> = chunks.into_iter()
+            .map(|chunk| scope.spawn(move || sensitivity(chunk, build, sample_size, f)))
+            .collect();
+        handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    })
+}
@@ -0,0 +1,117 @@
+use rand::seq;
+
+use bag::Bag;
+use dynamic::draw_dynamic;
+
+impl<T> Bag<T> {
+
+    /// Predicts probability of a sequence of `draws` items, drawn in order without replacement,
+    /// containing a run of at least `run_length` consecutive items that meet the criteria.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `draws` is larger than the number of items in the bag.
+    ///
+    /// # Examples
+    ///
+    /// Odds of 3 reds in a row within 10 cards dealt from a deck:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let deck = Bag::from_counts(vec![("red", 26), ("black", 26)]);
+    /// let odds = deck.streak(10, 3, |card| *card == "red");
+    /// assert!(0.48 < odds && odds < 0.54);
+    /// ```
+    pub fn streak<F>(&self, draws: usize, run_length: usize, f: F) -> f64 where
+        F: Fn(&T) -> bool {
+        let len = self.items.len();
+        assert!(draws <= len, "draws {} is larger than the population of {}", draws, len);
+        self.settings().run(|rng| {
+            // The indices come back in random order, so they're a random sequence of draws
+            let mut run = 0;
+            for i in seq::sample_indices(rng, len, draws) {
+                run = if f(&self.items[i]) { run + 1 } else { 0 };
+                if run >= run_length {
+                    return true;
+                }
+            }
+            run_length == 0
+        }).probability()
+    }
+
+    /// Returns, for each position of a sequence of `draws` items drawn in order without replacement,
+    /// the probability the item drawn at that position meets the criteria.
+    ///
+    /// Draws without replacement are exchangeable, so every position has the same probability as a
+    /// single grab: the first card dealt is as likely to be an ace as the tenth. See
+    /// [`positional_marginals_dynamic`](#method.positional_marginals_dynamic) for populations that
+    /// change as they're drawn from, where this no longer holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `draws` is larger than the number of items in the bag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let deck = Bag::from_counts(vec![("ace", 4), ("other", 48)]);
+    /// let marginals = deck.positional_marginals(5, |card| *card == "ace");
+    /// assert_eq!(marginals.len(), 5);
+    /// assert!(marginals.iter().all(|p| 0.07 < *p && *p < 0.085));
+    /// ```
+    pub fn positional_marginals<F>(&self, draws: usize, f: F) -> Vec<f64> where
+        F: Fn(&T) -> bool {
+        let len = self.items.len();
+        assert!(draws <= len, "draws {} is larger than the population of {}", draws, len);
+        let mut counts = vec![0u64; draws];
+        let trials = self.settings().repeat(|rng| {
+            for (count, i) in counts.iter_mut().zip(seq::sample_indices(rng, len, draws)) {
+                if f(&self.items[i]) {
+                    *count += 1;
+                }
+            }
+        });
+        counts.into_iter().map(|count| count as f64 / trials as f64).collect()
+    }
+
+    /// Like [`positional_marginals`](#method.positional_marginals), but draws from a population that
+    /// changes as it's drawn from, as with [`sample_dynamic`](#method.sample_dynamic).
+    ///
+    /// Positions past the point the population ran out count as not meeting the criteria.
+    ///
+    /// # Examples
+    ///
+    /// A Pólya urn, where every marble drawn is put back with another of its color, stays
+    /// exchangeable. Replacing every blue marble drawn with a red one doesn't:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let urn = Bag::from_counts(vec![("red", 1), ("blue", 1)]);
+    /// let polya = urn.positional_marginals_dynamic(3, |drawn, urn| urn.extend(vec![*drawn; 2]), |m| *m == "red");
+    /// assert!(polya.iter().all(|p| 0.49 < *p && *p < 0.51));
+    /// let reddening = urn.positional_marginals_dynamic(2, |drawn, urn| {
+    ///     urn.push(if *drawn == "blue" { "red" } else { *drawn });
+    /// }, |m| *m == "red");
+    /// // The second draw is red unless both draws are blue, 1/2 * 1/2
+    /// assert!(0.74 < reddening[1] && reddening[1] < 0.76);
+    /// ```
+    pub fn positional_marginals_dynamic<U, F>(&self, draws: usize, update: U, f: F) -> Vec<f64> where
+        T: Clone,
+        U: Fn(&T, &mut Vec<T>),
+        F: Fn(&T) -> bool {
+        let mut counts = vec![0u64; draws];
+        let trials = self.settings().repeat(|rng| {
+            let (drawn, _) = draw_dynamic(rng, &self.items, draws, &update);
+            for (count, item) in counts.iter_mut().zip(&drawn) {
+                if f(item) {
+                    *count += 1;
+                }
+            }
+        });
+        counts.into_iter().map(|count| count as f64 / trials as f64).collect()
+    }
+}
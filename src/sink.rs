@@ -0,0 +1,171 @@
+use std::io::{self, Write};
+
+use bag::{draw_sample, pick_one, Bag};
+use result::SimulationResult;
+use rng::SimRng;
+
+/// Receives the results of a simulation as it runs, see
+/// [`Bag::one_to_sink`](struct.Bag.html#method.one_to_sink).
+///
+/// The engine calls `on_progress` with the aggregated result after every chunk of 1,000
+/// simulations (and once more with the final counts), then `on_complete` with the final result. Both
+/// are called on the thread that started the simulation, even when it runs in parallel.
+pub trait ResultSink {
+    /// Called with the result so far. Does nothing by default.
+    fn on_progress(&mut self, _partial: &SimulationResult) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called once with the final result.
+    fn on_complete(&mut self, result: &SimulationResult) -> io::Result<()>;
+}
+
+/// Prints a line with the estimate and its 95% confidence interval for every update.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StdoutSink;
+
+impl ResultSink for StdoutSink {
+    fn on_progress(&mut self, partial: &SimulationResult) -> io::Result<()> {
+        let (low, high) = partial.confidence_interval();
+        writeln!(io::stdout(), "{} trials: {:.4} ({:.4} - {:.4})", partial.trials, partial.probability(), low, high)
+    }
+
+    fn on_complete(&mut self, result: &SimulationResult) -> io::Result<()> {
+        let (low, high) = result.confidence_interval();
+        writeln!(io::stdout(), "done after {} trials: {:.4} ({:.4} - {:.4})", result.trials, result.probability(), low, high)
+    }
+}
+
+/// Writes a `trials,successes,probability,ci_low,ci_high,complete` header, then a row for every update.
+pub struct CsvSink<W: Write> {
+    writer: W,
+    started: bool
+}
+
+impl<W: Write> CsvSink<W> {
+
+    /// Constructs a sink writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        CsvSink { writer, started: false }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_row(&mut self, result: &SimulationResult, complete: bool) -> io::Result<()> {
+        if !self.started {
+            writeln!(self.writer, "trials,successes,probability,ci_low,ci_high,complete")?;
+            self.started = true;
+        }
+        let (low, high) = result.confidence_interval();
+        writeln!(self.writer, "{},{},{},{},{},{}", result.trials, result.successes, result.probability(), low, high, complete)
+    }
+}
+
+impl<W: Write> ResultSink for CsvSink<W> {
+    fn on_progress(&mut self, partial: &SimulationResult) -> io::Result<()> {
+        self.write_row(partial, false)
+    }
+
+    fn on_complete(&mut self, result: &SimulationResult) -> io::Result<()> {
+        self.write_row(result, true)?;
+        self.writer.flush()
+    }
+}
+
+/// Writes one JSON object per update, e.g.
+/// `{"trials":1000,"successes":503,"probability":0.503,"ci_low":0.472,"ci_high":0.534,"complete":false}`.
+pub struct JsonLinesSink<W: Write> {
+    writer: W
+}
+
+impl<W: Write> JsonLinesSink<W> {
+
+    /// Constructs a sink writing to `writer`.
+    pub fn new(writer: W) -> Self {
+        JsonLinesSink { writer }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_line(&mut self, result: &SimulationResult, complete: bool) -> io::Result<()> {
+        let (low, high) = result.confidence_interval();
+        writeln!(self.writer, "{{\"trials\":{},\"successes\":{},\"probability\":{},\"ci_low\":{},\"ci_high\":{},\"complete\":{}}}",
+                 result.trials, result.successes, json_number(result.probability()), json_number(low), json_number(high), complete)
+    }
+}
+
+/// Formats `value` as a JSON number, or `null` if it's NaN or infinite, which JSON can't represent.
+fn json_number(value: f64) -> String {
+    if value.is_finite() { value.to_string() } else { "null".to_string() }
+}
+
+impl<W: Write> ResultSink for JsonLinesSink<W> {
+    fn on_progress(&mut self, partial: &SimulationResult) -> io::Result<()> {
+        self.write_line(partial, false)
+    }
+
+    fn on_complete(&mut self, result: &SimulationResult) -> io::Result<()> {
+        self.write_line(result, true)?;
+        self.writer.flush()
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Like [`one_detailed`](#method.one_detailed), but feeds the result to `sink` while the simulation
+    /// runs and once it completes.
+    ///
+    /// Stops feeding the sink at its first error and returns it once the run ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, CsvSink};
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_max_sims(3_000);
+    /// let mut sink = CsvSink::new(Vec::new());
+    /// let result = my_bag.one_to_sink(|v| v % 2 == 0, &mut sink).unwrap();
+    /// let csv = String::from_utf8(sink.into_inner()).unwrap();
+    /// // The header, a row per 1,000 simulations, and the final row
+    /// assert_eq!(csv.lines().count(), 5);
+    /// assert!(csv.lines().last().unwrap().starts_with(&format!("3000,{},", result.successes)));
+    /// ```
+    pub fn one_to_sink<F, S>(&self, f: F, sink: &mut S) -> io::Result<SimulationResult> where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync,
+        S: ResultSink {
+        let items: &[T] = &self.items;
+        self.feed(|rng| f(pick_one(rng, items)), sink)
+    }
+
+    /// Like [`sample_detailed`](#method.sample_detailed), but feeds the result to `sink` while the
+    /// simulation runs and once it completes, see [`one_to_sink`](#method.one_to_sink).
+    pub fn sample_to_sink<F, S>(&self, sample_size: usize, f: F, sink: &mut S) -> io::Result<SimulationResult> where
+        T: Sync,
+        F: Fn(Vec<&T>) -> bool + Sync,
+        S: ResultSink {
+        let items: &[T] = &self.items;
+        self.feed(|rng| f(draw_sample(rng, items, sample_size)), sink)
+    }
+
+    fn feed<F, S>(&self, trial: F, sink: &mut S) -> io::Result<SimulationResult> where
+        F: Fn(&mut SimRng) -> bool + Sync,
+        S: ResultSink {
+        let mut fed = Ok(());
+        let result = self.settings().run_parallel_with(trial, |partial| {
+            if fed.is_ok() {
+                fed = sink.on_progress(partial);
+            }
+        });
+        fed?;
+        sink.on_complete(&result)?;
+        Ok(result)
+    }
+}
@@ -0,0 +1,100 @@
+use rand::Rng;
+
+use bag::Bag;
+use rng::SimRng;
+
+/// The estimate of a rare event by multilevel splitting, see
+/// [`Bag::rare_event`](struct.Bag.html#method.rare_event).
+#[derive(Clone, Debug, PartialEq)]
+pub struct RareEvent {
+    /// The estimated probability, the product of `level_probabilities`.
+    pub probability: f64,
+    /// For every level, the estimated probability of reaching it from the level before.
+    pub level_probabilities: Vec<f64>,
+    /// For every level, the simulations run to reach it from the level before.
+    pub level_trials: Vec<u64>
+}
+
+/// Draws one more index, uniformly among those of `len` not in `drawn`.
+fn draw_next(rng: &mut SimRng, len: usize, drawn: &[usize]) -> usize {
+    let mut index = rng.gen_range(0, len - drawn.len());
+    let mut taken = drawn.to_vec();
+    taken.sort_unstable();
+    // Skip over the indices already drawn, the `index`th free one is the draw
+    for &d in &taken {
+        if d <= index {
+            index += 1;
+        }
+    }
+    index
+}
+
+impl<T> Bag<T> {
+
+    /// Estimates the probability that the `score` of a sample of `sample_size` items reaches the last of
+    /// `levels`, for events far too rare to simulate directly (think below 1 in 10 million).
+    ///
+    /// Items are drawn one at a time, and `score` measures the progress of the partial sample towards
+    /// the event. It needs to only ever grow as items are added, like a count of matching items. The
+    /// levels are increasing thresholds of progress: partial samples are simulated from scratch up to
+    /// the first level until the bag's budget is spent, then a budget's worth more are continued from
+    /// random samples that reached it up to the second, and so on. The rare probability is the product of the much more
+    /// common probabilities of getting from each level to the next.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `levels` is empty or not increasing, or `sample_size` is larger than the bag.
+    ///
+    /// # Examples
+    ///
+    /// The odds 5 random items out of 1,000 are all among 10 special ones, about 3 in 100 billion:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut items = Bag::from_range(0, 1_000);
+    /// items.set_max_sims(20_000);
+    /// items.set_seed(7);
+    /// let rare = items.rare_event(5, &[1.0, 2.0, 3.0, 4.0, 5.0], |sample| {
+    ///     sample.iter().filter(|v| ***v < 10).count() as f64
+    /// });
+    /// assert!(2e-11 < rare.probability && rare.probability < 5e-11);
+    /// ```
+    pub fn rare_event<F>(&self, sample_size: usize, levels: &[f64], score: F) -> RareEvent where
+        F: Fn(&[&T]) -> f64 {
+        assert!(!levels.is_empty(), "at least one level is needed");
+        assert!(levels.windows(2).all(|w| w[0] < w[1]), "levels must be increasing");
+        let len = self.items.len();
+        assert!(sample_size <= len, "sample_size {} is larger than the population of {}", sample_size, len);
+        let mut level_probabilities = Vec::with_capacity(levels.len());
+        let mut level_trials = Vec::with_capacity(levels.len());
+        // The partial samples that have reached the previous level, as drawn indices
+        let mut reached: Vec<Vec<usize>> = vec![Vec::new()];
+        for (&level, settings) in levels.iter().zip(self.settings().independent(levels.len())) {
+            let mut next = Vec::new();
+            let result = settings.run(|rng| {
+                let mut drawn = reached[rng.gen_range(0, reached.len())].clone();
+                loop {
+                    let sample: Vec<&T> = drawn.iter().map(|&i| &self.items[i]).collect();
+                    if score(&sample) >= level {
+                        next.push(drawn);
+                        return true;
+                    }
+                    if drawn.len() == sample_size {
+                        return false;
+                    }
+                    let index = draw_next(rng, len, &drawn);
+                    drawn.push(index);
+                }
+            });
+            level_probabilities.push(result.probability());
+            level_trials.push(result.trials);
+            if next.is_empty() {
+                break;
+            }
+            reached = next;
+        }
+        let probability = if level_probabilities.len() < levels.len() { 0.0 } else { level_probabilities.iter().product() };
+        RareEvent { probability, level_probabilities, level_trials }
+    }
+}
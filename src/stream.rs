@@ -0,0 +1,112 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+
+use bag::{draw_sample, pick_one, Bag};
+use engine::Settings;
+use result::SimulationResult;
+use rng::SimRng;
+
+/// A simulation running in the background, whose aggregated result can be watched while it runs,
+/// see [`Bag::one_streaming`](struct.Bag.html#method.one_streaming).
+///
+/// Dropping the subscription stops the simulation.
+pub struct Subscription {
+    receiver: Receiver<SimulationResult>,
+    current: SimulationResult,
+    /// The number of simulations to run, unless the budget is a time or a precision.
+    target: Option<u64>,
+    finished: bool
+}
+
+impl Subscription {
+
+    /// Starts the workers of `settings` on background threads.
+    fn start<F>(settings: Settings, trial: F) -> Self where
+        F: Fn(&mut SimRng) -> bool + Send + Sync + 'static {
+        let (sender, receiver) = mpsc::channel();
+        let trial = Arc::new(trial);
+        for worker in settings.workers() {
+            let (sender, trial) = (sender.clone(), Arc::clone(&trial));
+            thread::spawn(move || worker.work(&*trial, &sender));
+        }
+        Subscription { receiver, current: SimulationResult::new(0, 0), target: settings.budget().trials(), finished: false }
+    }
+
+    /// Collects the counts reported since the last poll, without blocking, and returns the result so far.
+    pub fn poll(&mut self) -> SimulationResult {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(chunk) => self.current = self.current.merge(&chunk),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+        self.current.clone()
+    }
+
+    /// Whether every worker has finished, as of the last poll.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The share of the simulations that have been reported, as of the last poll.
+    ///
+    /// Early stopping can finish a simulation before it gets to 1. Budgets that aren't a number of
+    /// simulations have no known end, and report 0 until finished.
+    pub fn progress(&self) -> f64 {
+        match self.target {
+            Some(0) => 1.0,
+            Some(target) => self.current.trials as f64 / target as f64,
+            None => if self.finished { 1.0 } else { 0.0 }
+        }
+    }
+
+    /// Blocks until the simulation finishes, and returns the final result.
+    pub fn wait(mut self) -> SimulationResult {
+        for chunk in self.receiver.iter() {
+            self.current = self.current.merge(&chunk);
+        }
+        self.current
+    }
+}
+
+impl<T: Send + Sync + 'static> Bag<T> {
+
+    /// Starts simulating the odds of `f` (as with [`one`](#method.one)) in the background, and returns
+    /// a `Subscription` to watch the aggregated result while it's in flight.
+    ///
+    /// The simulations are split over the bag's [`parallelism`](#method.set_parallelism), and every
+    /// worker reports its counts after each chunk of 1,000 simulations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_parallelism(2);
+    /// let mut subscription = my_bag.one_streaming(|v| v % 2 == 0);
+    /// while !subscription.is_finished() {
+    ///     let so_far = subscription.poll();
+    ///     assert!(so_far.trials <= 100_000);
+    /// }
+    /// assert_eq!(subscription.poll().trials, 100_000);
+    /// assert!(0.49 < subscription.poll().probability() && subscription.poll().probability() < 0.51);
+    /// ```
+    pub fn one_streaming<F>(&self, f: F) -> Subscription where
+        F: Fn(&T) -> bool + Send + Sync + 'static {
+        let items = Arc::clone(&self.items);
+        Subscription::start(self.settings(), move |rng| f(pick_one(rng, &items)))
+    }
+
+    /// Like [`one_streaming`](#method.one_streaming), but for samples as with [`sample`](#method.sample).
+    pub fn sample_streaming<F>(&self, sample_size: usize, f: F) -> Subscription where
+        F: Fn(Vec<&T>) -> bool + Send + Sync + 'static {
+        let items = Arc::clone(&self.items);
+        Subscription::start(self.settings(), move |rng| f(draw_sample(rng, &items, sample_size)))
+    }
+}
@@ -0,0 +1,210 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::AddAssign;
+
+use bag::Bag;
+
+/// A single distinct key of a [`Summary`](struct.Summary.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Group<K> {
+    pub key: K,
+    pub count: usize,
+    pub proportion: f64
+}
+
+/// The composition of a `Bag`, see [`Bag::summary`](struct.Bag.html#method.summary).
+///
+/// Groups are ordered from most to least common, ties keep the order the keys were first seen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary<K> {
+    pub total: usize,
+    pub groups: Vec<Group<K>>
+}
+
+/// Exact statistics of a numeric projection of a `Bag`, see [`Bag::stats`](struct.Bag.html#method.stats).
+///
+/// These are population parameters, so the standard deviation divides by `count`, not `count - 1`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stats {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    pub std_dev: f64
+}
+
+/// Counts the items of `items` per key, in the order the keys were first seen.
+pub(crate) fn tally<'a, T: 'a, K, I, F>(items: I, key_fn: F) -> Vec<(K, usize)> where
+    I: IntoIterator<Item = &'a T>,
+    K: Eq + Hash + Clone,
+    F: Fn(&T) -> K {
+    tally_counts(items.into_iter().map(|item| (item, 1)), key_fn)
+}
+
+/// Like `tally`, where each item comes with the number of times it occurs.
+pub(crate) fn tally_counts<'a, T: 'a, K, C, I, F>(items: I, key_fn: F) -> Vec<(K, C)> where
+    I: IntoIterator<Item = (&'a T, C)>,
+    K: Eq + Hash + Clone,
+    C: AddAssign,
+    F: Fn(&T) -> K {
+    let mut positions: HashMap<K, usize> = HashMap::new();
+    let mut counts: Vec<(K, C)> = Vec::new();
+    for (item, count) in items {
+        let key = key_fn(item);
+        match positions.get(&key) {
+            Some(&pos) => counts[pos].1 += count,
+            None => {
+                positions.insert(key.clone(), counts.len());
+                counts.push((key, count));
+            }
+        }
+    }
+    counts
+}
+
+impl<K> Summary<K> {
+
+    /// Returns the group for `key`, if any item in the bag has it.
+    pub fn get(&self, key: &K) -> Option<&Group<K>> where
+        K: PartialEq {
+        self.groups.iter().find(|g| g.key == *key)
+    }
+}
+
+impl<K: fmt::Display> fmt::Display for Summary<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let keys: Vec<String> = self.groups.iter().map(|g| g.key.to_string()).collect();
+        let key_width = keys.iter().map(|k| k.chars().count()).chain(Some("total".len())).max().unwrap_or(0);
+        let count_width = self.total.to_string().len().max("count".len());
+        writeln!(f, "{:<kw$}  {:>cw$}  {:>10}", "key", "count", "proportion", kw = key_width, cw = count_width)?;
+        for (key, group) in keys.iter().zip(self.groups.iter()) {
+            writeln!(f, "{:<kw$}  {:>cw$}  {:>9.2}%", key, group.count, group.proportion * 100.0,
+                     kw = key_width, cw = count_width)?;
+        }
+        let total_proportion = if self.total == 0 { 0.0 } else { 100.0 };
+        write!(f, "{:<kw$}  {:>cw$}  {:>9.2}%", "total", self.total, total_proportion, kw = key_width, cw = count_width)
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Summarizes the bag's composition: the count and proportion of items per distinct key.
+    ///
+    /// Handy for sanity-checking a bag before running any simulations. The `Summary` displays as a table.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let balls = Bag::from_vec(vec!["red", "blue", "red", "green", "red"]);
+    /// let summary = balls.summary(|b| *b);
+    /// assert_eq!(summary.total, 5);
+    /// assert_eq!(summary.groups[0].key, "red");
+    /// assert_eq!(summary.groups[0].count, 3);
+    /// println!("{}", summary);
+    /// ```
+    pub fn summary<K, F>(&self, key_fn: F) -> Summary<K> where
+        K: Eq + Hash + Clone,
+        F: Fn(&T) -> K {
+        let total = self.items.len();
+        let mut counts = tally(self.items.iter(), key_fn);
+        // Stable, so ties stay in order of first appearance
+        counts.sort_by_key(|&(_, count)| Reverse(count));
+        let groups = counts.into_iter()
+            .map(|(key, count)| Group { key, count, proportion: count as f64 / total as f64 })
+            .collect();
+        Summary { total, groups }
+    }
+
+    /// Counts the number of distinct keys in the bag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let numbers = Bag::from_range(1, 11);
+    /// assert_eq!(numbers.unique_count(|v| v % 3), 3);
+    /// ```
+    pub fn unique_count<K, F>(&self, key_fn: F) -> usize where
+        K: Eq + Hash + Clone,
+        F: Fn(&T) -> K {
+        tally(self.items.iter(), key_fn).len()
+    }
+
+    /// Counts the items in the bag that meet the criteria. No simulations are run, this is exact.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let numbers = Bag::from_range(1, 11);
+    /// assert_eq!(numbers.count_where(|v| v % 2 == 0), 5);
+    /// ```
+    pub fn count_where<F>(&self, f: F) -> usize where
+        F: Fn(&T) -> bool {
+        self.items.iter().filter(|item| f(item)).count()
+    }
+
+    /// Counts how many times each distinct key appears in the bag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let letters = Bag::from_vec(vec!['a', 'b', 'a', 'c', 'a']);
+    /// let multiplicities = letters.multiplicities(|l| *l);
+    /// assert_eq!(multiplicities[&'a'], 3);
+    /// assert_eq!(multiplicities[&'c'], 1);
+    /// ```
+    pub fn multiplicities<K, F>(&self, key_fn: F) -> HashMap<K, usize> where
+        K: Eq + Hash + Clone,
+        F: Fn(&T) -> K {
+        tally(self.items.iter(), key_fn).into_iter().collect()
+    }
+
+    /// Computes exact statistics of the numeric value of every item in the bag, such as the population
+    /// mean to compare with estimates from samples. Returns `None` if the bag is empty.
+    ///
+    /// With an even number of items the median is the mean of the two middle values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any value is NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let numbers = Bag::from_range(1, 11);
+    /// let stats = numbers.stats(|v| *v as f64).unwrap();
+    /// assert_eq!(stats.mean, 5.5);
+    /// assert_eq!(stats.median, 5.5);
+    /// assert_eq!((stats.min, stats.max), (1.0, 10.0));
+    /// assert!((stats.std_dev - 2.8723).abs() < 0.0001);
+    /// ```
+    pub fn stats<F>(&self, value_fn: F) -> Option<Stats> where
+        F: Fn(&T) -> f64 {
+        let mut values: Vec<f64> = self.items.iter().map(value_fn).collect();
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(|a, b| a.partial_cmp(b).expect("values must not be NaN"));
+        let count = values.len();
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let median = if count % 2 == 0 {
+            (values[count / 2 - 1] + values[count / 2]) / 2.0
+        } else {
+            values[count / 2]
+        };
+        let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / count as f64;
+        Some(Stats { count, mean, median, min: values[0], max: values[count - 1], std_dev: variance.sqrt() })
+    }
+}
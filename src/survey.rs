@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use rand::{seq, Rng};
+
+use bag::Bag;
+
+impl<T> Bag<T> {
+
+    /// Predicts probability of criteria being met for a systematic sample: every `step`th item of the
+    /// bag, starting from a random one of the first `step`.
+    ///
+    /// Unlike a simple random sample this depends on the order of the bag, which is the point when
+    /// comparing survey designs: a list with a period matching `step` gives very different samples.
+    /// Samples have `len / step` items, or one more depending on the start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` is zero.
+    ///
+    /// # Examples
+    ///
+    /// Surveying every 4th house on a street where every 4th house is on a corner:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let houses = Bag::from_range(0, 100);
+    /// let corners = houses.systematic_sample(4, |s| s.iter().all(|h| *h % 4 == 0));
+    /// // Simple random samples would practically never be only corner houses
+    /// assert!(0.24 < corners && corners < 0.26);
+    /// ```
+    pub fn systematic_sample<F>(&self, step: usize, f: F) -> f64 where
+        F: Fn(Vec<&T>) -> bool {
+        assert!(step > 0, "step must be positive");
+        self.settings().run(|rng| {
+            let start = rng.gen_range(0, step);
+            f(self.items.iter().skip(start).step_by(step).collect())
+        }).probability()
+    }
+
+    /// Predicts probability of criteria being met for a cluster sample: the items are grouped into
+    /// clusters by `cluster_fn`, `clusters` of them are picked at random, and the sample is every item
+    /// of the picked clusters.
+    ///
+    /// Items of a cluster keep their order in the bag, and clusters are in the order they were picked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if asked to pick more clusters than there are.
+    ///
+    /// # Examples
+    ///
+    /// Surveying 2 of 5 classrooms of 20 students, when one classroom is all honor students:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let students = Bag::from_range(0, 100);
+    /// let honors = students.cluster_sample(|s| s / 20, 2, |s| s.iter().any(|s| **s < 20));
+    /// // 1 - (4 / 5) * (3 / 4)
+    /// assert!(0.39 < honors && honors < 0.41);
+    /// ```
+    pub fn cluster_sample<K, C, F>(&self, cluster_fn: C, clusters: usize, f: F) -> f64 where
+        K: Eq + Hash,
+        C: Fn(&T) -> K,
+        F: Fn(Vec<&T>) -> bool {
+        let mut positions = HashMap::new();
+        let mut members: Vec<Vec<usize>> = Vec::new();
+        for (i, item) in self.items.iter().enumerate() {
+            let position = *positions.entry(cluster_fn(item)).or_insert(members.len());
+            if position == members.len() {
+                members.push(Vec::new());
+            }
+            members[position].push(i);
+        }
+        assert!(clusters <= members.len(), "can't pick {} clusters out of {}", clusters, members.len());
+        self.settings().run(|rng| {
+            let picked = seq::sample_indices(rng, members.len(), clusters);
+            f(picked.iter().flat_map(|&c| members[c].iter().map(|&i| &self.items[i])).collect())
+        }).probability()
+    }
+}
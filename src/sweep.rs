@@ -0,0 +1,151 @@
+use rand::{seq, Rng};
+
+use bag::Bag;
+use engine::Settings;
+use result::SimulationResult;
+
+/// Sample sizes tried by each round of [`Bag::picks_needed`](struct.Bag.html#method.picks_needed).
+const PROBES: usize = 8;
+
+/// The first rounds of `picks_needed` run this fraction of the budget...
+const COARSE_FRACTION: u64 = 16;
+
+/// ...but at least this many simulations.
+const COARSE_MIN_SIMS: u64 = 500;
+
+/// Coarse rounds quadruple their simulations at most this many times before the search falls back
+/// to the whole budget, which a probability exactly on the target would otherwise never reach.
+const MAX_REFINEMENTS: u32 = 4;
+
+impl<T> Bag<T> {
+
+    /// Predicts the probability of criteria being met for each of several sample sizes.
+    ///
+    /// Every simulation draws the largest requested sample once, in random order, and the criteria
+    /// is evaluated on its first `k` items for each sample size `k`, so the sweep costs about as much
+    /// as a single [`sample`](#method.sample) of the largest size. The results come back as
+    /// `(sample_size, probability)` pairs in the order they were requested.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any sample size is larger than the number of items in the bag.
+    ///
+    /// # Examples
+    ///
+    /// How many picks from 1 - 10 are needed for a 55% chance of getting a 1?
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let sweep = my_bag.sample_sweep(1..=10, |values| values.contains(&&1));
+    /// let (needed, _) = sweep.into_iter().find(|&(_, p)| p >= 0.55).unwrap();
+    /// assert_eq!(needed, 6);
+    /// ```
+    pub fn sample_sweep<I, F>(&self, sample_sizes: I, f: F) -> Vec<(usize, f64)> where
+        I: IntoIterator<Item = usize>,
+        F: Fn(Vec<&T>) -> bool {
+        let sample_sizes: Vec<usize> = sample_sizes.into_iter().collect();
+        let (counts, trials) = sweep_counts(&self.settings(), &self.items, &sample_sizes, &f);
+        counts.into_iter()
+            .zip(sample_sizes)
+            .map(|(count, k)| (k, count as f64 / trials as f64))
+            .collect()
+    }
+
+    /// Finds the smallest sample size whose probability of meeting the criteria is at least
+    /// `target_probability`, or `None` if even a sample of every item falls short.
+    ///
+    /// The search assumes bigger samples never make the criteria less likely. Each round sweeps a
+    /// few sample sizes spread over the ones still in the running, as with
+    /// [`sample_sweep`](#method.sample_sweep). Early rounds run a fraction of the budget and only rule
+    /// out sample sizes whose confidence interval is clear of the target, with more simulations
+    /// whenever a round can't rule any out, up to a few times, and the last rounds run the whole
+    /// budget. Sample sizes
+    /// whose probability is very close to the target may come out one either way.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target_probability` isn't between 0 and 1.
+    ///
+    /// # Examples
+    ///
+    /// How many picks from 1 - 10 are needed for a 55% chance of getting a 1?
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// assert_eq!(my_bag.picks_needed(0.55, |values| values.contains(&&1)), Some(6));
+    /// assert_eq!(my_bag.picks_needed(0.55, |values| values.contains(&&11)), None);
+    /// ```
+    pub fn picks_needed<F>(&self, target_probability: f64, f: F) -> Option<usize> where
+        F: Fn(Vec<&T>) -> bool {
+        assert!((0.0..=1.0).contains(&target_probability), "target_probability must be between 0 and 1, got {}", target_probability);
+        let settings = self.settings();
+        let full = settings.budget().without_successes().trials();
+        // `None` runs the whole budget
+        let mut coarse = Some(full.map_or(COARSE_MIN_SIMS, |trials| (trials / COARSE_FRACTION).max(COARSE_MIN_SIMS)))
+            .filter(|&trials| full.map_or(true, |full| trials < full));
+        let mut refinements = 0;
+        let mut seeds = settings.rng();
+        // The answer is in `low..=high`, where `high` past the last item means there is none
+        let (mut low, mut high) = (0, self.items.len() + 1);
+        while low < high {
+            let probes: Vec<usize> = if high - low <= PROBES {
+                (low..high).collect()
+            } else {
+                (0..PROBES).map(|i| low + i * (high - low) / PROBES).collect()
+            };
+            let mut round = Settings { seed: Some(seeds.gen()), ..settings.clone() };
+            if let Some(trials) = coarse {
+                round.set_max_sims(trials.min(u32::MAX as u64) as u32);
+            }
+            let (counts, trials) = sweep_counts(&round, &self.items, &probes, &f);
+            let meets: Vec<Option<bool>> = counts.into_iter().map(|count| {
+                let result = SimulationResult::new(count, trials);
+                if coarse.is_none() {
+                    return Some(result.probability() >= target_probability);
+                }
+                let (ci_low, ci_high) = result.confidence_interval();
+                if ci_low >= target_probability {
+                    Some(true)
+                } else if ci_high < target_probability {
+                    Some(false)
+                } else {
+                    None
+                }
+            }).collect();
+            let new_high = probes.iter().zip(&meets).find(|&(_, m)| *m == Some(true)).map_or(high, |(&k, _)| k);
+            let new_low = probes.iter().zip(&meets).rev().find(|&(&k, m)| k < new_high && *m == Some(false))
+                .map_or(low, |(&k, _)| k + 1);
+            if (new_low, new_high) == (low, high) {
+                refinements += 1;
+                coarse = coarse.map(|trials| trials * 4)
+                    .filter(|&trials| refinements <= MAX_REFINEMENTS && full.map_or(true, |full| trials < full));
+            }
+            low = new_low;
+            high = new_high;
+        }
+        if high <= self.items.len() { Some(high) } else { None }
+    }
+}
+
+/// Simulates until the budget of `settings` is spent, counting for each of `sample_sizes` how often
+/// its prefix of a random draw met the criteria. Returns the counts and the number of trials.
+pub(crate) fn sweep_counts<T, F>(settings: &Settings, items: &[T], sample_sizes: &[usize], f: &F) -> (Vec<u64>, u64) where
+    F: Fn(Vec<&T>) -> bool {
+    let largest = sample_sizes.iter().cloned().max().unwrap_or(0);
+    assert!(largest <= items.len(), "sample_size {} is larger than the population of {}", largest, items.len());
+    let mut counts = vec![0u64; sample_sizes.len()];
+    let trials = settings.repeat(|rng| {
+        // The indices come back in random order, so every prefix is a uniformly random sample
+        let indices = seq::sample_indices(rng, items.len(), largest);
+        for (count, &k) in counts.iter_mut().zip(sample_sizes) {
+            if f(indices[..k].iter().map(|&i| &items[i]).collect()) {
+                *count += 1;
+            }
+        }
+    });
+    (counts, trials)
+}
@@ -0,0 +1,88 @@
+use bag::Bag;
+
+/// A builder for synthetic populations with known category proportions, such as survey respondents.
+///
+/// Every category gets its share of the population (rounded so the shares add up to exactly the
+/// requested size), and `build` turns each member of a category into an item.
+///
+/// # Examples
+///
+/// ```
+/// use mendel::Synth;
+///
+/// let respondents = Synth::new(1_000)
+///     .category("female", 52.0)
+///     .category("male", 48.0)
+///     .cross(Synth::new(0).category("18-34", 0.3).category("35-64", 0.5).category("65+", 0.2))
+///     .build(|&(sex, age)| format!("{} {}", sex, age));
+/// assert_eq!(respondents.items.len(), 1_000);
+/// assert_eq!(respondents.count_where(|r| r == "female 65+"), 104);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Synth<C> {
+    pub size: usize,
+    pub categories: Vec<(C, f64)>
+}
+
+impl<C> Synth<C> {
+
+    /// Starts a population of `size` members with no categories.
+    pub fn new(size: usize) -> Self {
+        Synth { size, categories: Vec::new() }
+    }
+
+    /// Adds a category with the given share of the population.
+    ///
+    /// Proportions don't need to add up to 1, they are relative to each other, so percentages work too.
+    pub fn category(mut self, category: C, proportion: f64) -> Self {
+        assert!(proportion >= 0.0 && proportion.is_finite(), "proportions must be finite and non-negative, got {}", proportion);
+        self.categories.push((category, proportion));
+        self
+    }
+
+    /// Combines the categories with those of `other`, assuming the two are independent, so every pair
+    /// of categories gets the product of their proportions. The size of `other` is ignored.
+    pub fn cross<D>(self, other: Synth<D>) -> Synth<(C, D)> where
+        C: Clone,
+        D: Clone {
+        let mut categories = Vec::new();
+        for (c, p) in self.categories {
+            for (d, q) in &other.categories {
+                categories.push(((c.clone(), d.clone()), p * q));
+            }
+        }
+        Synth { size: self.size, categories }
+    }
+
+    /// The number of members of each category, in the order the categories were added.
+    ///
+    /// Shares are rounded with the largest remainder method, so they always add up to `size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no categories or every proportion is zero.
+    pub fn counts(&self) -> Vec<usize> {
+        let total: f64 = self.categories.iter().map(|&(_, p)| p).sum();
+        assert!(total > 0.0, "at least one proportion must be positive");
+        let exact: Vec<f64> = self.categories.iter().map(|&(_, p)| p / total * self.size as f64).collect();
+        let mut counts: Vec<usize> = exact.iter().map(|e| e.floor() as usize).collect();
+        let assigned: usize = counts.iter().sum();
+        let mut by_remainder: Vec<usize> = (0..counts.len()).collect();
+        // Stable, so ties go to the categories added first
+        by_remainder.sort_by(|&a, &b| (exact[b] - exact[b].floor()).partial_cmp(&(exact[a] - exact[a].floor())).unwrap());
+        for &i in by_remainder.iter().take(self.size.saturating_sub(assigned)) {
+            counts[i] += 1;
+        }
+        counts
+    }
+
+    /// Generates the population, calling `factory` once for every member with the member's category.
+    pub fn build<T, F>(&self, factory: F) -> Bag<T> where
+        F: Fn(&C) -> T {
+        let mut items = Vec::with_capacity(self.size);
+        for ((category, _), count) in self.categories.iter().zip(self.counts()) {
+            items.extend((0..count).map(|_| factory(category)));
+        }
+        Bag::from_vec(items)
+    }
+}
@@ -0,0 +1,69 @@
+//! Helpers for testing code against simulated probabilities.
+//!
+//! Simulated probabilities are never exact, so tests compare them with a tolerance using
+//! [`assert_prob_close!`](../macro.assert_prob_close.html), and can fix the seed of every bag in a
+//! block with [`with_fixed_seed`](fn.with_fixed_seed.html) so they never flake.
+
+use rng;
+
+/// Asserts a simulated probability is within `tolerance` of the expected one.
+///
+/// # Panics
+///
+/// Panics with both probabilities if they are further apart than `tolerance`.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate mendel;
+///
+/// use mendel::Bag;
+///
+/// # fn main() {
+/// let dice = Bag::from_range(1, 7);
+/// assert_prob_close!(dice.one(|d| *d > 4), 1.0 / 3.0, 0.01);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_prob_close {
+    ($actual:expr, $expected:expr, $tolerance:expr) => {{
+        let (actual, expected, tolerance): (f64, f64, f64) = ($actual, $expected, $tolerance);
+        assert!((actual - expected).abs() <= tolerance,
+                "probability {} is not within {} of the expected {}", actual, tolerance, expected);
+    }};
+}
+
+/// Restores the previous fixed seed when dropped, even if the test body panics.
+struct Restore(Option<u64>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        rng::set_fixed_seed(self.0);
+    }
+}
+
+/// Runs `f` with every unseeded simulation on the current thread seeded with `seed`, so its
+/// results are the same on every run.
+///
+/// Bags with their own seed (see [`Bag::set_seed`](../struct.Bag.html#method.set_seed)) keep using
+/// it. Simulations on other threads, such as the streaming ones, aren't affected, but the workers
+/// of parallel simulations are seeded from the calling thread and are.
+///
+/// # Examples
+///
+/// ```
+/// use mendel::Bag;
+/// use mendel::testing::with_fixed_seed;
+///
+/// let dice = Bag::from_range(1, 7);
+/// let first = with_fixed_seed(7, || dice.one(|d| *d == 6));
+/// let second = with_fixed_seed(7, || dice.one(|d| *d == 6));
+/// assert_eq!(first, second);
+/// ```
+pub fn with_fixed_seed<F, R>(seed: u64, f: F) -> R where
+    F: FnOnce() -> R {
+    let _restore = Restore(rng::fixed_seed());
+    rng::set_fixed_seed(Some(seed));
+    f()
+}
@@ -0,0 +1,72 @@
+use std::io::{self, Write};
+
+use rand::seq;
+
+use bag::Bag;
+use result::SimulationResult;
+
+/// How [`Bag::sample_traced`](struct.Bag.html#method.sample_traced) writes each trial.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// A `trial,indices,outcome` header, then one row per trial with the indices separated by `;`,
+    /// e.g. `0,3;7,true`.
+    Csv,
+    /// One JSON object per line, e.g. `{"trial":0,"indices":[3,7],"outcome":true}`.
+    JsonLines
+}
+
+fn write_trace<W: Write>(writer: &mut W, format: TraceFormat, trial: u64, indices: &[usize], outcome: bool) -> io::Result<()> {
+    let joined = |separator: &str| indices.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(separator);
+    match format {
+        TraceFormat::Csv => writeln!(writer, "{},{},{}", trial, joined(";"), outcome),
+        TraceFormat::JsonLines => writeln!(writer, "{{\"trial\":{},\"indices\":[{}],\"outcome\":{}}}", trial, joined(","), outcome)
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Like [`sample_detailed`](#method.sample_detailed), but records every trial's drawn item indices
+    /// and outcome to `writer`, so an estimate can be audited and replayed.
+    ///
+    /// The items are drawn exactly as `sample` draws them, so a seeded bag traces the very trials
+    /// behind its `sample` results, as long as those run on a single thread.
+    ///
+    /// Stops writing at the first error and returns it once the run ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, TraceFormat};
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_max_sims(3);
+    /// let mut trace = Vec::new();
+    /// let result = my_bag.sample_traced(2, |values| values.contains(&&1), &mut trace, TraceFormat::Csv).unwrap();
+    /// let trace = String::from_utf8(trace).unwrap();
+    /// assert!(trace.starts_with("trial,indices,outcome\n0,"));
+    /// assert_eq!(trace.lines().count(), 4);
+    /// assert_eq!(result.trials, 3);
+    /// ```
+    pub fn sample_traced<F, W>(&self, sample_size: usize, f: F, mut writer: W, format: TraceFormat) -> io::Result<SimulationResult> where
+        F: Fn(Vec<&T>) -> bool,
+        W: Write {
+        let mut written = if format == TraceFormat::Csv { writeln!(writer, "trial,indices,outcome") } else { Ok(()) };
+        let mut trial = 0;
+        let result = self.settings().run(|rng| {
+            // Sampling positions the same way as the items keeps traces in step with `sample`
+            let indices = match seq::sample_iter(rng, 0..self.items.len(), sample_size) {
+                Ok(indices) => indices,
+                Err(_) => panic!("sample_size {} is larger than the population of {}", sample_size, self.items.len())
+            };
+            let outcome = f(indices.iter().map(|&i| &self.items[i]).collect());
+            if written.is_ok() {
+                written = write_trace(&mut writer, format, trial, &indices, outcome);
+            }
+            trial += 1;
+            outcome
+        });
+        written?;
+        writer.flush()?;
+        Ok(result)
+    }
+}
@@ -0,0 +1,180 @@
+//! Exact answers for count-based events, to check simulated probabilities against.
+//!
+//! Useful as a sanity check of a simulation setup, and as test infrastructure: see
+//! [`Bag::validate_count`](../struct.Bag.html#method.validate_count).
+
+use bag::{pick_index, Bag};
+use math::{binomial_pmf, chi_square_p_value, hypergeometric_pmf};
+use result::SimulationResult;
+
+/// An event on the number of matching items in a sample.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CountEvent {
+    Exactly(usize),
+    AtLeast(usize),
+    AtMost(usize)
+}
+
+impl CountEvent {
+
+    /// Whether `count` matching items meet the event.
+    pub fn is_met(&self, count: usize) -> bool {
+        match *self {
+            CountEvent::Exactly(n) => count == n,
+            CountEvent::AtLeast(n) => count >= n,
+            CountEvent::AtMost(n) => count <= n
+        }
+    }
+
+    /// Sums `pmf` over the counts in `0..=max` that meet the event.
+    fn total<F: Fn(u64) -> f64>(&self, max: u64, pmf: F) -> f64 {
+        let p: f64 = (0..max + 1).filter(|&x| self.is_met(x as usize)).map(pmf).sum();
+        p.min(1.0)
+    }
+}
+
+/// The exact probability of `event` for the number of successes among `draws` items drawn without
+/// replacement from `population` items, `successes` of which are successes.
+///
+/// # Examples
+///
+/// ```
+/// use mendel::validate::{hypergeometric, CountEvent};
+///
+/// // Both aces when dealt 2 of 4 cards, 2 of which are aces
+/// assert!((hypergeometric(4, 2, 2, CountEvent::Exactly(2)) - 1.0 / 6.0).abs() < 1e-12);
+/// ```
+pub fn hypergeometric(population: u64, successes: u64, draws: u64, event: CountEvent) -> f64 {
+    assert!(successes <= population, "successes {} is larger than the population of {}", successes, population);
+    assert!(draws <= population, "draws {} is larger than the population of {}", draws, population);
+    event.total(draws, |x| hypergeometric_pmf(population, successes, draws, x))
+}
+
+/// The exact probability of `event` for the number of successes in `trials` independent trials that
+/// each succeed with probability `p`, such as drawing with replacement.
+///
+/// # Examples
+///
+/// ```
+/// use mendel::validate::{binomial, CountEvent};
+///
+/// // At least one 6 in 4 rolls of a die
+/// let p = binomial(4, 1.0 / 6.0, CountEvent::AtLeast(1));
+/// assert!((p - (1.0 - (5.0f64 / 6.0).powi(4))).abs() < 1e-12);
+/// ```
+pub fn binomial(trials: u64, p: f64, event: CountEvent) -> f64 {
+    assert!((0.0..=1.0).contains(&p), "p must be a probability, got {}", p);
+    event.total(trials, |x| binomial_pmf(trials, p, x))
+}
+
+/// A simulated probability compared with the exact answer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Validation {
+    pub simulated: SimulationResult,
+    pub exact: f64,
+    /// `simulated - exact`.
+    pub absolute_error: f64,
+    /// The absolute error relative to the exact answer, infinite if the exact answer is 0 but the simulation disagrees.
+    pub relative_error: f64
+}
+
+impl Validation {
+
+    /// Compares a simulation result with the exact answer.
+    pub fn new(simulated: SimulationResult, exact: f64) -> Self {
+        let absolute_error = simulated.probability() - exact;
+        let relative_error = if exact != 0.0 {
+            absolute_error.abs() / exact
+        } else if absolute_error == 0.0 {
+            0.0
+        } else {
+            f64::INFINITY
+        };
+        Validation { simulated, exact, absolute_error, relative_error }
+    }
+
+    /// Whether the exact answer lies inside the simulation's 95% confidence interval, which it
+    /// should about 19 times out of 20.
+    pub fn is_consistent(&self) -> bool {
+        let (low, high) = self.simulated.confidence_interval();
+        low <= self.exact && self.exact <= high
+    }
+}
+
+/// Smallest p-value a self test accepts as uniform.
+const SELF_TEST_ALPHA: f64 = 0.001;
+
+/// The outcome of a chi-square test of how uniformly a bag picks its items, see
+/// [`Bag::self_test`](../struct.Bag.html#method.self_test).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelfTest {
+    pub trials: u32,
+    pub chi_square: f64,
+    pub degrees_of_freedom: usize,
+    /// Probability of a chi-square statistic at least this large if picking is uniform.
+    pub p_value: f64,
+    /// Whether the p-value is at least 0.001, so a healthy setup fails about once in a thousand tests.
+    pub is_uniform: bool
+}
+
+impl<T> Bag<T> {
+
+    /// Picks `trials` random item positions with the bag's configured random number generator, the
+    /// way [`one`](#method.one) does, and checks with a chi-square test that every position is picked
+    /// equally often.
+    ///
+    /// For a meaningful test, `trials` should be at least 5 times the number of items.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bag is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::{Bag, RngKind};
+    ///
+    /// let mut my_bag = Bag::from_range(1, 11);
+    /// my_bag.set_rng_kind(RngKind::Pcg);
+    /// my_bag.set_seed(3);
+    /// assert!(my_bag.self_test(10_000).is_uniform);
+    /// ```
+    pub fn self_test(&self, trials: u32) -> SelfTest {
+        let len = self.items.len();
+        assert!(len > 0, "can't test an empty bag");
+        let mut counts = vec![0u64; len];
+        let mut rng = self.settings().rng();
+        for _ in 0..trials {
+            counts[pick_index(&mut rng, len)] += 1;
+        }
+        let expected = trials as f64 / len as f64;
+        let chi_square: f64 = counts.iter().map(|&c| (c as f64 - expected) * (c as f64 - expected) / expected).sum();
+        let degrees_of_freedom = len - 1;
+        let p_value = if degrees_of_freedom == 0 { 1.0 } else { chi_square_p_value(chi_square, degrees_of_freedom as f64) };
+        SelfTest { trials, chi_square, degrees_of_freedom, p_value, is_uniform: p_value >= SELF_TEST_ALPHA }
+    }
+
+    /// Simulates the odds of `event` for the number of items meeting `f` among `sample_size` random
+    /// items, and compares it with the exact hypergeometric answer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    /// use mendel::validate::CountEvent;
+    ///
+    /// let mut numbers = Bag::from_range(1, 11);
+    /// numbers.set_max_sims(20_000);
+    /// let validation = numbers.validate_count(3, |v| v % 2 == 0, CountEvent::AtLeast(2));
+    /// assert!((validation.exact - 0.5).abs() < 1e-12);
+    /// assert!(validation.absolute_error.abs() < 0.03);
+    /// ```
+    pub fn validate_count<F>(&self, sample_size: usize, f: F, event: CountEvent) -> Validation where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync {
+        let matching = self.count_where(&f);
+        let exact = hypergeometric(self.items.len() as u64, matching as u64, sample_size as u64, event);
+        let simulated = self.sample_detailed(sample_size, |values| event.is_met(values.into_iter().filter(|v| f(v)).count()));
+        Validation::new(simulated, exact)
+    }
+}
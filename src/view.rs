@@ -0,0 +1,81 @@
+use std::ops::{Index, Range};
+use std::slice;
+
+use bag::{draw_sample, pick_one};
+use engine::Settings;
+
+/// A borrowed, contiguous range of a `Bag`'s items, see [`Bag::view`](struct.Bag.html#method.view).
+pub struct BagView<'a, T: 'a> {
+    pub items: &'a [T],
+    settings: Settings
+}
+
+impl<'a, T> BagView<'a, T> {
+
+    pub(crate) fn new(items: &'a [T], settings: Settings) -> Self {
+        BagView { items, settings }
+    }
+
+    /// Returns the number of items in the view.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the view holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns an iterator over the items in the view.
+    pub fn iter(&self) -> slice::Iter<'a, T> {
+        self.items.iter()
+    }
+
+    /// Predicts probability of criteria being met for the first random item grabbed from the view.
+    ///
+    /// See [`Bag::one`](struct.Bag.html#method.one).
+    pub fn one<F>(&self, f: F) -> f64 where
+        T: Sync,
+        F: Fn(&T) -> bool + Sync {
+        self.settings.run_parallel(|rng| f(pick_one(rng, self.items))).probability()
+    }
+
+    /// Predicts probability of criteria being met for the first `sample_size` random items grabbed from the view.
+    ///
+    /// See [`Bag::sample`](struct.Bag.html#method.sample).
+    pub fn sample<F>(&self, sample_size: usize, f: F) -> f64 where
+        T: Sync,
+        F: Fn(Vec<&T>) -> bool + Sync {
+        self.settings.run_parallel(|rng| f(draw_sample(rng, self.items, sample_size))).probability()
+    }
+
+    /// Set the view's maximum amount of simulations to run when generating probabilities.
+    pub fn set_max_sims(&mut self, max_sims: u32) {
+        self.settings.set_max_sims(max_sims);
+    }
+}
+
+impl<'a, T> IntoIterator for &BagView<'a, T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.items.iter()
+    }
+}
+
+impl<'a, T> Index<usize> for BagView<'a, T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.items[index]
+    }
+}
+
+impl<'a, T> Index<Range<usize>> for BagView<'a, T> {
+    type Output = [T];
+
+    fn index(&self, range: Range<usize>) -> &[T] {
+        &self.items[range]
+    }
+}
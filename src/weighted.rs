@@ -0,0 +1,230 @@
+use rand::Rng;
+
+use bag::Bag;
+
+/// Running totals of item weights, for picking indices proportionally to their weight.
+pub(crate) struct CumulativeWeights {
+    totals: Vec<f64>
+}
+
+impl CumulativeWeights {
+
+    /// # Panics
+    ///
+    /// Panics if a weight is negative or not finite, or if every weight is zero.
+    pub(crate) fn new<I: IntoIterator<Item = f64>>(weights: I) -> Self {
+        let mut running = 0.0;
+        let totals: Vec<f64> = weights.into_iter().map(|w| {
+            assert!(w >= 0.0 && w.is_finite(), "weights must be finite and non-negative, got {}", w);
+            running += w;
+            running
+        }).collect();
+        assert!(running > 0.0, "at least one weight must be positive");
+        CumulativeWeights { totals }
+    }
+
+    /// Picks an index with probability proportional to its weight.
+    pub(crate) fn pick<R: Rng>(&self, rng: &mut R) -> usize {
+        let total = self.totals[self.totals.len() - 1];
+        let target = rng.gen::<f64>() * total;
+        // The first index whose running total passes the target, zero-weight items are never picked
+        match self.totals.binary_search_by(|t| t.partial_cmp(&target).unwrap()) {
+            Ok(idx) => self.next_positive(idx + 1),
+            Err(idx) => idx
+        }
+    }
+
+    /// The first index at or after `idx` with a positive weight.
+    fn next_positive(&self, idx: usize) -> usize {
+        let landed = self.totals[idx - 1];
+        idx + self.totals[idx..].iter().take_while(|t| **t == landed).count()
+    }
+}
+
+/// A Walker alias table, for picking indices proportionally to their weight in constant time.
+pub(crate) struct AliasTable {
+    /// The chance of keeping each index when it's landed on, rather than taking its alias.
+    keep: Vec<f64>,
+    alias: Vec<usize>
+}
+
+impl AliasTable {
+
+    /// # Panics
+    ///
+    /// Panics if a weight is negative or not finite, or if every weight is zero.
+    pub(crate) fn new(weights: &[f64]) -> Self {
+        assert!(weights.iter().all(|w| *w >= 0.0 && w.is_finite()), "weights must be finite and non-negative");
+        let total: f64 = weights.iter().sum();
+        assert!(total > 0.0, "at least one weight must be positive");
+        let len = weights.len();
+        // Each index gets a slot of height 1, scaled weights below it are topped up by an alias above it
+        let mut keep: Vec<f64> = weights.iter().map(|w| w * len as f64 / total).collect();
+        let mut alias: Vec<usize> = (0..len).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) = (0..len).partition(|&i| keep[i] < 1.0);
+        while let (Some(&s), Some(&l)) = (small.last(), large.last()) {
+            small.pop();
+            alias[s] = l;
+            keep[l] -= 1.0 - keep[s];
+            if keep[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // What's left is only off from 1 by rounding, unless it never had any weight
+        let positive = weights.iter().position(|w| *w > 0.0).unwrap();
+        for i in small.into_iter().chain(large) {
+            if weights[i] > 0.0 {
+                keep[i] = 1.0;
+            } else {
+                keep[i] = 0.0;
+                alias[i] = positive;
+            }
+        }
+        AliasTable { keep, alias }
+    }
+
+    /// Picks an index with probability proportional to its weight.
+    pub(crate) fn pick<R: Rng>(&self, rng: &mut R) -> usize {
+        let i = rng.gen_range(0, self.keep.len());
+        if rng.gen::<f64>() < self.keep[i] { i } else { self.alias[i] }
+    }
+}
+
+/// How likely each position of a bag is to be grabbed, see
+/// [`Bag::one_by_position`](struct.Bag.html#method.one_by_position).
+///
+/// Position 0 is the first item of the bag. Useful for popularity-skewed selection, such as
+/// users mostly picking results near the top of a list.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PositionDistribution {
+    /// Every position is equally likely, as with [`Bag::one`](struct.Bag.html#method.one).
+    Uniform,
+    /// Zipf's law: position `i` is weighted by `1 / (i + 1)^exponent`.
+    Zipf { exponent: f64 },
+    /// Each position is passed over with probability `1 - p`, so position `i` is weighted by
+    /// `p * (1 - p)^i`. Truncated to the length of the bag.
+    Geometric { p: f64 },
+    /// One weight per position, which needs to match the length of the bag.
+    Weights(Vec<f64>)
+}
+
+impl PositionDistribution {
+
+    /// The weight of every position of a bag of `len` items.
+    fn weights(&self, len: usize) -> Vec<f64> {
+        match *self {
+            PositionDistribution::Uniform => vec![1.0; len],
+            PositionDistribution::Zipf { exponent } => (0..len).map(|i| (i as f64 + 1.0).powf(-exponent)).collect(),
+            PositionDistribution::Geometric { p } => {
+                assert!(p > 0.0 && p <= 1.0, "p must be in (0, 1], got {}", p);
+                (0..len).map(|i| p * (1.0 - p).powi(i as i32)).collect()
+            }
+            PositionDistribution::Weights(ref weights) => {
+                assert_eq!(weights.len(), len, "expected one weight per position");
+                weights.clone()
+            }
+        }
+    }
+}
+
+impl<T> Bag<T> {
+
+    /// Predicts probability of criteria being met for a random item grabbed from the bag, where each
+    /// item's chance of being grabbed is proportional to its weight.
+    ///
+    /// This answers probability-proportional-to-size questions without expanding the population,
+    /// such as the region of a random resident when the bag holds regions and their populations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a weight is negative or not finite, or if every weight is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let regions = Bag::from_vec(vec![("Pittsburgh", 300), ("Denver", 700)]);
+    /// let odds_of_denver = regions.one_weighted_by(|r| r.1 as f64, |r| r.0 == "Denver");
+    /// assert!(0.69 < odds_of_denver && odds_of_denver < 0.71);
+    /// ```
+    pub fn one_weighted_by<W, F>(&self, weight_fn: W, f: F) -> f64 where
+        W: Fn(&T) -> f64,
+        F: Fn(&T) -> bool {
+        let weights = CumulativeWeights::new(self.items.iter().map(weight_fn));
+        self.settings().run(|rng| f(&self.items[weights.pick(rng)])).probability()
+    }
+
+    /// Predicts probability of criteria being met for a sample of `sample_size` items grabbed without
+    /// replacement, where each draw picks among the remaining items proportionally to their weight.
+    ///
+    /// Items of the sample are in the order they were drawn. Every trial gives each item a random
+    /// key of `ln(u) / weight` and keeps the `sample_size` largest, the exponential keys of
+    /// Efraimidis and Spirakis, which is equivalent to drawing one weighted item after another.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a weight is negative or not finite, or if fewer than `sample_size` items have a
+    /// positive weight.
+    ///
+    /// # Examples
+    ///
+    /// Odds the big spender wins one of 2 prizes, with an entry for every dollar spent:
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let customers = Bag::from_vec(vec![("Ann", 10), ("Bob", 10), ("Cal", 80)]);
+    /// let odds_of_cal = customers.sample_weighted_by(2, |c| c.1 as f64, |s| s.iter().any(|c| c.0 == "Cal"));
+    /// assert!(0.96 < odds_of_cal && odds_of_cal < 0.99);
+    /// ```
+    pub fn sample_weighted_by<W, F>(&self, sample_size: usize, weight_fn: W, f: F) -> f64 where
+        W: Fn(&T) -> f64,
+        F: Fn(Vec<&T>) -> bool {
+        let weights: Vec<f64> = self.items.iter().map(weight_fn).collect();
+        let positive = weights.iter().filter(|w| {
+            assert!(**w >= 0.0 && w.is_finite(), "weights must be finite and non-negative, got {}", w);
+            **w > 0.0
+        }).count();
+        assert!(sample_size <= positive, "sample_size {} is larger than the {} items with a positive weight", sample_size, positive);
+        let by_key = |a: &(f64, usize), b: &(f64, usize)| b.0.partial_cmp(&a.0).unwrap();
+        self.settings().run(|rng| {
+            if sample_size == 0 {
+                return f(Vec::new());
+            }
+            // Zero weights are left out, so they can never be drawn
+            let mut keys: Vec<(f64, usize)> = weights.iter().enumerate().filter(|&(_, w)| *w > 0.0)
+                .map(|(i, w)| (rng.gen::<f64>().ln() / w, i)).collect();
+            keys.select_nth_unstable_by(sample_size - 1, by_key);
+            keys.truncate(sample_size);
+            keys.sort_unstable_by(by_key);
+            f(keys.iter().map(|&(_, i)| &self.items[i]).collect())
+        }).probability()
+    }
+
+    /// Predicts probability of criteria being met for an item grabbed from the bag, where the
+    /// position grabbed follows `distribution` rather than being uniformly random.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the distribution's weights are invalid, see
+    /// [`one_weighted_by`](#method.one_weighted_by), or if `Weights` doesn't match the bag's length.
+    ///
+    /// # Examples
+    ///
+    /// Odds a user picks one of the top 3 of 10 results, when picks follow Zipf's law:
+    ///
+    /// ```
+    /// use mendel::{Bag, PositionDistribution};
+    ///
+    /// let results = Bag::from_range(1, 11);
+    /// let top_three = results.one_by_position(&PositionDistribution::Zipf { exponent: 1.0 }, |rank| *rank <= 3);
+    /// assert!(0.60 < top_three && top_three < 0.65);
+    /// ```
+    pub fn one_by_position<F>(&self, distribution: &PositionDistribution, f: F) -> f64 where
+        F: Fn(&T) -> bool {
+        let weights = CumulativeWeights::new(distribution.weights(self.items.len()));
+        self.settings().run(|rng| f(&self.items[weights.pick(rng)])).probability()
+    }
+}
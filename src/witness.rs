@@ -0,0 +1,62 @@
+use bag::{draw_sample, Bag};
+use result::SimulationResult;
+
+/// A simulation result with example samples that did and didn't meet the criteria, see
+/// [`Bag::sample_with_witnesses`](struct.Bag.html#method.sample_with_witnesses).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Witnessed<'a, T: 'a> {
+    pub result: SimulationResult,
+    /// The first samples that met the criteria, in the order they were simulated.
+    pub successes: Vec<Vec<&'a T>>,
+    /// The first samples that didn't meet the criteria, in the order they were simulated.
+    pub failures: Vec<Vec<&'a T>>
+}
+
+impl<T> Bag<T> {
+
+    /// Like [`sample_detailed`](#method.sample_detailed), but also keeps up to `max_witnesses` of the
+    /// samples that met the criteria and up to `max_witnesses` that didn't, to see what the draws
+    /// behind a surprising estimate actually look like.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mendel::Bag;
+    ///
+    /// let my_bag = Bag::from_range(1, 11);
+    /// let witnessed = my_bag.sample_with_witnesses(2, 3, |values| values[0] + values[1] == 19);
+    /// assert_eq!(witnessed.successes.len(), 3);
+    /// for sample in &witnessed.successes {
+    ///     assert!(sample.contains(&&9) && sample.contains(&&10));
+    /// }
+    /// assert!(witnessed.result.probability() < 0.05);
+    /// ```
+    pub fn sample_with_witnesses<F>(&self, sample_size: usize, max_witnesses: usize, f: F) -> Witnessed<'_, T> where
+        F: Fn(Vec<&T>) -> bool {
+        let mut successes = Vec::new();
+        let mut failures = Vec::new();
+        let result = self.settings().run(|rng| {
+            let sample = draw_sample(rng, &self.items, sample_size);
+            let keep = if successes.len() < max_witnesses || failures.len() < max_witnesses {
+                Some(sample.clone())
+            } else {
+                None
+            };
+            let met = f(sample);
+            if let Some(sample) = keep {
+                let witnesses = if met { &mut successes } else { &mut failures };
+                if witnesses.len() < max_witnesses {
+                    witnesses.push(sample);
+                }
+            }
+            met
+        });
+        Witnessed { result, successes, failures }
+    }
+
+    /// Like [`sample_with_witnesses`](#method.sample_with_witnesses), for a single item as with [`one`](#method.one).
+    pub fn one_with_witnesses<F>(&self, max_witnesses: usize, f: F) -> Witnessed<'_, T> where
+        F: Fn(&T) -> bool {
+        self.sample_with_witnesses(1, max_witnesses, |values| f(values[0]))
+    }
+}